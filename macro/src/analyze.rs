@@ -1,13 +1,18 @@
 use std::collections::HashMap;
 
-use proc_macro_error::abort;
-use syn::parse::Parser;
+use proc_macro_error::{abort, abort_call_site};
+use quote::{format_ident, quote};
+use syn::parse::{ParseStream, Parser};
+use syn::visit::Visit;
 use syn::{
-    parse_quote, Attribute, AttributeArgs, ExprCall, Field, FnArg, Generics, Ident, ImplItem,
-    ImplItemMethod, ItemImpl, Lit, Meta, MetaList, NestedMeta, Pat, PatType, Path, Receiver, Type,
-    Visibility,
+    parse_quote, Attribute, AttributeArgs, Block, Expr, ExprCall, Field, FnArg, Generics, Ident,
+    ImplItem, ImplItemMethod, ItemImpl, Lit, Meta, MetaList, MetaNameValue, NestedMeta, Pat,
+    PatType, Path, Receiver, ReturnType, Token, Type, Visibility,
 };
 
+use crate::visitors::EventPatternVisitor;
+use crate::{ENTRY_COUNT_ARG, SHARED_STORAGE_IDENT, STATE_ARG};
+
 /// Model of the state machine.
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct Model {
@@ -21,13 +26,18 @@ pub struct Model {
     pub superstates: HashMap<Ident, Superstate>,
     /// The actions of the state machine.
     pub actions: HashMap<Ident, Action>,
+    /// Paths that the state and superstate handlers match the event against
+    /// (e.g. `Event::TimerElapsed`), only collected when checking event coverage.
+    pub event_patterns: Vec<Path>,
 }
 
 /// General information regarding the state machine.
 #[cfg_attr(test, derive(Debug, Eq, PartialEq))]
 pub struct StateMachine {
     /// The inital state of the state machine.
-    pub initial_state: ExprCall,
+    pub initial_state: Option<Expr>,
+    /// The superstate whose initial state is the initial state of the state machine.
+    pub initial_superstate: Option<Ident>,
     /// The type on which the state machine is implemented.
     pub shared_storage_type: Type,
     /// The path of the shared storage.
@@ -38,6 +48,12 @@ pub struct StateMachine {
     pub state_ident: Ident,
     /// Derives that will be applied on the state type.
     pub state_derives: Vec<Path>,
+    /// Whether handlers are dispatched through a table of function pointers.
+    pub jump_table: bool,
+    /// Optional integer representation of the state type.
+    pub state_repr: Option<Ident>,
+    /// Optional name of a trait with the constructors of the states.
+    pub state_constructor_trait: Option<Ident>,
     /// The name of the superstate type.
     pub superstate_ident: Ident,
     /// Derives that will be applied to the superstate type.
@@ -52,6 +68,29 @@ pub struct StateMachine {
     pub on_transition: Option<Path>,
     /// Optional `on_dispatch` callback.
     pub on_dispatch: Option<Path>,
+    /// Optional `on_snapshot` callback.
+    pub on_snapshot: Option<Path>,
+    /// Events that can be created from a raw tag, the tag is their index
+    /// (e.g. `Event::TimerElapsed`).
+    pub ffi_events: Vec<Expr>,
+    /// Optional state whose handler is called with events that reach the top of
+    /// the hierarchy unhandled (e.g. `unknown`).
+    pub catch_all: Option<Ident>,
+    /// Whether events that reach the top of the hierarchy unhandled must be
+    /// dealt with by the caller.
+    pub require_handled: bool,
+    /// Whether to check that every event variant is matched by a handler.
+    pub check_event_coverage: bool,
+    /// Whether the handlers receive the entry and exit of their state as a
+    /// `Lifecycle` event.
+    pub lifecycle_events: bool,
+    /// Optional type that the effects returned by the handlers are collected in.
+    pub effects: Option<Type>,
+    /// Whether the state enum gets a `const fn step` that computes the next state.
+    pub const_step: bool,
+    /// Whether a single async function may make the state machine async without
+    /// a warning.
+    pub allow_single_async: bool,
 }
 
 /// Information regarding a state.
@@ -59,14 +98,22 @@ pub struct StateMachine {
 pub struct State {
     /// Name of the state.
     pub handler_name: Ident,
+    /// Doc comments on the state handler.
+    pub docs: Vec<Attribute>,
     /// Optional superstate.
     pub superstate: Option<Ident>,
+    /// Additional superstates whose handlers are offered unhandled events.
+    pub mixin_superstates: Vec<Ident>,
     /// Optional entry action.
     pub entry_action: Option<Ident>,
     /// Optional exit action.
     pub exit_action: Option<Ident>,
     /// Local storage,
     pub local_storage: Vec<Field>,
+    /// Local storage that is built the first time the state is entered.
+    pub lazy_storage: Vec<LazyStorage>,
+    /// Actions that are declared inline with `entry` or `exit`.
+    pub inline_actions: Vec<ImplItemMethod>,
     /// Inputs required by the state handler.
     pub inputs: Vec<FnArg>,
     /// Optional receiver input for the state handler (e.g. `&mut self`).
@@ -77,8 +124,47 @@ pub struct State {
     pub event_arg: Option<PatType>,
     /// Context that is submitted to the state machine.
     pub context_arg: Option<PatType>,
+    /// Reference to the current state (e.g. `state: &State`).
+    pub state_arg: Option<PatType>,
+    /// Number of times the state has been entered (e.g. `entry_count: u32`).
+    pub entry_count_arg: Option<PatType>,
+    /// Transitions that are declared on the state.
+    pub transitions: Vec<Transition>,
+    /// Whether the state is a terminal state.
+    pub terminal: bool,
+    /// Optional response that replaces `Super` when returned by the handler.
+    pub fallback: Option<Expr>,
     /// Whether the function is async or not.
     pub is_async: bool,
+    /// Whether the function is a `const fn` or not.
+    pub is_const: bool,
+    /// Type of the effects the handler returns next to the response
+    /// (e.g. `Effects` for `(Effects, Response<State>)`).
+    pub effects: Option<Type>,
+}
+
+/// State-local storage that is built the first time the state is entered
+/// (e.g. `buf: Vec<u8> = make_buf()`).
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct LazyStorage {
+    /// The field of the storage.
+    pub field: Field,
+    /// Expression that builds the storage.
+    pub init: Expr,
+}
+
+/// Information regarding a transition that is declared on a state.
+#[cfg_attr(test, derive(Debug, Eq, PartialEq))]
+pub struct Transition {
+    /// Pattern the event must match for the transition to be taken.
+    pub event: Pat,
+    /// Optional condition on the event, used by transition tables that compare the
+    /// event with `==` (e.g. `*event == Event::Start`).
+    pub guard: Option<Expr>,
+    /// Optional action that is run during the transition.
+    pub action: Option<Ident>,
+    /// The target state of the transition.
+    pub target: Expr,
 }
 
 /// Information regarding a superstate.
@@ -88,6 +174,8 @@ pub struct Superstate {
     pub handler_name: Ident,
     /// Optional superstate.
     pub superstate: Option<Ident>,
+    /// Optional initial state that is entered when the superstate is the initial superstate.
+    pub initial: Option<ExprCall>,
     /// Optional entry action.
     pub entry_action: Option<Ident>,
     /// Optional exit action.
@@ -106,6 +194,11 @@ pub struct Superstate {
     pub context_arg: Option<PatType>,
     /// Whether the function is async or not.
     pub is_async: bool,
+    /// Whether the function is a `const fn` or not.
+    pub is_const: bool,
+    /// Type of the effects the handler returns next to the response
+    /// (e.g. `Effects` for `(Effects, Response<State>)`).
+    pub effects: Option<Type>,
 }
 
 /// Information regarding an action.
@@ -117,15 +210,26 @@ pub struct Action {
     pub inputs: Vec<FnArg>,
     /// Whether the function is async or not.
     pub is_async: bool,
+    /// Whether the action returns a `bool` that can veto entering the state.
+    pub is_vetoable: bool,
 }
 
 /// Analyze the impl block and create a model.
-pub fn analyze(attribute_args: AttributeArgs, item_impl: ItemImpl) -> Model {
+pub fn analyze(attribute_args: AttributeArgs, mut item_impl: ItemImpl) -> Model {
     let state_machine = analyze_state_machine(&attribute_args, &item_impl);
 
+    // Transition tables are consumed by the macro.
+    let (transition_tables, attrs) = item_impl
+        .attrs
+        .into_iter()
+        .partition(|attr| attr.path.is_ident("transitions"));
+    item_impl.attrs = attrs;
+
     let mut states = HashMap::new();
     let mut superstates = HashMap::new();
     let mut actions = HashMap::new();
+    let mut inline_actions = Vec::new();
+    let mut event_patterns = EventPatternVisitor::new();
 
     // Create an iterator over only the method items.
     let methods = item_impl.items.iter().filter_map(|item| match item {
@@ -139,11 +243,25 @@ pub fn analyze(attribute_args: AttributeArgs, item_impl: ItemImpl) -> Model {
             match &attr.path {
                 path if path.is_ident("state") => {
                     let state = analyze_state(method, &state_machine);
+                    if state_machine.check_event_coverage {
+                        event_patterns.visit_block(&method.block);
+                        for transition in &state.transitions {
+                            event_patterns.visit_pat(&transition.event);
+                        }
+                    }
+                    for method in &state.inline_actions {
+                        let action = analyze_action(method);
+                        actions.insert(action.handler_name.clone(), action);
+                        inline_actions.push(ImplItem::Method(method.clone()));
+                    }
                     states.insert(state.handler_name.clone(), state);
                 }
 
                 path if path.is_ident("superstate") => {
                     let superstate = analyze_superstate(method, &state_machine);
+                    if state_machine.check_event_coverage {
+                        event_patterns.visit_block(&method.block);
+                    }
                     superstates.insert(superstate.handler_name.clone(), superstate);
                 }
 
@@ -157,12 +275,61 @@ pub fn analyze(attribute_args: AttributeArgs, item_impl: ItemImpl) -> Model {
         }
     }
 
+    // States that only appear in a transition table get a handler that defers every
+    // event that doesn't match one of their transitions.
+    let mut table_states = Vec::new();
+    for attr in &transition_tables {
+        let table = analyze_transition_table(attr, &state_machine);
+        for (handler_name, transition) in table.transitions {
+            if superstates.contains_key(&handler_name) {
+                abort!(
+                    handler_name,
+                    "transition tables can only declare transitions of states"
+                );
+            }
+            let state = states.entry(handler_name.clone()).or_insert_with(|| {
+                let state_ident = &state_machine.state_ident;
+                let event_arg = table.event_type.as_ref().map(|event_type| {
+                    let event_ident = &state_machine.event_ident;
+                    match state_machine.lifecycle_events {
+                        true => quote!(#event_ident: &statig::Lifecycle<#event_type>),
+                        false => quote!(#event_ident: &#event_type),
+                    }
+                });
+                let method: ImplItemMethod = parse_quote!(
+                    #[state]
+                    #[allow(unused_variables)]
+                    fn #handler_name(#event_arg) -> statig::Response<#state_ident> {
+                        statig::Response::Super
+                    }
+                );
+                table_states.push(ImplItem::Method(method.clone()));
+                analyze_state(&method, &state_machine)
+            });
+            state.transitions.push(transition);
+        }
+    }
+
+    if states.is_empty() {
+        abort!(
+            item_impl.self_ty,
+            "a state machine needs at least one #[state] handler";
+            help = "add a method tagged with `#[state]` that returns `Response<State>`"
+        );
+    }
+
+    // The methods of inline actions and states from transition tables are added to
+    // the impl block.
+    item_impl.items.extend(inline_actions);
+    item_impl.items.extend(table_states);
+
     Model {
         item_impl,
         state_machine,
         states,
         superstates,
         actions,
+        event_patterns: event_patterns.finish(),
     }
 }
 
@@ -172,20 +339,34 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
     let shared_storage_generics = item_impl.generics.clone();
     let shared_storage_path = get_shared_storage_path(&shared_storage_type);
 
-    let mut initial_state: Option<ExprCall> = None;
+    let mut initial_state: Option<Expr> = None;
+    let mut initial_superstate: Option<Ident> = None;
 
     let mut state_ident = parse_quote!(State);
     let mut state_derives = Vec::new();
+    let mut jump_table = false;
+    let mut state_repr = None;
+    let mut state_constructor_trait = None;
     let mut superstate_ident = parse_quote!(Superstate);
     let mut superstate_derives = Vec::new();
 
     let mut on_transition = None;
     let mut on_dispatch = None;
+    let mut on_snapshot = None;
+    let mut ffi_events = Vec::new();
+    let mut catch_all = None;
+    let mut require_handled = false;
+    let mut check_event_coverage = false;
+    let mut lifecycle_events = false;
+    let mut effects = None;
+    let mut const_step = false;
+    let mut allow_single_async = false;
 
     let mut visibility = parse_quote!(pub);
     let mut event_ident = parse_quote!(event);
     let mut context_ident = parse_quote!(context);
 
+    let mut common_derives: Vec<Path> = Vec::new();
     let mut state_meta: MetaList = parse_quote!(state());
     let mut superstate_meta: MetaList = parse_quote!(superstate());
 
@@ -196,7 +377,26 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
                 if name_value.path.is_ident("initial") =>
             {
                 initial_state = match &name_value.lit {
-                    Lit::Str(input_pat) => input_pat.parse().ok(),
+                    Lit::Str(input_pat) => match input_pat.parse() {
+                        Ok(initial_state) => Some(initial_state),
+                        Err(_) => abort!(input_pat, "expected an expression"),
+                    },
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("initial_superstate") =>
+            {
+                initial_superstate = match &name_value.lit {
+                    Lit::Str(value) => Some(Ident::new(&value.value(), value.span())),
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("catch_all") =>
+            {
+                catch_all = match &name_value.lit {
+                    Lit::Str(value) => Some(Ident::new(&value.value(), value.span())),
                     _ => abort!(name_value, "must be a string literal"),
                 }
             }
@@ -232,6 +432,25 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
                     _ => abort!(name_value, "must be a string literal"),
                 }
             }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("on_snapshot") =>
+            {
+                on_snapshot = match &name_value.lit {
+                    Lit::Str(input_pat) => Some(input_pat.parse().unwrap()),
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value))
+                if name_value.path.is_ident("effects") =>
+            {
+                effects = match &name_value.lit {
+                    Lit::Str(effects) => match effects.parse() {
+                        Ok(effects) => Some(effects),
+                        Err(_) => abort!(effects, "expected a type"),
+                    },
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
             NestedMeta::Meta(Meta::NameValue(name_value))
                 if name_value.path.is_ident("visibility") =>
             {
@@ -240,25 +459,65 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
                     _ => abort!(name_value, "must be a string literal"),
                 }
             }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("require_handled") => {
+                require_handled = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("check_event_coverage") => {
+                check_event_coverage = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("lifecycle_events") => {
+                lifecycle_events = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("const_step") => {
+                const_step = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("allow_single_async") => {
+                allow_single_async = true;
+            }
             NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("state") => {
                 state_meta = list.clone();
             }
             NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("superstate") => {
                 superstate_meta = list.clone();
             }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("ffi_events") => {
+                for nested_meta in &list.nested {
+                    match nested_meta {
+                        NestedMeta::Lit(Lit::Str(event)) => match event.parse() {
+                            Ok(event) => ffi_events.push(event),
+                            Err(_) => abort!(event, "expected an expression for the event"),
+                        },
+                        _ => abort!(nested_meta, "must be a string literal"),
+                    }
+                }
+            }
+            NestedMeta::Meta(Meta::List(list)) if list.path.is_ident("derive") => {
+                for nested_meta in &list.nested {
+                    match nested_meta {
+                        NestedMeta::Meta(meta) => {
+                            common_derives.push(meta.path().clone());
+                        }
+                        _ => abort!(nested_meta, "expected list of traits"),
+                    }
+                }
+            }
 
             _ => abort!(arg, "argument not recognized"),
         }
     }
 
-    // Check if there is an initial state given.
-    let Some(initial_state) = initial_state else {
-        abort!(
-            initial_state,
+    // Check if there is exactly one initial state given.
+    match (&initial_state, &initial_superstate) {
+        (None, None) => abort_call_site!(
             "no initial state defined";
             help = "add an initial state `#[state_machine(initial = \"State::initial_state()\"]"
-        );
-    };
+        ),
+        (Some(_), Some(initial_superstate)) => abort!(
+            initial_superstate,
+            "`initial` and `initial_superstate` can not be used together"
+        ),
+        _ => {}
+    }
 
     // Iterate over the meta attributes for the state enum.
     for meta in state_meta
@@ -290,6 +549,38 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
                 }
             }
 
+            // Dispatch to the state handlers through a table of function pointers.
+            Meta::Path(path) if path.is_ident("jump_table") => {
+                jump_table = true;
+            }
+
+            // Get the integer representation of the state enum.
+            Meta::NameValue(name_value) if name_value.path.is_ident("repr") => {
+                let repr: Ident = match &name_value.lit {
+                    Lit::Str(str_lit) => str_lit.parse().unwrap(),
+                    _ => abort!(name_value, "expected string literal"),
+                };
+                const INTEGER_TYPES: [&str; 10] = [
+                    "u8", "u16", "u32", "u64", "usize", "i8", "i16", "i32", "i64", "isize",
+                ];
+                if !INTEGER_TYPES.iter().any(|ty| repr == ty) {
+                    abort!(
+                        name_value.lit,
+                        "repr must be a primitive integer type";
+                        help = "use one of `u8`, `u16`, `u32`, `u64`, `usize`, `i8`, `i16`, `i32`, `i64` or `isize`"
+                    );
+                }
+                state_repr = Some(repr);
+            }
+
+            // Get the name of the trait with the constructors of the states.
+            Meta::NameValue(name_value) if name_value.path.is_ident("constructor_trait") => {
+                state_constructor_trait = match &name_value.lit {
+                    Lit::Str(str_lit) => Some(str_lit.parse().unwrap()),
+                    _ => abort!(name_value, "expected string literal"),
+                }
+            }
+
             // Other attributes are not recognized.
             _ => abort!(meta, "unknown attribute"),
         }
@@ -330,16 +621,39 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
         }
     }
 
+    // Derives on the state machine are applied to both the state and superstate enum.
+    for derive in common_derives.iter().rev() {
+        if !state_derives.contains(derive) {
+            state_derives.insert(0, derive.clone());
+        }
+        if !superstate_derives.contains(derive) {
+            superstate_derives.insert(0, derive.clone());
+        }
+    }
+
     StateMachine {
         initial_state,
+        initial_superstate,
         shared_storage_type,
         shared_storage_path,
         shared_storage_generics,
         state_ident,
         state_derives,
+        jump_table,
+        state_repr,
+        state_constructor_trait,
         superstate_ident,
         superstate_derives,
         on_dispatch,
+        on_snapshot,
+        ffi_events,
+        catch_all,
+        require_handled,
+        check_event_coverage,
+        lifecycle_events,
+        effects,
+        const_step,
+        allow_single_async,
         on_transition,
         event_ident,
         context_ident,
@@ -350,16 +664,37 @@ pub fn analyze_state_machine(attribute_args: &AttributeArgs, item_impl: &ItemImp
 /// Retrieve information regarding the state.
 pub fn analyze_state(method: &ImplItemMethod, state_machine: &StateMachine) -> State {
     let handler_name = method.sig.ident.clone();
-    let inputs = method.sig.inputs.iter().cloned().collect();
+    let docs = method
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .cloned()
+        .collect();
+    let context_ident = get_context_ident(&method.attrs, "state", state_machine);
+    let inputs = method
+        .sig
+        .inputs
+        .iter()
+        .map(|input| rename_context_input(input, &context_ident, state_machine))
+        .collect();
 
     let mut superstate = None;
+    let mut mixin_superstates = Vec::new();
     let mut entry_action = None;
     let mut exit_action = None;
     let mut local_storage = Vec::new();
+    let mut lazy_storage = Vec::new();
+    let mut inline_entry = None;
+    let mut inline_exit = None;
     let mut shared_storage_input = None;
     let mut state_inputs = Vec::new();
     let mut event_arg = None;
     let mut context_arg = None;
+    let mut state_arg = None;
+    let mut entry_count_arg = None;
+    let mut transitions = Vec::new();
+    let mut terminal = false;
+    let mut fallback = None;
 
     let generic_params = &method.sig.generics.params;
     if !generic_params.is_empty() {
@@ -371,6 +706,8 @@ pub fn analyze_state(method: &ImplItemMethod, state_machine: &StateMachine) -> S
     }
 
     let is_async = method.sig.asyncness.is_some();
+    let is_const = method.sig.constness.is_some();
+    let effects = get_effects_type(&method.sig.output);
 
     // Iterate over the inputs of the state handler.
     for input in &method.sig.inputs {
@@ -380,8 +717,23 @@ pub fn analyze_state(method: &ImplItemMethod, state_machine: &StateMachine) -> S
                 Pat::Ident(pat) if state_machine.event_ident.eq(&pat.ident) => {
                     event_arg = Some(pat_type.clone());
                 }
-                Pat::Ident(pat) if state_machine.context_ident.eq(&pat.ident) => {
-                    context_arg = Some(pat_type.clone());
+                Pat::Ident(pat) if context_ident.eq(&pat.ident) => {
+                    if let FnArg::Typed(pat_type) =
+                        rename_context_input(input, &context_ident, state_machine)
+                    {
+                        context_arg = Some(pat_type);
+                    }
+                }
+                Pat::Ident(pat)
+                    if pat.ident == STATE_ARG && is_state_ref(pat_type, state_machine) =>
+                {
+                    state_arg = Some(pat_type.clone());
+                }
+                Pat::Ident(pat)
+                    if pat.ident == ENTRY_COUNT_ARG
+                        && matches!(pat_type.ty.as_ref(), Type::Path(path) if path.path.is_ident("u32")) =>
+                {
+                    entry_count_arg = Some(pat_type.clone());
                 }
                 Pat::Ident(_) => {
                     state_inputs.push(pat_type.clone());
@@ -405,11 +757,36 @@ pub fn analyze_state(method: &ImplItemMethod, state_machine: &StateMachine) -> S
     // Iterate over the meta attributes on the state handler.
     for meta in get_meta(&method.attrs, "state") {
         match meta {
+            // Already handled by `get_context_ident`.
+            Meta::NameValue(name_value) if name_value.path.is_ident("context") => {}
+            Meta::List(list) if list.path.is_ident("on") => {
+                transitions.push(analyze_transition(&list));
+            }
+            Meta::Path(path) if path.is_ident("terminal") => {
+                terminal = true;
+            }
             Meta::NameValue(name_value) if name_value.path.is_ident("superstate") => {
                 if let Lit::Str(value) = name_value.lit {
                     superstate = Some(Ident::new(&value.value(), value.span()));
                 }
             }
+            Meta::List(list) if list.path.is_ident("superstate") => {
+                let mut superstates = Vec::new();
+                for item in &list.nested {
+                    match item {
+                        NestedMeta::Lit(Lit::Str(value)) => {
+                            superstates.push(Ident::new(&value.value(), value.span()))
+                        }
+                        _ => abort!(item, "expected the name of a superstate"),
+                    }
+                }
+                if superstates.is_empty() {
+                    abort!(list, "expected at least one superstate");
+                }
+                // The first superstate is the parent, the others are mixins.
+                superstate = Some(superstates.remove(0));
+                mixin_superstates = superstates;
+            }
             Meta::NameValue(name_value) if name_value.path.is_ident("entry_action") => {
                 if let Lit::Str(value) = name_value.lit {
                     entry_action = Some(Ident::new(&value.value(), value.span()));
@@ -420,6 +797,20 @@ pub fn analyze_state(method: &ImplItemMethod, state_machine: &StateMachine) -> S
                     exit_action = Some(Ident::new(&value.value(), value.span()));
                 }
             }
+            Meta::NameValue(name_value) if name_value.path.is_ident("entry") => {
+                inline_entry = Some(parse_inline_action(&name_value));
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("exit") => {
+                inline_exit = Some(parse_inline_action(&name_value));
+            }
+            Meta::NameValue(name_value) if name_value.path.is_ident("fallback") => {
+                if let Lit::Str(value) = name_value.lit {
+                    match value.parse::<Expr>() {
+                        Ok(expr) => fallback = Some(expr),
+                        Err(_) => abort!(value, "fallback is not a valid expression"),
+                    }
+                }
+            }
             Meta::List(list) if list.path.is_ident("local_storage") => {
                 for item in list.nested {
                     if let NestedMeta::Lit(Lit::Str(value)) = item {
@@ -428,31 +819,303 @@ pub fn analyze_state(method: &ImplItemMethod, state_machine: &StateMachine) -> S
                     }
                 }
             }
+            Meta::List(list) if list.path.is_ident("lazy_storage") => {
+                for item in list.nested {
+                    match item {
+                        NestedMeta::Lit(Lit::Str(value)) => {
+                            match parse_lazy_storage.parse_str(&value.value()) {
+                                Ok(storage) => lazy_storage.push(storage),
+                                Err(_) => abort!(
+                                    value,
+                                    "expected lazy storage";
+                                    help = "declare the storage as `\"name: Type = expression\"`"
+                                ),
+                            }
+                        }
+                        _ => abort!(item, "must be a string literal"),
+                    }
+                }
+            }
             _ => abort!(meta, "unknown attribute"),
         }
     }
 
+    for storage in &lazy_storage {
+        let ident = &storage.field.ident;
+        if local_storage.iter().any(|field| &field.ident == ident) {
+            abort!(
+                ident,
+                "`{}` is declared both as local storage and as lazy storage",
+                ident.as_ref().unwrap()
+            );
+        }
+    }
+
+    let storage_fields: Vec<Field> = local_storage
+        .iter()
+        .cloned()
+        .chain(lazy_storage.iter().map(|storage| storage.field.clone()))
+        .collect();
+    check_local_storage_idents(
+        &storage_fields,
+        &state_inputs,
+        &context_ident,
+        state_machine,
+    );
+
+    // Inline actions are turned into methods that take the local storage of the state.
+    let mut inline_actions = Vec::new();
+    for (kind, inline_action, action) in [
+        ("entry", inline_entry, &mut entry_action),
+        ("exit", inline_exit, &mut exit_action),
+    ] {
+        if let Some(body) = inline_action {
+            if let Some(action) = action {
+                abort!(
+                    action,
+                    "`{}` and `{}_action` can not be used together",
+                    kind,
+                    kind
+                );
+            }
+            let method = inline_action_method(
+                format_ident!("__{}_{}", handler_name, kind),
+                body,
+                &local_storage,
+                &state_inputs,
+                &lazy_storage,
+            );
+            *action = Some(method.sig.ident.clone());
+            inline_actions.push(method);
+        }
+    }
+
+    // Terminal states consider every event handled, so their handler can't return a response.
+    if terminal {
+        if let ReturnType::Type(_, ty) = &method.sig.output {
+            abort!(
+                ty,
+                "terminal state handlers can not return a response";
+                help = "remove the return type, every event is considered handled in a terminal state"
+            );
+        }
+        if let Some(transition) = transitions.first() {
+            abort!(
+                transition.event,
+                "terminal states can not declare transitions"
+            );
+        }
+        if let Some(fallback) = &fallback {
+            abort!(fallback, "terminal states can not declare a fallback");
+        }
+    }
+
+    // The state is borrowed immutably to pass it to the handler, so the local storage
+    // can't be borrowed mutably at the same time.
+    if state_arg.is_some() {
+        for pat_type in &state_inputs {
+            if let Type::Reference(reference) = pat_type.ty.as_ref() {
+                if reference.mutability.is_some() {
+                    abort!(
+                        pat_type,
+                        "local storage can not be borrowed mutably when the handler also takes the state";
+                        help = "borrow the local storage immutably with `&` instead of `&mut`"
+                    )
+                }
+            }
+        }
+    }
+
     State {
         handler_name,
+        docs,
         superstate,
+        mixin_superstates,
         entry_action,
         exit_action,
         local_storage,
+        lazy_storage,
+        inline_actions,
         inputs,
         shared_storage_input,
         state_inputs,
         event_arg,
         context_arg,
+        state_arg,
+        entry_count_arg,
+        transitions,
+        terminal,
+        fallback,
         is_async,
+        is_const,
+        effects,
+    }
+}
+
+/// Retrieve the information regarding a transition declared with `on(..)`.
+pub fn analyze_transition(list: &MetaList) -> Transition {
+    let mut event = None;
+    let mut action = None;
+    let mut target = None;
+
+    for nested_meta in &list.nested {
+        match nested_meta {
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("event") => {
+                event = match &name_value.lit {
+                    Lit::Str(value) => match value.parse() {
+                        Ok(pat) => Some(pat),
+                        Err(_) => abort!(value, "expected a pattern"),
+                    },
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("action") => {
+                action = match &name_value.lit {
+                    Lit::Str(value) => Some(Ident::new(&value.value(), value.span())),
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
+            NestedMeta::Meta(Meta::NameValue(name_value)) if name_value.path.is_ident("target") => {
+                target = match &name_value.lit {
+                    Lit::Str(value) => match value.parse() {
+                        Ok(expr) => Some(expr),
+                        Err(_) => abort!(value, "expected an expression"),
+                    },
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
+            _ => abort!(nested_meta, "unknown attribute"),
+        }
+    }
+
+    let Some(event) = event else {
+        abort!(
+            list,
+            "no event defined for transition";
+            help = "add an event pattern `on(event = \"Event::A\", ..)`"
+        );
+    };
+
+    let Some(target) = target else {
+        abort!(
+            list,
+            "no target defined for transition";
+            help = "add a target state `on(.., target = \"State::a()\")`"
+        );
+    };
+
+    Transition {
+        event,
+        guard: None,
+        action,
+        target,
+    }
+}
+
+/// A table of transitions declared on the impl block with `#[transitions(..)]`.
+pub struct TransitionTable {
+    /// Optional type of the event, needed when no handler takes the event
+    /// (e.g. `event = Event`).
+    pub event_type: Option<Type>,
+    /// The transitions with the name of the handler of their source state.
+    pub transitions: Vec<(Ident, Transition)>,
+}
+
+/// Retrieve the transitions of a transition table of the form
+/// `#[transitions(event = Event, Idle: Event::Start => Running, Event::Stop => Idle)]`.
+/// The source state of an entry carries over to the next entries, events are compared
+/// with `==`.
+pub fn analyze_transition_table(attr: &Attribute, state_machine: &StateMachine) -> TransitionTable {
+    let event_ident = &state_machine.event_ident;
+    let state_ident = &state_machine.state_ident;
+
+    let parser = |input: ParseStream| {
+        let mut event_type = None;
+        let mut transitions = Vec::new();
+        let mut source: Option<Ident> = None;
+
+        if input.peek(Ident) && input.peek2(Token![=]) && !input.peek2(Token![=>]) {
+            let key: Ident = input.parse()?;
+            if key != "event" {
+                return Err(syn::Error::new(key.span(), "expected `event = ..`"));
+            }
+            input.parse::<Token![=]>()?;
+            event_type = Some(input.parse::<Type>()?);
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        while !input.is_empty() {
+            if input.peek(Ident) && input.peek2(Token![:]) && !input.peek2(Token![::]) {
+                let state: Ident = input.parse()?;
+                input.parse::<Token![:]>()?;
+                source = Some(pascal_case_to_snake_case(&state));
+            }
+            let event: Expr = input.parse()?;
+            input.parse::<Token![=>]>()?;
+            let target: Ident = input.parse()?;
+
+            let Some(source) = &source else {
+                return Err(syn::Error::new_spanned(
+                    event,
+                    "expected the source state of the transition (e.g. `Idle: Event::Start => Running`)",
+                ));
+            };
+            let target = pascal_case_to_snake_case(&target);
+            transitions.push((
+                source.clone(),
+                Transition {
+                    event: parse_quote!(_),
+                    guard: Some(parse_quote!(*#event_ident == #event)),
+                    action: None,
+                    target: parse_quote!(#state_ident::#target()),
+                },
+            ));
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(TransitionTable {
+            event_type,
+            transitions,
+        })
+    };
+
+    match attr.parse_args_with(parser) {
+        Ok(table) => table,
+        Err(error) => abort!(error),
     }
 }
 
+/// Convert the name of a state variant to the name of its handler (e.g. `LedOn` to
+/// `led_on`).
+fn pascal_case_to_snake_case(pascal: &Ident) -> Ident {
+    let mut snake = String::new();
+    for (i, character) in pascal.to_string().chars().enumerate() {
+        if character.is_uppercase() && i > 0 {
+            snake.push('_');
+        }
+        snake.extend(character.to_lowercase());
+    }
+    Ident::new(&snake, pascal.span())
+}
+
 /// Retrieve the information regarding the superstate.
 pub fn analyze_superstate(method: &ImplItemMethod, state_machine: &StateMachine) -> Superstate {
     let handler_name = method.sig.ident.clone();
-    let inputs = method.sig.inputs.iter().cloned().collect();
+    let context_ident = get_context_ident(&method.attrs, "superstate", state_machine);
+    let inputs = method
+        .sig
+        .inputs
+        .iter()
+        .map(|input| rename_context_input(input, &context_ident, state_machine))
+        .collect();
 
     let mut superstate = None;
+    let mut initial = None;
     let mut entry_action = None;
     let mut exit_action = None;
     let mut local_storage = Vec::new();
@@ -471,6 +1134,8 @@ pub fn analyze_superstate(method: &ImplItemMethod, state_machine: &StateMachine)
     }
 
     let is_async = method.sig.asyncness.is_some();
+    let is_const = method.sig.constness.is_some();
+    let effects = get_effects_type(&method.sig.output);
 
     // Iterate over the inputs of the superstate handler.
     for input in &method.sig.inputs {
@@ -480,8 +1145,12 @@ pub fn analyze_superstate(method: &ImplItemMethod, state_machine: &StateMachine)
                 Pat::Ident(pat) if state_machine.event_ident.eq(&pat.ident) => {
                     event_arg = Some(pat_type.clone());
                 }
-                Pat::Ident(pat) if state_machine.context_ident.eq(&pat.ident) => {
-                    context_arg = Some(pat_type.clone());
+                Pat::Ident(pat) if context_ident.eq(&pat.ident) => {
+                    if let FnArg::Typed(pat_type) =
+                        rename_context_input(input, &context_ident, state_machine)
+                    {
+                        context_arg = Some(pat_type);
+                    }
                 }
                 Pat::Ident(_) => {
                     state_inputs.push(pat_type.clone());
@@ -505,11 +1174,22 @@ pub fn analyze_superstate(method: &ImplItemMethod, state_machine: &StateMachine)
     // Iterate over the meta attributes on the superstate handler.
     for meta in get_meta(&method.attrs, "superstate") {
         match meta {
+            // Already handled by `get_context_ident`.
+            Meta::NameValue(name_value) if name_value.path.is_ident("context") => {}
             Meta::NameValue(name_value) if name_value.path.is_ident("superstate") => {
                 if let Lit::Str(value) = name_value.lit {
                     superstate = Some(Ident::new(&value.value(), value.span()));
                 }
             }
+            Meta::NameValue(name_value) if name_value.path.is_ident("initial") => {
+                initial = match &name_value.lit {
+                    Lit::Str(value) => match value.parse() {
+                        Ok(initial) => Some(initial),
+                        Err(_) => abort!(value, "expected a call to a state constructor"),
+                    },
+                    _ => abort!(name_value, "must be a string literal"),
+                }
+            }
             Meta::NameValue(name_value) if name_value.path.is_ident("entry_action") => {
                 if let Lit::Str(value) = name_value.lit {
                     entry_action = Some(Ident::new(&value.value(), value.span()));
@@ -532,9 +1212,12 @@ pub fn analyze_superstate(method: &ImplItemMethod, state_machine: &StateMachine)
         }
     }
 
+    check_local_storage_idents(&local_storage, &state_inputs, &context_ident, state_machine);
+
     Superstate {
         handler_name,
         superstate,
+        initial,
         entry_action,
         exit_action,
         local_storage,
@@ -544,6 +1227,8 @@ pub fn analyze_superstate(method: &ImplItemMethod, state_machine: &StateMachine)
         event_arg,
         context_arg,
         is_async,
+        is_const,
+        effects,
     }
 }
 
@@ -552,6 +1237,18 @@ pub fn analyze_action(method: &ImplItemMethod) -> Action {
     let handler_name = method.sig.ident.clone();
     let inputs = method.sig.inputs.clone().into_iter().collect();
     let is_async = method.sig.asyncness.is_some();
+    let is_vetoable = match &method.sig.output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(type_path) if type_path.path.is_ident("bool") => true,
+            Type::Tuple(tuple) if tuple.elems.is_empty() => false,
+            _ => abort!(
+                ty,
+                "actions can only return `bool`";
+                help = "an entry action that returns `false` vetoes entering the state"
+            ),
+        },
+        ReturnType::Default => false,
+    };
 
     let generic_params = &method.sig.generics.params;
     if !generic_params.is_empty() {
@@ -566,6 +1263,105 @@ pub fn analyze_action(method: &ImplItemMethod) -> Action {
         handler_name,
         inputs,
         is_async,
+        is_vetoable,
+    }
+}
+
+/// Parse the body of an inline action (e.g. `entry = "self.led = true"`).
+fn parse_inline_action(name_value: &MetaNameValue) -> Block {
+    match &name_value.lit {
+        Lit::Str(value) => match syn::parse_str::<Block>(&format!("{{ {} }}", value.value())) {
+            Ok(body) => body,
+            Err(_) => abort!(value, "expected the statements of the action"),
+        },
+        _ => abort!(name_value, "must be a string literal"),
+    }
+}
+
+/// Create the method for an inline action. It takes the local storage of the state
+/// by mutable reference, except for lazy storage which is not built yet on entry.
+fn inline_action_method(
+    name: Ident,
+    body: Block,
+    local_storage: &[Field],
+    state_inputs: &[PatType],
+    lazy_storage: &[LazyStorage],
+) -> ImplItemMethod {
+    let mut fields: Vec<(Ident, Type)> = Vec::new();
+    for pat_type in state_inputs {
+        if let (Pat::Ident(pat), Type::Reference(reference)) =
+            (pat_type.pat.as_ref(), pat_type.ty.as_ref())
+        {
+            fields.push((pat.ident.clone(), *reference.elem.clone()));
+        }
+    }
+    for field in local_storage {
+        let ident = field.ident.clone().unwrap();
+        fields.retain(|(existing, _)| existing != &ident);
+        fields.push((ident, field.ty.clone()));
+    }
+    fields.retain(|(ident, _)| {
+        !lazy_storage
+            .iter()
+            .any(|storage| storage.field.ident.as_ref() == Some(ident))
+    });
+    let inputs = fields.iter().map(|(ident, ty)| quote!(#ident: &mut #ty));
+
+    parse_quote!(
+        #[allow(unused_variables)]
+        fn #name(&mut self, #(#inputs),*) #body
+    )
+}
+
+/// Parse lazy storage declared as `name: Type = expression`.
+fn parse_lazy_storage(input: ParseStream) -> syn::Result<LazyStorage> {
+    let field = Field::parse_named(input)?;
+    input.parse::<Token![=]>()?;
+    let init = input.parse()?;
+    Ok(LazyStorage { field, init })
+}
+
+/// The generated code binds the shared storage, event and context to these
+/// identifiers, so local storage with the same name would shadow them. The
+/// context is also reserved under the name the handler gives it.
+fn check_local_storage_idents(
+    local_storage: &[Field],
+    state_inputs: &[PatType],
+    context_ident: &Ident,
+    state_machine: &StateMachine,
+) {
+    let field_idents = local_storage
+        .iter()
+        .filter_map(|field| field.ident.as_ref());
+    let input_idents = state_inputs
+        .iter()
+        .filter_map(|pat_type| match &*pat_type.pat {
+            Pat::Ident(pat) => Some(&pat.ident),
+            _ => None,
+        });
+    for ident in field_idents.chain(input_idents) {
+        if ident == SHARED_STORAGE_IDENT
+            || ident == &state_machine.event_ident
+            || ident == &state_machine.context_ident
+            || ident == context_ident
+        {
+            abort!(
+                ident,
+                "`{}` is reserved and can not be used as the name of local storage", ident;
+                help = "rename the local storage, `{}`, `{}` and `{}` are used by the generated code",
+                    SHARED_STORAGE_IDENT, state_machine.event_ident, context_ident
+            );
+        }
+    }
+}
+
+/// Check whether the input is a shared reference to the state enum (e.g. `state: &State`).
+fn is_state_ref(pat_type: &PatType, state_machine: &StateMachine) -> bool {
+    match pat_type.ty.as_ref() {
+        Type::Reference(reference) if reference.mutability.is_none() => {
+            matches!(reference.elem.as_ref(), Type::Path(path) if path.path.is_ident(&state_machine.state_ident))
+        }
+        _ => false,
     }
 }
 
@@ -587,6 +1383,55 @@ pub fn get_meta(attrs: &[Attribute], name: &str) -> Vec<Meta> {
         .collect()
 }
 
+/// Get the identifier of the context input of a handler, which can be renamed with
+/// `context = "..."`.
+fn get_context_ident(attrs: &[Attribute], name: &str, state_machine: &StateMachine) -> Ident {
+    for meta in get_meta(attrs, name) {
+        if let Meta::NameValue(name_value) = meta {
+            if name_value.path.is_ident("context") {
+                match &name_value.lit {
+                    Lit::Str(context_ident) => match context_ident.parse() {
+                        Ok(context_ident) => return context_ident,
+                        Err(_) => abort!(context_ident, "context must be an identifier"),
+                    },
+                    _ => abort!(name_value, "context must be a string literal"),
+                }
+            }
+        }
+    }
+    state_machine.context_ident.clone()
+}
+
+/// Give a renamed context input the identifier of the state machine, so it can be passed
+/// along with the other handlers.
+fn rename_context_input(
+    input: &FnArg,
+    context_ident: &Ident,
+    state_machine: &StateMachine,
+) -> FnArg {
+    let mut input = input.clone();
+    if let FnArg::Typed(pat_type) = &mut input {
+        if let Pat::Ident(pat) = pat_type.pat.as_mut() {
+            if context_ident.eq(&pat.ident) {
+                pat.ident = state_machine.context_ident.clone();
+            }
+        }
+    }
+    input
+}
+
+/// Get the type of the effects a handler returns next to the response, in which case
+/// its return type is a pair (e.g. `(Effects, Response<State>)`).
+fn get_effects_type(output: &ReturnType) -> Option<Type> {
+    match output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Tuple(tuple) if tuple.elems.len() == 2 => Some(tuple.elems[0].clone()),
+            _ => None,
+        },
+        ReturnType::Default => None,
+    }
+}
+
 /// Get the ident of the shared storage type.
 pub fn get_shared_storage_path(ty: &Type) -> Path {
     match ty {
@@ -634,7 +1479,8 @@ fn valid_state_analyze() {
 
     let actual = analyze(attribute_args, item_impl.clone());
 
-    let initial_state = parse_quote!(State::on());
+    let initial_state = Some(parse_quote!(State::on()));
+    let initial_superstate = None;
 
     let shared_storage_type = parse_quote!(Blinky);
     let shared_storage_path = parse_quote!(Blinky);
@@ -642,25 +1488,50 @@ fn valid_state_analyze() {
 
     let state_ident = parse_quote!(State);
     let state_derives = vec![parse_quote!(Copy), parse_quote!(Clone)];
+    let jump_table = false;
+    let state_repr = None;
+    let state_constructor_trait = None;
     let superstate_ident = parse_quote!(Superstate);
     let superstate_derives = vec![parse_quote!(Copy), parse_quote!(Clone)];
     let on_transition = None;
     let on_dispatch = None;
+    let on_snapshot = None;
+    let ffi_events = Vec::new();
+    let catch_all = None;
+    let require_handled = false;
+    let check_event_coverage = false;
+    let lifecycle_events = false;
+    let effects = None;
+    let const_step = false;
+    let allow_single_async = false;
     let event_ident = parse_quote!(event);
     let context_ident = parse_quote!(context);
     let visibility = parse_quote!(pub);
 
     let state_machine = StateMachine {
         initial_state,
+        initial_superstate,
         shared_storage_type,
         shared_storage_path,
         shared_storage_generics,
         state_ident,
         state_derives,
+        jump_table,
+        state_repr,
+        state_constructor_trait,
         superstate_ident,
         superstate_derives,
         on_transition,
         on_dispatch,
+        on_snapshot,
+        ffi_events,
+        catch_all,
+        require_handled,
+        check_event_coverage,
+        lifecycle_events,
+        effects,
+        const_step,
+        allow_single_async,
         event_ident,
         context_ident,
         visibility,
@@ -668,10 +1539,14 @@ fn valid_state_analyze() {
 
     let state = State {
         handler_name: parse_quote!(on),
+        docs: vec![],
         superstate: parse_quote!(playing),
+        mixin_superstates: vec![],
         entry_action: parse_quote!(enter_on),
         exit_action: parse_quote!(enter_off),
         local_storage: vec![],
+        lazy_storage: vec![],
+        inline_actions: vec![],
         inputs: vec![parse_quote!(&mut self), parse_quote!(event: &Event)],
         shared_storage_input: Some(parse_quote!(&mut self)),
         state_inputs: vec![],
@@ -681,12 +1556,20 @@ fn valid_state_analyze() {
             return;
         }),
         context_arg: None,
+        state_arg: None,
+        entry_count_arg: None,
+        transitions: vec![],
+        terminal: false,
+        fallback: None,
         is_async: false,
+        is_const: false,
+        effects: None,
     };
 
     let superstate = Superstate {
         handler_name: parse_quote!(playing),
         superstate: None,
+        initial: None,
         entry_action: None,
         exit_action: None,
         local_storage: vec![],
@@ -700,18 +1583,22 @@ fn valid_state_analyze() {
         }),
         context_arg: None,
         is_async: false,
+        is_const: false,
+        effects: None,
     };
 
     let entry_action = Action {
         handler_name: parse_quote!(enter_on),
         inputs: vec![parse_quote!(&mut self)],
         is_async: false,
+        is_vetoable: false,
     };
 
     let exit_action = Action {
         handler_name: parse_quote!(enter_off),
         inputs: vec![parse_quote!(&mut self)],
         is_async: false,
+        is_vetoable: false,
     };
 
     let mut states = HashMap::new();
@@ -729,7 +1616,188 @@ fn valid_state_analyze() {
         states,
         superstates,
         actions,
+        event_patterns: vec![],
     };
 
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn common_derives() {
+    let attribute_args = vec![
+        parse_quote!(initial = "State::on()"),
+        parse_quote!(derive(Debug, Clone)),
+        parse_quote!(state(derive(Clone, PartialEq))),
+    ];
+    let item_impl: ItemImpl = parse_quote!(impl Blinky {});
+
+    let state_machine = analyze_state_machine(&attribute_args, &item_impl);
+
+    let state_derives: Vec<Path> = vec![
+        parse_quote!(Debug),
+        parse_quote!(Clone),
+        parse_quote!(PartialEq),
+    ];
+    let superstate_derives: Vec<Path> = vec![parse_quote!(Debug), parse_quote!(Clone)];
+
+    assert_eq!(state_machine.state_derives, state_derives);
+    assert_eq!(state_machine.superstate_derives, superstate_derives);
+}
+
+#[test]
+#[should_panic]
+fn no_states() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        impl Blinky {
+            #[superstate]
+            fn playing(&mut self) -> Response<State> {
+                Super
+            }
+        }
+    );
+
+    analyze(attribute_args, item_impl);
+}
+
+#[test]
+#[should_panic]
+fn reserved_local_storage_ident() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        impl Blinky {
+            #[state]
+            fn on(shared_storage: &mut u32, event: &Event) -> Response<State> {
+                Handled
+            }
+        }
+    );
+
+    analyze(attribute_args, item_impl);
+}
+
+#[test]
+#[should_panic]
+fn reserved_local_storage_ident_renamed_context() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        impl Blinky {
+            #[state(context = "world", local_storage("world: u32"))]
+            fn on(world: &mut u32, event: &Event) -> Response<State> {
+                Handled
+            }
+        }
+    );
+
+    analyze(attribute_args, item_impl);
+}
+
+#[test]
+#[should_panic]
+fn reserved_local_storage_ident_renamed_superstate_context() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        impl Blinky {
+            #[state(superstate = "playing")]
+            fn on(event: &Event) -> Response<State> {
+                Handled
+            }
+
+            #[superstate(context = "world", local_storage("world: &'a u32"))]
+            fn playing(world: &u32, event: &Event) -> Response<State> {
+                Handled
+            }
+        }
+    );
+
+    analyze(attribute_args, item_impl);
+}
+
+#[test]
+#[should_panic]
+fn lazy_storage_without_init() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        impl Blinky {
+            #[state(lazy_storage("buf: Vec<u8>"))]
+            fn on(buf: &mut Vec<u8>, event: &Event) -> Response<State> {
+                Handled
+            }
+        }
+    );
+
+    analyze(attribute_args, item_impl);
+}
+
+#[test]
+#[should_panic]
+fn inline_entry_with_entry_action() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        impl Blinky {
+            #[state(entry = "self.led = true", entry_action = "enter_on")]
+            fn on(event: &Event) -> Response<State> {
+                Handled
+            }
+        }
+    );
+
+    analyze(attribute_args, item_impl);
+}
+
+#[test]
+fn transition_table() {
+    let attribute_args = vec![parse_quote!(initial = "State::led_off()")];
+    let item_impl: ItemImpl = parse_quote!(
+        #[transitions(
+            event = Event,
+            LedOff: Event::Toggle => LedOn,
+            LedOn: Event::Toggle => LedOff, Event::Reset => LedOff
+        )]
+        impl Blinky {
+            #[state]
+            fn led_on(event: &Event) -> Response<State> {
+                Super
+            }
+        }
+    );
+
+    let model = analyze(attribute_args, item_impl);
+
+    assert!(model.item_impl.attrs.is_empty());
+    assert_eq!(model.item_impl.items.len(), 2);
+
+    let led_on = &model.states[&format_ident!("led_on")];
+    let targets: Vec<Expr> = led_on
+        .transitions
+        .iter()
+        .map(|transition| transition.target.clone())
+        .collect();
+    assert_eq!(
+        targets,
+        [
+            parse_quote!(State::led_off()),
+            parse_quote!(State::led_off())
+        ]
+    );
+    assert_eq!(
+        led_on.transitions[1].guard,
+        Some(parse_quote!(*event == Event::Reset))
+    );
+
+    let led_off = &model.states[&format_ident!("led_off")];
+    assert_eq!(led_off.transitions.len(), 1);
+    assert!(led_off.event_arg.is_some());
+}
+
+#[test]
+#[should_panic]
+fn transition_table_without_source() {
+    let attribute_args = vec![parse_quote!(initial = "State::on()")];
+    let item_impl: ItemImpl = parse_quote!(
+        #[transitions(Event::Toggle => Off)]
+        impl Blinky {}
+    );
+
+    analyze(attribute_args, item_impl);
+}
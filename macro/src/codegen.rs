@@ -1,22 +1,32 @@
-use proc_macro2::{Span, TokenStream};
-use quote::quote;
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
 use syn::{
-    parse_quote, Arm, GenericParam, ItemEnum, ItemFn, ItemImpl, Lifetime, LifetimeDef, Variant,
+    parse_quote, Arm, FnArg, GenericParam, Ident, ImplItem, ImplItemConst, ImplItemMethod,
+    ItemConst, ItemEnum, ItemFn, ItemImpl, Lifetime, LifetimeDef, Signature, Variant,
 };
 
-use crate::lower::{Ir, Mode};
+use crate::lower::{Ir, Mode, State};
 use crate::{CONTEXT_LIFETIME, EVENT_LIFETIME, SUPERSTATE_LIFETIME};
 
 pub fn codegen(ir: Ir) -> TokenStream {
     let item_impl = &ir.item_impl;
 
     let state_machine_impl = codegen_state_machine_impl(&ir);
+    let require_handled_impl = codegen_require_handled_impl(&ir);
+    let has_effects_impl = codegen_has_effects_impl(&ir);
 
     let state_enum = codegen_state(&ir);
     let state_impl = codegen_state_impl(&ir);
     let state_impl_state = codegen_state_impl_state(&ir);
     let superstate_enum = codegen_superstate(&ir);
-    let superstate_impl = codegen_superstate_impl_superstate(&ir);
+    let superstate_impl = codegen_superstate_impl(&ir);
+    let superstate_impl_superstate = codegen_superstate_impl_superstate(&ir);
+    let ffi_events_impl = codegen_ffi_events_impl(&ir);
+    let constructor_trait = codegen_constructor_trait(&ir);
+    let pure_state_machine_impl = codegen_pure_state_machine_impl(&ir);
+    let event_coverage_check = codegen_event_coverage_check(&ir);
+    let single_async_function_warning = codegen_single_async_function_warning(&ir);
+    let entry_count_check = codegen_entry_count_check(&ir);
 
     quote!(
         // Import the proc_macro attributes so they can be used to tag functions.
@@ -26,6 +36,10 @@ pub fn codegen(ir: Ir) -> TokenStream {
 
         #state_machine_impl
 
+        #require_handled_impl
+
+        #has_effects_impl
+
         #state_enum
 
         #state_impl
@@ -35,6 +49,20 @@ pub fn codegen(ir: Ir) -> TokenStream {
         #superstate_enum
 
         #superstate_impl
+
+        #superstate_impl_superstate
+
+        #ffi_events_impl
+
+        #constructor_trait
+
+        #pure_state_machine_impl
+
+        #event_coverage_check
+
+        #single_async_function_warning
+
+        #entry_count_check
     )
 }
 
@@ -69,7 +97,14 @@ fn codegen_state_machine_impl(ir: &Ir) -> ItemImpl {
     let on_dispatch = match &ir.state_machine.on_dispatch {
         None => quote!(),
         Some(on_dispatch) => quote!(
-            const ON_DISPATCH: fn(&mut Self, StateOrSuperstate<'_, '_, Self>, &Self::Event<'_>) = #on_dispatch;
+            const ON_DISPATCH: fn(&mut Self, statig::StateOrSuperstate<'_, '_, Self>, &Self::Event<'_>) = #on_dispatch;
+        ),
+    };
+
+    let on_snapshot = match &ir.state_machine.on_snapshot {
+        None => quote!(),
+        Some(on_snapshot) => quote!(
+            const ON_SNAPSHOT: fn(&Self, &Self::State) = #on_snapshot;
         ),
     };
 
@@ -85,28 +120,262 @@ fn codegen_state_machine_impl(ir: &Ir) -> ItemImpl {
             #on_transition
 
             #on_dispatch
+
+            #on_snapshot
         }
     )
 }
 
+/// The compiler reports the event variants that are missing from this match, which
+/// are the ones that no handler matches.
+fn codegen_event_coverage_check(ir: &Ir) -> Option<ItemConst> {
+    let event_coverage = ir.state_machine.event_coverage.as_ref()?;
+    let event_path = &event_coverage.event_path;
+
+    let arms = match event_coverage.variants.as_slice() {
+        [] => quote!(),
+        variants => quote!(#(#event_path::#variants { .. })|* => {}),
+    };
+
+    Some(parse_quote!(
+        const _: () = {
+            #[allow(dead_code)]
+            fn check_event_coverage(event: &#event_path) {
+                match *event {
+                    #arms
+                }
+            }
+        };
+    ))
+}
+
+fn codegen_entry_count_check(ir: &Ir) -> Option<ItemConst> {
+    // Entry counts are kept by the state machine in an array with room for a fixed
+    // number of states.
+    let count = ir.states.values().filter(|state| state.entry_count).count();
+    (count > 0).then(|| {
+        parse_quote!(
+            const _: () = statig::__private::check_entry_counts(#count);
+        )
+    })
+}
+
+fn codegen_single_async_function_warning(ir: &Ir) -> Option<ItemConst> {
+    let async_function = ir.state_machine.single_async_function.as_ref()?;
+
+    // Proc macros can't emit warnings on stable, so the warning is raised by using a
+    // deprecated item at the span of the async function.
+    let use_warning = quote_spanned!(async_function.span()=> let _ = single_async_function;);
+
+    Some(parse_quote!(
+        const _: () = {
+            #[deprecated(
+                note = "this async function makes the whole state machine async, so `init()` and `handle()` will have to be awaited. Remove `async` from it if the state machine should be blocking, or add `#[state_machine(allow_single_async)]` if it should be async"
+            )]
+            #[allow(non_upper_case_globals)]
+            const single_async_function: u8 = 0;
+
+            #use_warning
+        };
+    ))
+}
+
+fn codegen_require_handled_impl(ir: &Ir) -> Option<ItemImpl> {
+    // The marker trait makes `try_handle` available to submit events.
+    if !ir.state_machine.require_handled {
+        return None;
+    }
+
+    let shared_storage_type = &ir.state_machine.shared_storage_type;
+    let (impl_generics, _, where_clause) =
+        &ir.state_machine.shared_storage_generics.split_for_impl();
+
+    Some(parse_quote!(
+        impl #impl_generics statig::RequireHandled for #shared_storage_type #where_clause {}
+    ))
+}
+
+fn codegen_has_effects_impl(ir: &Ir) -> Option<ItemImpl> {
+    // The effects type makes `handle_with_effects` available to collect the effects.
+    let effects_type = ir.state_machine.effects_type.as_ref()?;
+
+    let shared_storage_type = &ir.state_machine.shared_storage_type;
+    let (impl_generics, _, where_clause) =
+        &ir.state_machine.shared_storage_generics.split_for_impl();
+
+    Some(parse_quote!(
+        impl #impl_generics statig::HasEffects for #shared_storage_type #where_clause {
+            type Effects = #effects_type;
+        }
+    ))
+}
+
+fn codegen_ffi_events_impl(ir: &Ir) -> Option<ItemImpl> {
+    if ir.state_machine.ffi_events.is_empty() {
+        return None;
+    }
+
+    let shared_storage_type = &ir.state_machine.shared_storage_type;
+    let (impl_generics, _, where_clause) =
+        &ir.state_machine.shared_storage_generics.split_for_impl();
+
+    let mode = match ir.state_machine.mode {
+        Mode::Blocking => quote!(blocking),
+        Mode::Awaitable => quote!(awaitable),
+    };
+
+    // The tag of an event is its position in the list.
+    let tag_arms: Vec<Arm> = ir
+        .state_machine
+        .ffi_events
+        .iter()
+        .enumerate()
+        .map(|(tag, event)| {
+            let tag = Literal::u32_unsuffixed(tag as u32);
+            parse_quote!(#tag => Some(#event))
+        })
+        .collect();
+
+    Some(parse_quote!(
+        impl #impl_generics statig::FfiEvents<<#shared_storage_type as statig::#mode::IntoStateMachine>::Event<'static>> for #shared_storage_type #where_clause
+        {
+            #[allow(unused_variables, unused_unsafe)]
+            unsafe fn try_from_tag(tag: u32, payload: *const u8) -> Option<<Self as statig::#mode::IntoStateMachine>::Event<'static>> {
+                match tag {
+                    #(#tag_arms,)*
+                    _ => None,
+                }
+            }
+        }
+    ))
+}
+
+fn codegen_pure_state_machine_impl(ir: &Ir) -> Option<ItemImpl> {
+    let shared_storage_type = &ir.state_machine.shared_storage_type;
+    let state_ident = &ir.state_machine.state_ident;
+    let (_, state_generics, _) = &ir.state_machine.state_generics.split_for_impl();
+    let event_ident = &ir.state_machine.event_ident;
+
+    let classify_arms: Vec<Arm> = ir
+        .states
+        .values()
+        .map(|state| {
+            let pat = &state.pat;
+            let pure_call = state.pure_call.as_ref()?;
+            Some(parse_quote!(#pat => #pure_call))
+        })
+        .collect::<Option<_>>()?;
+
+    Some(parse_quote!(
+        impl statig::blocking::PureStateMachine for #shared_storage_type {
+            #[allow(unused_variables)]
+            fn classify_event(
+                &self,
+                state: &#state_ident #state_generics,
+                #event_ident: &<Self as statig::IntoStateMachine>::Event<'_>
+            ) -> statig::Response<#state_ident #state_generics> {
+                let shared_storage = self;
+                match state {
+                    #(#classify_arms),*
+                }
+            }
+        }
+    ))
+}
+
 fn codegen_state(ir: &Ir) -> ItemEnum {
     let state_ident = &ir.state_machine.state_ident;
     let (state_generics, _, _) = &ir.state_machine.state_generics.split_for_impl();
     let state_derives = &ir.state_machine.state_derives;
 
-    let variants: Vec<Variant> = ir
-        .states
-        .values()
-        .map(|state| state.variant.clone())
-        .collect();
     let visibility = &ir.state_machine.visibility;
 
-    parse_quote!(
-        #[derive(#(#state_derives),*)]
-        # visibility enum #state_ident #state_generics {
-            #(#variants),*
+    match &ir.state_machine.state_repr {
+        None => {
+            let variants: Vec<Variant> = ir
+                .states
+                .values()
+                .map(|state| state.variant.clone())
+                .collect();
+
+            parse_quote!(
+                #[derive(#(#state_derives),*)]
+                # visibility enum #state_ident #state_generics {
+                    #(#variants),*
+                }
+            )
         }
-    )
+        // Give every variant an explicit discriminant in declaration order.
+        Some(repr) => {
+            let variants: Vec<Variant> = states_in_declaration_order(ir)
+                .into_iter()
+                .enumerate()
+                .map(|(index, state)| {
+                    let mut variant = state.variant.clone();
+                    let discriminant = Literal::usize_unsuffixed(index);
+                    variant.discriminant = Some((parse_quote!(=), parse_quote!(#discriminant)));
+                    variant
+                })
+                .collect();
+
+            parse_quote!(
+                #[derive(#(#state_derives),*)]
+                #[repr(#repr)]
+                # visibility enum #state_ident #state_generics {
+                    #(#variants),*
+                }
+            )
+        }
+    }
+}
+
+fn codegen_constructor_trait(ir: &Ir) -> Option<TokenStream> {
+    let constructor_trait = ir.state_machine.state_constructor_trait.as_ref()?;
+    let state_ident = &ir.state_machine.state_ident;
+    let visibility = &ir.state_machine.visibility;
+
+    let (signatures, calls): (Vec<Signature>, Vec<ImplItemMethod>) =
+        states_in_declaration_order(ir)
+            .into_iter()
+            .map(|state| {
+                let mut sig = state.constructor.sig.clone();
+                sig.constness = None;
+                let ident = &sig.ident;
+                let args = sig.inputs.iter().filter_map(|input| match input {
+                    FnArg::Typed(pat_type) => Some(&pat_type.pat),
+                    FnArg::Receiver(_) => None,
+                });
+                let call = parse_quote!(#sig {
+                    #state_ident::#ident(#(#args),*)
+                });
+                (sig, call)
+            })
+            .unzip();
+
+    let doc = format!("The constructors of the states of [`{state_ident}`].");
+
+    Some(quote!(
+        #[doc = #doc]
+        #visibility trait #constructor_trait: Sized {
+            #(#signatures;)*
+        }
+
+        impl #constructor_trait for #state_ident {
+            #(#calls)*
+        }
+    ))
+}
+
+/// Get the states in the order in which their handlers are declared.
+fn states_in_declaration_order(ir: &Ir) -> Vec<&State> {
+    ir.item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => ir.states.get(&method.sig.ident),
+            _ => None,
+        })
+        .collect()
 }
 
 fn codegen_state_impl(ir: &Ir) -> ItemImpl {
@@ -120,9 +389,136 @@ fn codegen_state_impl(ir: &Ir) -> ItemImpl {
         .cloned()
         .collect();
 
+    let superstate_name_arms: Vec<Arm> = ir
+        .states
+        .values()
+        .map(|state| {
+            let variant_ident = &state.variant.ident;
+            let superstate_name = &state.superstate_name;
+            parse_quote!(Self::#variant_ident { .. } => #superstate_name)
+        })
+        .collect();
+
+    let discriminant_fn: Option<ItemFn> = ir.state_machine.state_repr.as_ref().map(|repr| {
+        let discriminant_arms: Vec<Arm> = states_in_declaration_order(ir)
+            .into_iter()
+            .enumerate()
+            .map(|(index, state)| {
+                let variant_ident = &state.variant.ident;
+                let discriminant = Literal::usize_unsuffixed(index);
+                parse_quote!(Self::#variant_ident { .. } => #discriminant)
+            })
+            .collect();
+
+        parse_quote!(
+            /// Returns the discriminant of the state.
+            pub const fn discriminant(&self) -> #repr {
+                match self {
+                    #(#discriminant_arms),*
+                }
+            }
+        )
+    });
+
+    // A table of constructors for the child states of every superstate, so a superstate
+    // handler can pick a child by index.
+    let children_consts: Vec<ImplItemConst> = ir
+        .item_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Method(method) => ir
+                .superstates
+                .get(&method.sig.ident)
+                .map(|superstate| (&method.sig.ident, superstate)),
+            _ => None,
+        })
+        .filter(|(_, superstate)| !superstate.child_constructors.is_empty())
+        .map(|(handler_name, superstate)| {
+            let const_ident = format_ident!("{}_CHILDREN", handler_name.to_string().to_uppercase());
+            let child_constructors = &superstate.child_constructors;
+            let len = child_constructors.len();
+            let doc = format!(
+                "Constructors of the child states of the `{handler_name}` superstate that \
+                take no arguments, in the order they are declared."
+            );
+            parse_quote!(
+                #[doc = #doc]
+                pub const #const_ident: [fn() -> Self; #len] = [#(Self::#child_constructors),*];
+            )
+        })
+        .collect();
+
+    let step_arms: Option<Vec<Arm>> = ir
+        .states
+        .values()
+        .map(|state| {
+            let pat = &state.pat;
+            let step_call = state.const_step_call.as_ref()?;
+            let superstate_initial_state = &state.superstate_initial_state;
+            Some(parse_quote!(#pat => match #step_call {
+                statig::Response::Transition(target) => target,
+                statig::Response::TransitionToSuperstate => match #superstate_initial_state {
+                    Some(target) => target,
+                    None => self,
+                },
+                _ => self,
+            }))
+        })
+        .collect();
+
+    let step_fn: Option<ItemFn> = step_arms.map(|step_arms| {
+        let shared_storage_type = &ir.state_machine.shared_storage_type;
+        let event_ident = &ir.state_machine.event_ident;
+        parse_quote!(
+            /// Returns the state that is reached by handling the given event in this
+            /// state. Entry and exit actions are not run.
+            pub const fn step(
+                self,
+                #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>
+            ) -> Self {
+                match self {
+                    #(#step_arms),*
+                }
+            }
+        )
+    });
+
+    let terminal_pats: Vec<TokenStream> = ir
+        .states
+        .values()
+        .filter(|state| state.terminal)
+        .map(|state| {
+            let variant_ident = &state.variant.ident;
+            quote!(Self::#variant_ident { .. })
+        })
+        .collect();
+    let is_final_body = match terminal_pats.is_empty() {
+        true => quote!(false),
+        false => quote!(matches!(self, #(#terminal_pats)|*)),
+    };
+
     parse_quote!(
         impl #impl_generics #state_ident #state_generics {
             #(#constructors)*
+
+            #(#children_consts)*
+
+            #step_fn
+
+            /// Returns the name of the superstate of the state, if it has one.
+            pub const fn superstate_name(&self) -> Option<&'static str> {
+                match self {
+                    #(#superstate_name_arms),*
+                }
+            }
+
+            /// Returns `true` if the state is a terminal state.
+            pub const fn is_final(&self) -> bool {
+                #is_final_body
+            }
+
+            #discriminant_fn
         }
     )
 }
@@ -140,31 +536,203 @@ fn codegen_state_impl_state(ir: &Ir) -> ItemImpl {
     let mut call_handler_arms: Vec<Arm> = Vec::new();
     let mut call_entry_action_arms: Vec<Arm> = Vec::new();
     let mut call_exit_action_arms: Vec<Arm> = Vec::new();
+    let mut try_call_entry_action_arms: Vec<Arm> = Vec::new();
     let mut superstate_arms: Vec<Arm> = Vec::new();
     let mut same_state_arms: Vec<Arm> = Vec::new();
+    let mut is_terminal_arms: Vec<Arm> = Vec::new();
+    let mut superstate_initial_state_arms: Vec<Arm> = Vec::new();
 
     for state in ir.states.values() {
         let pat = &state.pat;
-        let handler_call = &state.handler_call;
         let entry_action_call = &state.entry_action_call;
         let exit_action_call = &state.exit_action_call;
         let superstate_pat = &state.superstate_pat;
 
         constructors.push(state.constructor.clone());
-        call_handler_arms.push(parse_quote!(#pat => #handler_call));
+        call_handler_arms.push(codegen_call_handler_arm(ir, state, quote!(self)));
         call_entry_action_arms.push(parse_quote!(#pat => #entry_action_call));
         call_exit_action_arms.push(parse_quote!(#pat => #exit_action_call));
         superstate_arms.push(parse_quote!(#pat => #superstate_pat));
+
+        match &state.entry_veto_call {
+            Some(entry_veto_call) => {
+                try_call_entry_action_arms.push(parse_quote!(#pat => #entry_veto_call))
+            }
+            None => try_call_entry_action_arms.push(parse_quote!(#pat => {
+                #entry_action_call;
+                true
+            })),
+        }
+
+        let variant_ident = &state.variant.ident;
+        let superstate_initial_state = &state.superstate_initial_state;
+        superstate_initial_state_arms
+            .push(parse_quote!(#state_ident::#variant_ident { .. } => #superstate_initial_state));
+
+        if state.terminal {
+            is_terminal_arms.push(parse_quote!(#state_ident::#variant_ident { .. } => true));
+        }
     }
 
     call_handler_arms.push(parse_quote!(_ => statig::Response::Super));
     call_entry_action_arms.push(parse_quote!(_ => {}));
     call_exit_action_arms.push(parse_quote!(_ => {}));
+    try_call_entry_action_arms.push(parse_quote!(_ => true));
     superstate_arms.push(parse_quote!(_ => None));
     same_state_arms.push(parse_quote!(_ => false));
+    is_terminal_arms.push(parse_quote!(_ => false));
+    superstate_initial_state_arms.push(parse_quote!(_ => None));
+
+    let call_handler_body = match ir.state_machine.jump_table {
+        false => quote!(
+            match self {
+                #(#call_handler_arms),*
+            }
+        ),
+        true => codegen_jump_table(ir),
+    };
+
+    // When a handler takes the event as `&mut`, the handlers can only be called through
+    // `call_handler_mut`.
+    let (call_handler_body, call_handler_mut) = match ir.state_machine.mut_event {
+        false => (call_handler_body, None),
+        true => {
+            let call_handler_mut: ImplItemMethod = parse_quote!(
+                fn call_handler_mut(
+                    &mut self,
+                    shared_storage: &mut #shared_storage_type,
+                    #event_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                    #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
+                ) -> statig::Response<Self> where Self: Sized {
+                    #call_handler_body
+                }
+            );
+            (codegen_mut_event_unreachable(), Some(call_handler_mut))
+        }
+    };
+
+    // The effects that are being collected are handed to the handlers as the effects
+    // type of the state machine.
+    let has_effects = ir.state_machine.effects_type.is_some();
+    let downcast_effects = has_effects.then(|| {
+        quote!(
+            let mut collected_effects = collected_effects.and_then(|collected_effects| {
+                collected_effects.downcast_mut::<<#shared_storage_type as statig::HasEffects>::Effects>()
+            });
+        )
+    });
+    let has_entry_count = ir.states.values().any(|state| state.entry_count);
+
+    // When states take their entry count, the handlers are called through
+    // `call_handler_with_entry_count`, which is given the entry count of the current state
+    // and the effects that are being collected.
+    let call_handler_with_entry_count: Option<ImplItemMethod> = has_entry_count.then(|| {
+        parse_quote!(
+            fn call_handler_with_entry_count(
+                &mut self,
+                shared_storage: &mut #shared_storage_type,
+                #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+                collected_effects: Option<&mut dyn core::any::Any>,
+                entry_count: u32
+            ) -> statig::Response<Self> where Self: Sized {
+                #downcast_effects
+                #call_handler_body
+            }
+        )
+    });
+
+    // When handlers return effects, they are called through `call_handler_with_effects`,
+    // which is given the effects that are being collected.
+    let call_handler_with_effects: Option<ImplItemMethod> = has_effects.then(|| {
+        let call_handler_with_effects_body = match has_entry_count {
+            true => quote!(self.call_handler_with_entry_count(shared_storage, #event_ident, #context_ident, collected_effects, 0)),
+            false => quote!(
+                #downcast_effects
+                #call_handler_body
+            ),
+        };
+        parse_quote!(
+            fn call_handler_with_effects(
+                &mut self,
+                shared_storage: &mut #shared_storage_type,
+                #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+                collected_effects: Option<&mut dyn core::any::Any>
+            ) -> statig::Response<Self> where Self: Sized {
+                #call_handler_with_effects_body
+            }
+        )
+    });
+
+    let call_handler_body = match (has_entry_count, has_effects) {
+        (true, _) => {
+            quote!(self.call_handler_with_entry_count(shared_storage, #event_ident, #context_ident, None, 0))
+        }
+        (false, true) => {
+            quote!(self.call_handler_with_effects(shared_storage, #event_ident, #context_ident, None))
+        }
+        (false, false) => call_handler_body,
+    };
+
+    // Every state that takes its entry count gets its own counter, in declaration order.
+    let entry_counter_arms: Vec<Arm> = states_in_declaration_order(ir)
+        .into_iter()
+        .filter(|state| state.entry_count)
+        .enumerate()
+        .map(|(index, state)| {
+            let variant_ident = &state.variant.ident;
+            parse_quote!(#state_ident::#variant_ident { .. } => Some(#index))
+        })
+        .collect();
+    let entry_counter: Option<ImplItemMethod> = (!entry_counter_arms.is_empty()).then(|| {
+        parse_quote!(
+            fn entry_counter(&self) -> Option<usize> {
+                match self {
+                    #(#entry_counter_arms,)*
+                    _ => None,
+                }
+            }
+        )
+    });
+
+    // Only states with an entry action that returns a `bool` can veto being entered, so
+    // the default implementation is kept when there are none.
+    let has_entry_veto = ir
+        .states
+        .values()
+        .any(|state| state.entry_veto_call.is_some());
 
     match ir.state_machine.mode {
         Mode::Blocking => {
+            let try_call_entry_action: Option<ImplItemMethod> = has_entry_veto.then(|| {
+                parse_quote!(
+                    fn try_call_entry_action(
+                        &mut self,
+                        shared_storage: &mut #shared_storage_type,
+                        #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
+                    ) -> bool {
+                        match self {
+                            #(#try_call_entry_action_arms),*
+                        }
+                    }
+                )
+            });
+
+            let call_catch_all: Option<ImplItemMethod> =
+                ir.state_machine.catch_all_call.as_ref().map(|catch_all_call| {
+                    parse_quote!(
+                        fn call_catch_all(
+                            &mut self,
+                            shared_storage: &mut #shared_storage_type,
+                            #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                            #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
+                        ) -> statig::Response<Self> {
+                            #catch_all_call
+                        }
+                    )
+                });
+
             parse_quote!(
                 #[allow(unused)]
                 impl #impl_generics statig::blocking::State<#shared_storage_type> for #state_ident #state_generics #where_clause
@@ -175,11 +743,17 @@ fn codegen_state_impl_state(ir: &Ir) -> ItemImpl {
                         #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
                         #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
                     ) -> statig::Response<Self> where Self: Sized {
-                        match self {
-                            #(#call_handler_arms),*
-                        }
+                        #call_handler_body
                     }
 
+                    #call_handler_with_effects
+
+                    #call_handler_with_entry_count
+
+                    #call_handler_mut
+
+                    #call_catch_all
+
                     fn call_entry_action(
                         &mut self,
                         shared_storage: &mut #shared_storage_type,
@@ -190,6 +764,8 @@ fn codegen_state_impl_state(ir: &Ir) -> ItemImpl {
                         }
                     }
 
+                    #try_call_entry_action
+
                     fn call_exit_action(
                         &mut self,
                         shared_storage: &mut #shared_storage_type,
@@ -205,10 +781,113 @@ fn codegen_state_impl_state(ir: &Ir) -> ItemImpl {
                             #(#superstate_arms),*
                         }
                     }
+
+                    fn superstate_initial_state(&self) -> Option<Self> {
+                        match self {
+                            #(#superstate_initial_state_arms),*
+                        }
+                    }
+
+                    fn is_terminal(&self) -> bool {
+                        match self {
+                            #(#is_terminal_arms),*
+                        }
+                    }
+
+                    #entry_counter
                 }
             )
         }
-        Mode::Awaitable => parse_quote!(
+        Mode::Awaitable => {
+            let async_call_handler_body = quote!(statig::__private::Box::pin(async move {
+                match self {
+                    #(#call_handler_arms),*
+                }
+            }));
+
+            // When states take their entry count, the handlers are called through
+            // `call_handler_with_entry_count`, which is given the entry count of the current
+            // state and the effects that are being collected.
+            let async_call_handler_with_entry_count: Option<ImplItemMethod> = has_entry_count.then(|| {
+                parse_quote!(
+                    fn call_handler_with_entry_count<'fut>(
+                        &'fut mut self,
+                        shared_storage: &'fut mut #shared_storage_type,
+                        #event_ident: &'fut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                        #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+                        collected_effects: Option<&'fut mut (dyn core::any::Any + Send)>,
+                        entry_count: u32
+                    ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = statig::Response<Self>> + 'fut + Send>> {
+                        #downcast_effects
+                        #async_call_handler_body
+                    }
+                )
+            });
+
+            // When handlers return effects, they are called through
+            // `call_handler_with_effects`, which is given the effects that are being collected.
+            let async_call_handler_with_effects: Option<ImplItemMethod> = has_effects.then(|| {
+                let call_handler_with_effects_body = match has_entry_count {
+                    true => quote!(self.call_handler_with_entry_count(shared_storage, #event_ident, #context_ident, collected_effects, 0)),
+                    false => quote!(
+                        #downcast_effects
+                        #async_call_handler_body
+                    ),
+                };
+                parse_quote!(
+                    fn call_handler_with_effects<'fut>(
+                        &'fut mut self,
+                        shared_storage: &'fut mut #shared_storage_type,
+                        #event_ident: &'fut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                        #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+                        collected_effects: Option<&'fut mut (dyn core::any::Any + Send)>
+                    ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = statig::Response<Self>> + 'fut + Send>> {
+                        #call_handler_with_effects_body
+                    }
+                )
+            });
+
+            let async_call_handler_body = match (has_entry_count, has_effects) {
+                (true, _) => {
+                    quote!(self.call_handler_with_entry_count(shared_storage, #event_ident, #context_ident, None, 0))
+                }
+                (false, true) => {
+                    quote!(self.call_handler_with_effects(shared_storage, #event_ident, #context_ident, None))
+                }
+                (false, false) => async_call_handler_body,
+            };
+
+            let try_call_entry_action: Option<ImplItemMethod> = has_entry_veto.then(|| {
+                parse_quote!(
+                    fn try_call_entry_action<'fut>(
+                        &'fut mut self,
+                        shared_storage: &'fut mut #shared_storage_type,
+                        #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
+                    ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = bool> + 'fut + Send>> {
+                        statig::__private::Box::pin(async move {
+                            match self {
+                                #(#try_call_entry_action_arms),*
+                            }
+                        })
+                    }
+                )
+            });
+            let call_catch_all: Option<ImplItemMethod> =
+                ir.state_machine.catch_all_call.as_ref().map(|catch_all_call| {
+                    parse_quote!(
+                        fn call_catch_all<'fut>(
+                            &'fut mut self,
+                            shared_storage: &'fut mut #shared_storage_type,
+                            #event_ident: &'fut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                            #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
+                        ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = statig::Response<Self>> + 'fut + Send>> {
+                            statig::__private::Box::pin(async move {
+                                #catch_all_call
+                            })
+                        }
+                    )
+                });
+            parse_quote!(
             #[allow(unused)]
             impl #impl_generics statig::awaitable::State<#shared_storage_type> for #state_ident #state_generics #where_clause
             {
@@ -217,32 +896,36 @@ fn codegen_state_impl_state(ir: &Ir) -> ItemImpl {
                     shared_storage: &'fut mut #shared_storage_type,
                     #event_ident: &'fut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
                     #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
-                ) -> core::pin::Pin<std::boxed::Box<dyn core::future::Future<Output = statig::Response<Self>> + 'fut + Send>> {
-                    Box::pin(async move {
-                        match self {
-                            #(#call_handler_arms),*
-                        }
-                    })
+                ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = statig::Response<Self>> + 'fut + Send>> {
+                    #async_call_handler_body
                 }
 
+                #async_call_handler_with_effects
+
+                #async_call_handler_with_entry_count
+
+                #call_catch_all
+
                 fn call_entry_action<'fut>(
                     &'fut mut self,
                     shared_storage: &'fut mut #shared_storage_type,
                     #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
-                ) -> core::pin::Pin<std::boxed::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
-                    Box::pin(async move {
+                ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
+                    statig::__private::Box::pin(async move {
                         match self {
                             #(#call_entry_action_arms),*
                         }
                     })
                 }
 
+                #try_call_entry_action
+
                 fn call_exit_action<'fut>(
                     &'fut mut self,
                     shared_storage: &'fut mut #shared_storage_type,
                     #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
-                ) -> core::pin::Pin<std::boxed::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
-                    Box::pin(async move {
+                ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
+                    statig::__private::Box::pin(async move {
                         match self {
                             #(#call_exit_action_arms),*
                         }
@@ -254,9 +937,122 @@ fn codegen_state_impl_state(ir: &Ir) -> ItemImpl {
                         #(#superstate_arms),*
                     }
                 }
+
+                fn superstate_initial_state(&self) -> Option<Self> {
+                    match self {
+                        #(#superstate_initial_state_arms),*
+                    }
+                }
+
+                fn is_terminal(&self) -> bool {
+                    match self {
+                        #(#is_terminal_arms),*
+                    }
+                }
+
+                #entry_counter
             }
+            )
+        }
+    }
+}
+
+/// Generate the arm of `call_handler` that calls the handler of the given state, where
+/// `scrutinee` is the mutable reference to the state that is matched on.
+fn codegen_call_handler_arm(ir: &Ir, state: &State, scrutinee: TokenStream) -> Arm {
+    let pat = &state.pat;
+    let handler_call = &state.handler_call;
+
+    match state.borrows_state {
+        // Instead of binding the local storage mutably, the state is reborrowed immutably
+        // so it can be passed to the handler together with its local storage.
+        true => {
+            let state_ident = &ir.state_machine.state_ident;
+            let variant_ident = &state.variant.ident;
+            parse_quote!(#state_ident::#variant_ident { .. } => {
+                let state: &#state_ident = &*#scrutinee;
+                match state {
+                    #pat => #handler_call,
+                    _ => unreachable!(),
+                }
+            })
+        }
+        false => parse_quote!(#pat => #handler_call),
+    }
+}
+
+/// Generate the body of `call_handler` that looks up the handler of the current state
+/// in a table of function pointers instead of matching on every state.
+fn codegen_jump_table(ir: &Ir) -> TokenStream {
+    let shared_storage_type = &ir.state_machine.shared_storage_type;
+    let state_ident = &ir.state_machine.state_ident;
+    let event_ident = &ir.state_machine.event_ident;
+    let context_ident = &ir.state_machine.context_ident;
+
+    let states: Vec<_> = ir.states.values().collect();
+    let state_count = states.len();
+
+    // When handlers return effects, the handlers are also given the collected effects.
+    let (effects_input, effects_param, effects_arg) = match ir.state_machine.effects_type.is_some()
+    {
+        false => (quote!(), quote!(), quote!()),
+        true => (
+            quote!(, mut collected_effects),
+            quote!(Option<&mut <#shared_storage_type as statig::HasEffects>::Effects>,),
+            quote!(, collected_effects),
         ),
+    };
+
+    // When states take their entry count, the handlers are also given the entry count.
+    let (entry_count_input, entry_count_param, entry_count_arg) =
+        match ir.states.values().any(|state| state.entry_count) {
+            false => (quote!(), quote!(), quote!()),
+            true => (quote!(, entry_count), quote!(u32,), quote!(, entry_count)),
+        };
+
+    let mut index_arms: Vec<Arm> = Vec::new();
+    let mut handlers: Vec<TokenStream> = Vec::new();
+
+    for (index, state) in states.iter().enumerate() {
+        let variant_ident = &state.variant.ident;
+
+        let call_handler_arm = codegen_call_handler_arm(ir, state, quote!(state));
+
+        index_arms.push(parse_quote!(#state_ident::#variant_ident { .. } => #index));
+        handlers.push(quote!(
+            |state, shared_storage, #event_ident, #context_ident #effects_input #entry_count_input| match state {
+                #call_handler_arm,
+                _ => unreachable!(),
+            }
+        ));
     }
+
+    quote!(
+        type Handler = fn(
+            &mut #state_ident,
+            &mut #shared_storage_type,
+            &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+            &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+            #effects_param
+            #entry_count_param
+        ) -> statig::Response<#state_ident>;
+
+        const HANDLERS: [Handler; #state_count] = [#(#handlers),*];
+
+        let index = match self {
+            #(#index_arms),*
+        };
+
+        HANDLERS[index](self, shared_storage, #event_ident, #context_ident #effects_arg #entry_count_arg)
+    )
+}
+
+/// Body of `call_handler` for state machines that take the event as `&mut`. These
+/// state machines must be driven with `handle_mut`, which calls `call_handler_mut`.
+fn codegen_mut_event_unreachable() -> TokenStream {
+    quote!(unreachable!(
+        "handlers take the event as `&mut`, so the state machine must be driven with `handle_mut`"
+    ))
 }
 
 fn codegen_superstate(ir: &Ir) -> ItemEnum {
@@ -279,6 +1075,65 @@ fn codegen_superstate(ir: &Ir) -> ItemEnum {
     )
 }
 
+fn codegen_superstate_impl(ir: &Ir) -> ItemImpl {
+    let superstate_ident = &ir.state_machine.superstate_ident;
+    let (impl_generics, superstate_generics, where_clause) =
+        &ir.state_machine.superstate_generics.split_for_impl();
+
+    let children_arms: Vec<Arm> = ir
+        .superstates
+        .values()
+        .map(|superstate| {
+            let variant_ident = &superstate.variant.ident;
+            let children = &superstate.children;
+            parse_quote!(Self::#variant_ident { .. } => &[#(#children),*])
+        })
+        .collect();
+
+    // When none of the superstates borrow any storage, every value of the superstate
+    // enum is known, so they can be listed.
+    let variants_consts: Option<TokenStream> =
+        (ir.state_machine.superstate_generics.params.is_empty()
+            && ir
+                .superstates
+                .values()
+                .all(|superstate| superstate.variant.fields.is_empty()))
+        .then(|| {
+            let variant_idents: Vec<&Ident> = ir
+                .item_impl
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    ImplItem::Method(method) => ir.superstates.get(&method.sig.ident),
+                    _ => None,
+                })
+                .map(|superstate| &superstate.variant.ident)
+                .collect();
+            let count = variant_idents.len();
+            quote!(
+                /// The number of superstates.
+                pub const COUNT: usize = #count;
+
+                /// All the superstates, in the order in which their handlers are declared.
+                pub const VARIANTS: [Self; #count] = [#(Self::#variant_idents {}),*];
+            )
+        });
+
+    parse_quote!(
+        impl #impl_generics #superstate_ident #superstate_generics #where_clause {
+            #variants_consts
+
+            /// Returns the names of the states and superstates that have this superstate
+            /// as their parent.
+            pub const fn children(&self) -> &'static [&'static str] {
+                match *self {
+                    #(#children_arms),*
+                }
+            }
+        }
+    )
+}
+
 fn codegen_superstate_impl_superstate(ir: &Ir) -> ItemImpl {
     let shared_storage_type = &ir.state_machine.shared_storage_type;
     let mut shared_storage_generics = ir.state_machine.shared_storage_generics.clone();
@@ -323,6 +1178,59 @@ fn codegen_superstate_impl_superstate(ir: &Ir) -> ItemImpl {
     superstate_arms.push(parse_quote!(_ => None));
     same_state_arms.push(parse_quote!(_ => false));
 
+    let call_handler_body = quote!(
+        match self {
+            #(#call_handler_arms),*
+        }
+    );
+
+    let (call_handler_body, call_handler_mut) = match ir.state_machine.mut_event {
+        false => (call_handler_body, None),
+        true => {
+            let call_handler_mut: ImplItemMethod = parse_quote!(
+                fn call_handler_mut(
+                    &mut self,
+                    shared_storage: &mut #shared_storage_type,
+                    #event_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                    #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
+                ) -> statig::Response<<#shared_storage_type as statig::IntoStateMachine>::State> where Self: Sized {
+                    #call_handler_body
+                }
+            );
+            (codegen_mut_event_unreachable(), Some(call_handler_mut))
+        }
+    };
+
+    // When handlers return effects, they are called through `call_handler_with_effects`,
+    // which is given the effects that are being collected.
+    let (call_handler_body, call_handler_with_effects) = match ir
+        .state_machine
+        .effects_type
+        .is_some()
+    {
+        false => (call_handler_body, None),
+        true => {
+            let call_handler_with_effects: ImplItemMethod = parse_quote!(
+                fn call_handler_with_effects(
+                    &mut self,
+                    shared_storage: &mut #shared_storage_type,
+                    #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                    #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+                    collected_effects: Option<&mut dyn core::any::Any>
+                ) -> statig::Response<<#shared_storage_type as statig::IntoStateMachine>::State> where Self: Sized {
+                    let mut collected_effects = collected_effects.and_then(|collected_effects| {
+                        collected_effects.downcast_mut::<<#shared_storage_type as statig::HasEffects>::Effects>()
+                    });
+                    #call_handler_body
+                }
+            );
+            (
+                quote!(self.call_handler_with_effects(shared_storage, #event_ident, #context_ident, None)),
+                Some(call_handler_with_effects),
+            )
+        }
+    };
+
     match ir.state_machine.mode {
         Mode::Blocking => {
             parse_quote!(
@@ -335,11 +1243,13 @@ fn codegen_superstate_impl_superstate(ir: &Ir) -> ItemImpl {
                         #event_ident: &<#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
                         #context_ident: &mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
                     ) -> statig::Response<<#shared_storage_type as statig::IntoStateMachine>::State> where Self: Sized {
-                        match self {
-                            #(#call_handler_arms),*
-                        }
+                        #call_handler_body
                     }
 
+                    #call_handler_with_effects
+
+                    #call_handler_mut
+
                     fn call_entry_action(
                         &mut self,
                         shared_storage: &mut #shared_storage_type,
@@ -369,6 +1279,47 @@ fn codegen_superstate_impl_superstate(ir: &Ir) -> ItemImpl {
             )
         }
         Mode::Awaitable => {
+            // When handlers return effects, they are called through
+            // `call_handler_with_effects`, which is given the effects that are being collected.
+            let (async_call_handler_body, async_call_handler_with_effects) = match ir
+                .state_machine
+                .effects_type
+                .is_some()
+            {
+                false => (
+                    quote!(statig::__private::Box::pin(async move {
+                        match self {
+                            #(#call_handler_arms),*
+                        }
+                    })),
+                    None,
+                ),
+                true => {
+                    let call_handler_with_effects: ImplItemMethod = parse_quote!(
+                        fn call_handler_with_effects<'fut>(
+                            &'fut mut self,
+                            shared_storage: &'fut mut #shared_storage_type,
+                            #event_ident: &'fut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
+                            #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>,
+                            collected_effects: Option<&'fut mut (dyn core::any::Any + Send)>
+                        ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = statig::Response<<#shared_storage_type as statig::IntoStateMachine>::State>> + 'fut + Send>> {
+                            let mut collected_effects = collected_effects.and_then(|collected_effects| {
+                                collected_effects.downcast_mut::<<#shared_storage_type as statig::HasEffects>::Effects>()
+                            });
+                            statig::__private::Box::pin(async move {
+                                match self {
+                                    #(#call_handler_arms),*
+                                }
+                            })
+                        }
+                    );
+                    (
+                        quote!(self.call_handler_with_effects(shared_storage, #event_ident, #context_ident, None)),
+                        Some(call_handler_with_effects),
+                    )
+                }
+            };
+
             parse_quote!(
                 #[allow(unused)]
                 impl #impl_generics statig::awaitable::Superstate<#shared_storage_type> for #superstate_ident #superstate_generics #where_clause
@@ -378,20 +1329,18 @@ fn codegen_superstate_impl_superstate(ir: &Ir) -> ItemImpl {
                         shared_storage: &'fut mut #shared_storage_type,
                         #event_ident: &'fut <#shared_storage_type as statig::IntoStateMachine>::Event<'_>,
                         #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
-                    ) -> core::pin::Pin<std::boxed::Box<dyn core::future::Future<Output = statig::Response<<#shared_storage_type as statig::IntoStateMachine>::State>> + 'fut + Send>> {
-                        Box::pin(async move {
-                            match self {
-                                #(#call_handler_arms),*
-                            }
-                        })
+                    ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = statig::Response<<#shared_storage_type as statig::IntoStateMachine>::State>> + 'fut + Send>> {
+                        #async_call_handler_body
                     }
 
+                    #async_call_handler_with_effects
+
                     fn call_entry_action<'fut>(
                         &'fut mut self,
                         shared_storage: &'fut mut #shared_storage_type,
                         #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
-                    ) -> core::pin::Pin<std::boxed::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
-                        Box::pin(async move {
+                    ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
+                        statig::__private::Box::pin(async move {
                             match self {
                                 #(#call_entry_action_arms),*
                             }
@@ -402,8 +1351,8 @@ fn codegen_superstate_impl_superstate(ir: &Ir) -> ItemImpl {
                         &'fut mut self,
                         shared_storage: &'fut mut #shared_storage_type,
                         #context_ident: &'fut mut <#shared_storage_type as statig::IntoStateMachine>::Context<'_>
-                    ) -> core::pin::Pin<std::boxed::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
-                        Box::pin(async move {
+                    ) -> core::pin::Pin<statig::__private::Box<dyn core::future::Future<Output = ()> + 'fut + Send>> {
+                        statig::__private::Box::pin(async move {
                             match self {
                                 #(#call_exit_action_arms),*
                             }
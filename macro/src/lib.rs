@@ -19,6 +19,9 @@ use parse::{parse_args, parse_input};
 const SUPERSTATE_LIFETIME: &str = "'sub";
 const EVENT_LIFETIME: &str = "'event";
 const CONTEXT_LIFETIME: &str = "'context";
+const STATE_ARG: &str = "state";
+const ENTRY_COUNT_ARG: &str = "entry_count";
+const SHARED_STORAGE_IDENT: &str = "shared_storage";
 
 #[proc_macro_error]
 #[proc_macro_attribute]
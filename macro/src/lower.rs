@@ -2,20 +2,21 @@ use std::collections::HashMap;
 use std::ops::Deref;
 
 use proc_macro2::Span;
-use proc_macro_error::abort;
+use proc_macro_error::{abort, abort_call_site};
 
 use syn::parse::Parser;
 use syn::parse_quote;
 use syn::{
-    Expr, ExprCall, Field, FnArg, GenericParam, Generics, Ident, ItemFn, ItemImpl, Lifetime, Pat,
-    PatType, Path, Type, Variant, Visibility, WhereClause, WherePredicate,
+    Arm, Expr, Field, FieldValue, FnArg, GenericArgument, GenericParam, Generics, Ident, ImplItem,
+    ItemFn, ItemImpl, Lifetime, Pat, PatType, Path, PathArguments, Receiver, Stmt, Type, Variant,
+    Visibility, WhereClause, WherePredicate,
 };
 
 use quote::format_ident;
 
 use crate::analyze;
 use crate::analyze::Model;
-use crate::visitors::{GenericParamVisitor, LifetimeVisitor};
+use crate::visitors::{GenericParamVisitor, LifetimeVisitor, SelfPathVisitor};
 use crate::SUPERSTATE_LIFETIME;
 
 /// Intermediate representation of the state machine.
@@ -35,19 +36,30 @@ pub struct Ir {
 /// General information regarding the state machine.
 pub struct StateMachine {
     /// Initial state.
-    pub initial_state: ExprCall,
+    pub initial_state: Expr,
     /// The type on which the state machine is implemented.
     pub shared_storage_type: Type,
     /// The generics associated with the shared storage type.
     pub shared_storage_generics: Generics,
     /// The type of the event.
     pub event_type: Type,
+    /// Whether a handler takes the event as a mutable reference.
+    pub mut_event: bool,
     /// The type of the context.
     pub context_type: Type,
+    /// The type that the effects returned by the handlers are collected in, when a
+    /// handler returns effects next to its response.
+    pub effects_type: Option<Type>,
     /// The type of the state enum.
     pub state_ident: Ident,
     /// Derives that will be applied on the state type.
     pub state_derives: Vec<Path>,
+    /// Whether handlers are dispatched through a table of function pointers.
+    pub jump_table: bool,
+    /// Optional integer representation of the state type.
+    pub state_repr: Option<Ident>,
+    /// Optional name of a trait with the constructors of the states.
+    pub state_constructor_trait: Option<Ident>,
     /// The generics associated with the state type.
     pub state_generics: Generics,
     /// The type of the superstate enum (ex. `Superstate<'sub>`)
@@ -60,6 +72,17 @@ pub struct StateMachine {
     pub on_transition: Option<Path>,
     /// The path of the `on_dispatch` callback.
     pub on_dispatch: Option<Path>,
+    /// The path of the `on_snapshot` callback.
+    pub on_snapshot: Option<Path>,
+    /// Events that can be created from a raw tag, indexed by tag.
+    pub ffi_events: Vec<Expr>,
+    /// The call to the catch-all handler, which defers when the catch-all state is the
+    /// current state (e.g. `match self { State::Unknown {} => Super, _ => ... }`).
+    pub catch_all_call: Option<Expr>,
+    /// Whether unhandled events must be dealt with by the caller.
+    pub require_handled: bool,
+    /// The event variants matched by the handlers, if event coverage is checked.
+    pub event_coverage: Option<EventCoverage>,
     /// The visibility for the derived types,
     pub visibility: Visibility,
     /// The external input pattern.
@@ -68,6 +91,9 @@ pub struct StateMachine {
     pub context_ident: Ident,
     /// Whether the state machine is sync (blocking) or async (awaitable).
     pub mode: Mode,
+    /// The only async function, when it makes an otherwise blocking state machine
+    /// async.
+    pub single_async_function: Option<Ident>,
 }
 
 /// Information regarding a state.
@@ -88,12 +114,34 @@ pub struct State {
     /// The call to the exit action of the state, if defined
     /// (e.g. `Blinky::exit_on(shared_storage, led)`, `{}`, ..).
     pub exit_action_call: Expr,
+    /// The call to the entry action of the state if it can veto entering the state
+    /// (e.g. `Some(Blinky::try_enter_on(shared_storage, led))`, `None`, ..).
+    pub entry_veto_call: Option<Expr>,
     /// The pattern to create the superstate variant.
     /// (e.g. `Some(Superstate::Playing { led })`, `None`, ..).
     pub superstate_pat: Pat,
     /// The constructor to create the state
     /// (e.g. `const fn on(led: bool) -> Self { Self::On { led }}`).
     pub constructor: ItemFn,
+    /// Whether the state is a terminal state.
+    pub terminal: bool,
+    /// Whether the state handler takes a reference to the current state.
+    pub borrows_state: bool,
+    /// Whether the state handler takes the number of times the state has been entered.
+    pub entry_count: bool,
+    /// The name of the superstate of the state, if it has one.
+    /// (e.g. `Some("playing")`, `None`, ..).
+    pub superstate_name: Expr,
+    /// The initial state of the superstate of the state, if it has one.
+    /// (e.g. `Some(State::on())`, `None`, ..).
+    pub superstate_initial_state: Expr,
+    /// The call to the state handler followed by the handlers of its superstates, if
+    /// the state machine can be stepped in a const context.
+    /// (e.g. `match Blinky::on(input) { statig::Response::Super => .., response => response }`).
+    pub const_step_call: Option<Expr>,
+    /// The call to the state handler followed by the handlers of its superstates, if
+    /// the handlers only need shared access to the shared storage and the state.
+    pub pure_call: Option<Expr>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -116,6 +164,12 @@ pub struct Superstate {
     /// The pattern to create the superstate variant.
     /// (e.g. `Some(Superstate::Playing { led })`, `None`, ..).
     pub superstate_pat: Expr,
+    /// The names of the states and superstates that have this superstate as their parent.
+    /// (e.g. `["on", "off"]`).
+    pub children: Vec<String>,
+    /// The constructors of the child states that take no arguments
+    /// (e.g. `[on, off]`).
+    pub child_constructors: Vec<Ident>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -123,6 +177,17 @@ pub struct Action {
     /// The call to the action.
     /// (e.g. `Blinky::exit_off(shared_storage, led)`)
     pub handler_call: Expr,
+    /// Whether the action returns a `bool` that can veto entering the state.
+    pub is_vetoable: bool,
+}
+
+/// The event variants that are matched by at least one handler.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EventCoverage {
+    /// The path of the event enum without its generic arguments (e.g. `Event`).
+    pub event_path: Path,
+    /// The matched variants (e.g. `[TimerElapsed, ButtonPressed]`).
+    pub variants: Vec<Ident>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -133,16 +198,44 @@ pub enum Mode {
 
 pub fn lower(model: &Model) -> Ir {
     let item_impl = model.item_impl.clone();
-    let initial_state = model.state_machine.initial_state.clone();
+    // The initial state is either given directly or is the initial state of a superstate.
+    let initial_state = match &model.state_machine.initial_superstate {
+        Some(initial_superstate) => match model.superstates.get(initial_superstate) {
+            Some(superstate) => match &superstate.initial {
+                Some(initial) => Expr::Call(initial.clone()),
+                None => abort!(
+                    initial_superstate,
+                    "superstate has no initial state";
+                    help = "add an initial state to the superstate `#[superstate(initial = \"State::initial_state()\")]`"
+                ),
+            },
+            None => abort!(initial_superstate, "superstate not found"),
+        },
+        None => match &model.state_machine.initial_state {
+            Some(initial_state) => initial_state.clone(),
+            None => abort_call_site!("no initial state defined"),
+        },
+    };
+    let mut initial_state = resolve_initial_state(&initial_state, model, &mut Vec::new());
+    // Paths relative to `Self` refer to the shared storage type, but end up in the impl
+    // blocks of other types.
+    let mut self_path_visitor = SelfPathVisitor::new(&model.state_machine.shared_storage_type);
+    self_path_visitor.replace_expr(&mut initial_state);
     let state_ident = model.state_machine.state_ident.clone();
     let superstate_ident = model.state_machine.superstate_ident.clone();
     let on_transition = model.state_machine.on_transition.clone();
     let on_dispatch = model.state_machine.on_dispatch.clone();
+    let on_snapshot = model.state_machine.on_snapshot.clone();
+    let ffi_events = model.state_machine.ffi_events.clone();
+    let require_handled = model.state_machine.require_handled;
     let event_ident = model.state_machine.event_ident.clone();
     let context_ident = model.state_machine.context_ident.clone();
     let shared_storage_type = model.state_machine.shared_storage_type.clone();
     let shared_storage_generics = model.state_machine.shared_storage_generics.clone();
     let state_derives = model.state_machine.state_derives.clone();
+    let jump_table = model.state_machine.jump_table;
+    let state_repr = model.state_machine.state_repr.clone();
+    let state_constructor_trait = model.state_machine.state_constructor_trait.clone();
     let superstate_derives = model.state_machine.superstate_derives.clone();
     let visibility = model.state_machine.visibility.clone();
 
@@ -186,6 +279,32 @@ pub fn lower(model: &Model) -> Ir {
                 }
                 None => abort!(superstate, "superstate not found"),
             }
+            // The superstate borrows its fields from the state, but lazy storage is
+            // wrapped in an `Option` so it can't be passed on.
+            let shared_lazy_storage = model.states[key].lazy_storage.iter().find(|storage| {
+                model.superstates[superstate]
+                    .state_inputs
+                    .iter()
+                    .any(|pat_type| fn_arg_to_state_field(pat_type).ident == storage.field.ident)
+            });
+            if let Some(storage) = shared_lazy_storage {
+                abort!(
+                    storage.field.ident,
+                    "lazy storage can not be shared with a superstate";
+                    help = "declare the storage with `local_storage` instead"
+                );
+            }
+            let superstate_name = superstate.to_string();
+            state.superstate_name = parse_quote!(Some(#superstate_name));
+            if let Some(initial) = model
+                .superstates
+                .get(superstate)
+                .and_then(|superstate| superstate.initial.as_ref())
+            {
+                let mut initial = Expr::Call(initial.clone());
+                self_path_visitor.replace_expr(&mut initial);
+                state.superstate_initial_state = parse_quote!(Some(#initial));
+            }
         }
 
         if let Some(entry_action) = model
@@ -194,6 +313,13 @@ pub fn lower(model: &Model) -> Ir {
             .and_then(|state| state.entry_action.as_ref())
         {
             match actions.get(entry_action) {
+                Some(action) if action.is_vetoable => {
+                    let handler_call = &action.handler_call;
+                    state.entry_action_call = parse_quote!({
+                        #handler_call;
+                    });
+                    state.entry_veto_call = Some(handler_call.clone());
+                }
                 Some(action) => state.entry_action_call = action.handler_call.clone(),
                 None => abort!(entry_action, "entry action not found"),
             }
@@ -205,10 +331,104 @@ pub fn lower(model: &Model) -> Ir {
             .and_then(|state| state.exit_action.as_ref())
         {
             match actions.get(exit_action) {
+                Some(action) if action.is_vetoable => abort!(
+                    exit_action,
+                    "only the entry action of a state can veto a transition"
+                ),
                 Some(action) => state.exit_action_call = action.handler_call.clone(),
                 None => abort!(exit_action, "exit action not found"),
             }
         }
+
+        // Events that are deferred by the handler are offered to the mixin superstates
+        // in the order they were declared, before they reach the parent superstate.
+        let mixin_superstates = model
+            .states
+            .get(key)
+            .map(|state| state.mixin_superstates.as_slice())
+            .unwrap_or_default();
+        for mixin_superstate in mixin_superstates {
+            let mixin_handler_call = match superstates.get(mixin_superstate) {
+                Some(superstate) => &superstate.handler_call,
+                None => abort!(mixin_superstate, "superstate not found"),
+            };
+            // Mixins are not part of the hierarchy, so they are never entered or exited.
+            let mixin = &model.superstates[mixin_superstate];
+            if let Some(action) = mixin.entry_action.as_ref().or(mixin.exit_action.as_ref()) {
+                abort!(
+                    action,
+                    "a superstate that is used as a mixin can not have entry or exit actions";
+                    help = "`{}` is a mixin of `{}`, which only uses its handler", mixin_superstate, key
+                );
+            }
+            let handler_call = &state.handler_call;
+            state.handler_call = parse_quote!(match #handler_call {
+                statig::Response::Super => #mixin_handler_call,
+                response => response,
+            });
+        }
+
+        // When the handler defers the event with `Super` the fallback is returned instead.
+        let fallback = model
+            .states
+            .get(key)
+            .and_then(|state| state.fallback.as_ref());
+        let handler_call = &state.handler_call;
+        match fallback {
+            Some(Expr::Path(path)) if path.path.is_ident("Super") => {}
+            Some(Expr::Path(path)) if path.path.is_ident("Handled") => {
+                state.handler_call = parse_quote!(match #handler_call {
+                    statig::Response::Super => statig::Response::Handled,
+                    response => response,
+                });
+            }
+            Some(target) => {
+                state.handler_call = parse_quote!(match #handler_call {
+                    statig::Response::Super => statig::Response::Transition(#target),
+                    response => response,
+                });
+            }
+            None => {}
+        }
+
+        // Declared transitions are matched before the event is passed to the handler.
+        let transitions = model
+            .states
+            .get(key)
+            .map(|state| state.transitions.as_slice())
+            .unwrap_or_default();
+        if !transitions.is_empty() {
+            let event_ident = &model.state_machine.event_ident;
+            let handler_call = &state.handler_call;
+            let mut arms: Vec<Arm> = Vec::new();
+            for transition in transitions {
+                let event = &transition.event;
+                let target = &transition.target;
+                let action_call: Expr = match &transition.action {
+                    Some(action) => match actions.get(action) {
+                        Some(found) if found.is_vetoable => abort!(
+                            action,
+                            "only the entry action of a state can veto a transition"
+                        ),
+                        Some(action) => action.handler_call.clone(),
+                        None => abort!(action, "transition action not found"),
+                    },
+                    None => parse_quote!({}),
+                };
+                let guard = transition
+                    .guard
+                    .as_ref()
+                    .map(|guard| quote::quote!(if #guard));
+                arms.push(parse_quote!(#event #guard => {
+                    #action_call;
+                    statig::Response::Transition(#target)
+                }));
+            }
+            state.handler_call = parse_quote!(match #event_ident {
+                #(#arms)*
+                _ => #handler_call
+            });
+        }
     }
 
     // Linking superstates to superstates and entry/exit action.
@@ -234,6 +454,10 @@ pub fn lower(model: &Model) -> Ir {
             .and_then(|state| state.entry_action.as_ref())
         {
             match actions.get(entry_action) {
+                Some(action) if action.is_vetoable => abort!(
+                    entry_action,
+                    "only the entry action of a state can veto a transition"
+                ),
                 Some(action) => superstate.entry_action_call = action.handler_call.clone(),
                 None => abort!(entry_action, "action not found"),
             }
@@ -245,16 +469,44 @@ pub fn lower(model: &Model) -> Ir {
             .and_then(|state| state.exit_action.as_ref())
         {
             match actions.get(exit_action) {
+                Some(action) if action.is_vetoable => abort!(
+                    exit_action,
+                    "only the entry action of a state can veto a transition"
+                ),
                 Some(action) => superstate.exit_action_call = action.handler_call.clone(),
                 None => abort!(exit_action, "action not found"),
             }
         }
     }
 
+    // Collecting the direct children of every superstate in the order they were declared.
+    for item in &model.item_impl.items {
+        if let ImplItem::Method(method) = item {
+            let ident = &method.sig.ident;
+            let parent = match model.states.get(ident) {
+                Some(state) => state.superstate.as_ref(),
+                None => model
+                    .superstates
+                    .get(ident)
+                    .and_then(|superstate| superstate.superstate.as_ref()),
+            };
+            if let Some(superstate) = parent.and_then(|parent| superstates.get_mut(parent)) {
+                superstate.children.push(ident.to_string());
+                if let Some(state) = states.get(ident) {
+                    if state.constructor.sig.inputs.is_empty() {
+                        superstate.child_constructors.push(ident.clone());
+                    }
+                }
+            }
+        }
+    }
+
     // Find event and/or context types and check whether there are any async functions.
     let mut mode = Mode::Blocking;
     let mut event_type = None;
+    let mut mut_event = false;
     let mut context_type = None;
+    let mut async_functions: Vec<&Ident> = Vec::new();
 
     for state in model.states.values() {
         if let Some(pat_type) = &state.event_arg {
@@ -265,7 +517,10 @@ pub fn lower(model: &Model) -> Ir {
                     .eq(&external_input_ident.ident)
                 {
                     let ty = match &*pat_type.ty {
-                        Type::Reference(reference) => reference.elem.deref().clone(),
+                        Type::Reference(reference) => {
+                            mut_event |= reference.mutability.is_some();
+                            reference.elem.deref().clone()
+                        }
                         _ => abort!(pat_type.ty, "event must be passed in as a reference"),
                     };
                     event_type = Some(ty);
@@ -289,6 +544,7 @@ pub fn lower(model: &Model) -> Ir {
         }
         if state.is_async {
             mode = Mode::Awaitable;
+            async_functions.push(&state.handler_name);
         }
     }
 
@@ -301,7 +557,10 @@ pub fn lower(model: &Model) -> Ir {
                     .eq(&external_input_ident.ident)
                 {
                     let ty = match &*pat_type.ty {
-                        Type::Reference(reference) => reference.elem.deref().clone(),
+                        Type::Reference(reference) => {
+                            mut_event |= reference.mutability.is_some();
+                            reference.elem.deref().clone()
+                        }
                         _ => abort!(pat_type.ty, "event must be passed in as a reference"),
                     };
                     event_type = Some(ty);
@@ -325,17 +584,249 @@ pub fn lower(model: &Model) -> Ir {
         }
         if superstate.is_async {
             mode = Mode::Awaitable;
+            async_functions.push(&superstate.handler_name);
         }
     }
 
+    // The effects are collected in the type given with `effects`, which defaults to the
+    // type of the effects that the handlers return.
+    let returned_effects: Vec<&Type> = model
+        .states
+        .values()
+        .filter_map(|state| state.effects.as_ref())
+        .chain(
+            model
+                .superstates
+                .values()
+                .filter_map(|superstate| superstate.effects.as_ref()),
+        )
+        .collect();
+    let has_effects = !returned_effects.is_empty();
+    let has_entry_count = model
+        .states
+        .values()
+        .any(|state| state.entry_count_arg.is_some());
+    let effects_type = match (&model.state_machine.effects, returned_effects.first()) {
+        (_, None) => None,
+        (Some(effects_type), _) => Some(effects_type.clone()),
+        (None, Some(effects_type)) => Some((*effects_type).clone()),
+    };
+
     for action in model.actions.values() {
         if action.is_async {
             mode = Mode::Awaitable;
+            async_functions.push(&action.handler_name);
+        }
+    }
+
+    // If a single async function turns an otherwise blocking state machine into an
+    // awaitable one, point at it. The errors this causes (missing `.await`s on every
+    // `handle()` call) will otherwise show up far away from their cause. State machines
+    // that are meant to be async can opt out with `allow_single_async`.
+    let function_count = model.states.len() + model.superstates.len() + model.actions.len();
+    let single_async_function = match async_functions.as_slice() {
+        [async_function] if function_count > 1 && !model.state_machine.allow_single_async => {
+            Some((*async_function).clone())
+        }
+        _ => None,
+    };
+
+    // The table of function pointers is stored in a `const` inside the `call_handler`
+    // method, which can't refer to the generics of the impl block or hold async handlers.
+    if jump_table {
+        if mode == Mode::Awaitable {
+            abort_call_site!("`jump_table` is only supported on blocking state machines");
+        }
+        if !shared_storage_generics.params.is_empty() {
+            abort!(
+                shared_storage_generics,
+                "`jump_table` is not supported on generic state machines"
+            );
+        }
+    }
+
+    // Handlers that take the event as `&mut` are called through `call_handler_mut`, which
+    // only exists for blocking state machines and can't be stored in the jump table.
+    if mut_event {
+        if mode == Mode::Awaitable {
+            abort_call_site!(
+                "taking the event as `&mut` is only supported on blocking state machines"
+            );
+        }
+        if jump_table {
+            abort_call_site!("`jump_table` is not supported when the event is taken as `&mut`");
+        }
+        if model.state_machine.lifecycle_events {
+            abort_call_site!(
+                "`lifecycle_events` is not supported when the event is taken as `&mut`"
+            );
+        }
+        if has_effects {
+            abort_call_site!("handlers can not return effects when the event is taken as `&mut`");
+        }
+        if has_entry_count {
+            abort_call_site!(
+                "handlers can not take an `entry_count` when the event is taken as `&mut`"
+            );
+        }
+    }
+
+    // Effects and entry counts are only passed while dispatching an event to the handlers,
+    // not when the handlers are called with the entry and exit of their state.
+    if has_effects && model.state_machine.lifecycle_events {
+        abort_call_site!("handlers can not return effects with `lifecycle_events`");
+    }
+    if has_entry_count && model.state_machine.lifecycle_events {
+        abort_call_site!("handlers can not take an `entry_count` with `lifecycle_events`");
+    }
+
+    // Events that reach the top of the hierarchy are offered to the handler of the
+    // catch-all state, unless that state is the one that deferred them.
+    let catch_all_call = model.state_machine.catch_all.as_ref().map(|catch_all| {
+        let state = match states.get(catch_all) {
+            Some(state) => state,
+            None => abort!(catch_all, "catch-all state not found"),
+        };
+        if !state.constructor.sig.inputs.is_empty() {
+            abort!(
+                catch_all,
+                "the catch-all state can not have state-local storage"
+            );
+        }
+        if mut_event {
+            abort!(
+                catch_all,
+                "`catch_all` is not supported when the event is taken as `&mut`"
+            );
+        }
+        if model.states[catch_all].effects.is_some() {
+            abort!(
+                catch_all,
+                "the handler of the catch-all state can not return effects"
+            );
+        }
+        if model.states[catch_all].entry_count_arg.is_some() {
+            abort!(
+                catch_all,
+                "the handler of the catch-all state can not take an `entry_count`"
+            );
+        }
+        let pat = &state.pat;
+        let handler_call = &state.handler_call;
+        // A handler that takes the state is given the state that deferred the event.
+        let handler_call: Expr = match state.borrows_state {
+            true => parse_quote!({
+                let state: &#state_ident = &*self;
+                #handler_call
+            }),
+            false => handler_call.clone(),
+        };
+        parse_quote!(match self {
+            #pat => statig::Response::Super,
+            _ => #handler_call,
+        })
+    });
+
+    // With `const_step`, every handler must be a `const fn` that only depends on the
+    // event, so the next state can be computed in a const context by calling the
+    // handlers of a state and its superstates in turn.
+    let is_pure = |inputs: &[FnArg]| {
+        inputs.iter().all(|input| match input {
+            FnArg::Typed(pat_type) => matches!(
+                pat_type.pat.as_ref(),
+                Pat::Ident(pat) if pat.ident == model.state_machine.event_ident
+            ),
+            FnArg::Receiver(_) => false,
+        })
+    };
+    let is_const_step = mode == Mode::Blocking
+        && !mut_event
+        && shared_storage_generics.params.is_empty()
+        && !model.states.is_empty()
+        && model.states.values().all(|state| {
+            state.is_const
+                && state.effects.is_none()
+                && is_pure(&state.inputs)
+                && state.entry_action.is_none()
+                && state.exit_action.is_none()
+                && state.lazy_storage.is_empty()
+                && state
+                    .transitions
+                    .iter()
+                    .all(|transition| transition.action.is_none())
+        })
+        && model.superstates.values().all(|superstate| {
+            superstate.is_const
+                && superstate.effects.is_none()
+                && is_pure(&superstate.inputs)
+                && superstate.entry_action.is_none()
+                && superstate.exit_action.is_none()
+        });
+    // Call the handler of a state followed by the handlers of its superstates, until one
+    // of them doesn't return `Super`.
+    let call_with_superstates = |key: &Ident, state: &State| -> Expr {
+        let mut call = state.handler_call.clone();
+        let mut parent = model.states[key].superstate.as_ref();
+        while let Some(superstate) = parent {
+            let superstate_handler_call = &superstates[superstate].handler_call;
+            call = parse_quote!(match #call {
+                statig::Response::Super => #superstate_handler_call,
+                response => response,
+            });
+            parent = model.superstates[superstate].superstate.as_ref();
+        }
+        call
+    };
+    if model.state_machine.const_step {
+        if !is_const_step {
+            abort_call_site!(
+                "`const_step` requires every handler to be a `const fn` that only takes the event";
+                help = "entry, exit and transition actions, effects, state machines with type parameters and async state machines are not supported"
+            );
+        }
+        for (key, state) in &mut states {
+            state.const_step_call = Some(call_with_superstates(key, state));
+        }
+    }
+
+    // When the handlers don't mutate the shared storage or the local storage and take no
+    // context, events can be classified with a shared reference to the state machine.
+    let is_shared_receiver = |receiver: &Option<Receiver>| match receiver {
+        Some(receiver) => receiver.reference.is_some() && receiver.mutability.is_none(),
+        None => true,
+    };
+    let is_shared_ref = |pat_type: &PatType| matches!(pat_type.ty.as_ref(), Type::Reference(reference) if reference.mutability.is_none());
+    let is_pure_classify = mode == Mode::Blocking
+        && !mut_event
+        && shared_storage_generics.params.is_empty()
+        && model.states.values().all(|state| {
+            is_shared_receiver(&state.shared_storage_input)
+                && state.context_arg.is_none()
+                && state.effects.is_none()
+                && state.entry_count_arg.is_none()
+                && state.state_inputs.iter().all(is_shared_ref)
+                && state
+                    .transitions
+                    .iter()
+                    .all(|transition| transition.action.is_none())
+        })
+        && model.superstates.values().all(|superstate| {
+            is_shared_receiver(&superstate.shared_storage_input)
+                && superstate.context_arg.is_none()
+                && superstate.effects.is_none()
+                && superstate.state_inputs.is_empty()
+        });
+    if is_pure_classify {
+        for (key, state) in &mut states {
+            state.pure_call = Some(call_with_superstates(key, state));
         }
     }
 
     // Set the event type if it was found, otherwise set it to `()`.
     let mut event_type = match event_type {
+        Some(event_type) if model.state_machine.lifecycle_events => {
+            lifecycle_event_type(&event_type)
+        }
         Some(event_type) => event_type,
         None => parse_quote!(()),
     };
@@ -354,6 +845,11 @@ pub fn lower(model: &Model) -> Ir {
     let mut lifetime_visitor = LifetimeVisitor::new("'context");
     lifetime_visitor.rename_type(&mut context_type);
 
+    let event_coverage = match model.state_machine.check_event_coverage {
+        true => Some(lower_event_coverage(&event_type, &model.event_patterns)),
+        false => None,
+    };
+
     // Find the generics that need to be included on the state and superstate enums.
     let shared_storage_generics_map = map_generics(&shared_storage_generics);
 
@@ -401,6 +897,16 @@ pub fn lower(model: &Model) -> Ir {
         }
     }
 
+    // The methods of the constructor trait can't refer to the generics of the state enum.
+    if let Some(constructor_trait) = &state_constructor_trait {
+        if !state_generics.params.is_empty() {
+            abort!(
+                constructor_trait,
+                "`constructor_trait` is not supported when the state enum is generic"
+            );
+        }
+    }
+
     // If a lifetime is required it must be part of the superstate generics.
     if let Some(lifetime) = superstate_lifetime {
         superstate_generics
@@ -413,19 +919,30 @@ pub fn lower(model: &Model) -> Ir {
         shared_storage_type,
         shared_storage_generics,
         event_type,
+        mut_event,
         context_type,
+        effects_type,
         state_ident,
         state_derives,
+        jump_table,
+        state_repr,
+        state_constructor_trait,
         state_generics,
         superstate_ident,
         superstate_derives,
         superstate_generics,
         on_transition,
         on_dispatch,
+        on_snapshot,
+        ffi_events,
+        catch_all_call,
+        require_handled,
+        event_coverage,
         visibility,
         event_ident,
         context_ident,
         mode,
+        single_async_function,
     };
 
     Ir {
@@ -460,17 +977,51 @@ pub fn lower_state(state: &analyze::State, state_machine: &analyze::StateMachine
         }
     }
 
+    // Lazy storage is not passed to the constructor, it starts out as `None`.
+    variant_fields.retain(|field| {
+        !state
+            .lazy_storage
+            .iter()
+            .any(|storage| storage.field.ident == field.ident)
+    });
+    let constructor_fields = variant_fields.clone();
+    let lazy_idents: Vec<&Ident> = state
+        .lazy_storage
+        .iter()
+        .map(|storage| storage.field.ident.as_ref().unwrap())
+        .collect();
+    for storage in &state.lazy_storage {
+        let ident = &storage.field.ident;
+        let ty = &storage.field.ty;
+        variant_fields.push(
+            Field::parse_named
+                .parse2(quote::quote!(#ident: Option<#ty>))
+                .unwrap(),
+        );
+    }
+    // The entry count is kept by the state machine and passed to the handler.
+    let entry_count = state.entry_count_arg.is_some();
+
     let pat_fields: Vec<Ident> = variant_fields
         .iter()
         .map(|field| field.ident.as_ref().unwrap().clone())
         .collect();
+    let constructor_values: Vec<FieldValue> = constructor_fields
+        .iter()
+        .map(|field| {
+            let ident = &field.ident;
+            parse_quote!(#ident)
+        })
+        .chain(lazy_idents.iter().map(|ident| parse_quote!(#ident: None)))
+        .collect();
     let handler_inputs: Vec<Ident> = state.inputs.iter().map(fn_arg_to_ident).collect();
 
-    let variant = parse_quote!(#variant_name { #(#variant_fields),* });
+    let docs = &state.docs;
+    let variant = parse_quote!(#(#docs)* #variant_name { #(#variant_fields),* });
     let pat = parse_quote!(#state_name::#variant_name { #(#pat_fields),*});
-    let constructor = parse_quote!(const fn #state_handler_name ( #(#variant_fields),* ) -> Self { Self::#variant_name { #(#pat_fields),*} });
+    let constructor = parse_quote!(#(#docs)* const fn #state_handler_name ( #(#constructor_fields),* ) -> Self { Self::#variant_name { #(#constructor_values),*} });
 
-    let handler_call = match &state.is_async {
+    let mut handler_call = match &state.is_async {
         true => {
             parse_quote!(#shared_storage_path #shared_storage_turbofish ::#state_handler_name(#(#handler_inputs),*).await)
         }
@@ -479,9 +1030,76 @@ pub fn lower_state(state: &analyze::State, state_machine: &analyze::StateMachine
         }
     };
 
-    let entry_action_call = parse_quote!({});
-    let exit_action_call = parse_quote!({});
+    // The lazy storage is built the first time the handler takes it and is then kept in
+    // the state, so it is built at most once for every state value.
+    let build_lazy_storage: Vec<Stmt> = state
+        .lazy_storage
+        .iter()
+        .filter(|storage| {
+            state
+                .state_inputs
+                .iter()
+                .any(|pat_type| fn_arg_to_state_field(pat_type).ident == storage.field.ident)
+        })
+        .map(|storage| {
+            let ident = &storage.field.ident;
+            let init = &storage.init;
+            parse_quote!(let #ident = #ident.get_or_insert_with(|| #init);)
+        })
+        .collect();
+    if !build_lazy_storage.is_empty() {
+        // A handler that takes the state only gets its local storage immutably, so
+        // the storage can't be built.
+        if let Some(state_arg) = &state.state_arg {
+            abort!(
+                state_arg,
+                "lazy storage can not be taken by a handler that also takes the state";
+                help = "declare the storage with `local_storage` instead"
+            );
+        }
+        handler_call = parse_quote!({
+            #(#build_lazy_storage)*
+            #handler_call
+        });
+    }
+
+    // Effects returned next to the response are merged into the collected effects.
+    if state.effects.is_some() {
+        handler_call = collect_effects(&handler_call);
+    }
+
+    // Terminal state handlers don't return a response, every event is considered handled.
+    let terminal = state.terminal;
+    if terminal {
+        handler_call = parse_quote!({
+            #handler_call;
+            statig::Response::Handled
+        });
+    }
+
+    let mut entry_action_call = parse_quote!({});
+    let mut exit_action_call = parse_quote!({});
+
+    // The entry and exit of the state are passed to the handler as lifecycle events.
+    if state_machine.lifecycle_events && state.event_arg.is_some() {
+        if let Some(state_arg) = &state.state_arg {
+            abort!(
+                state_arg,
+                "handlers that take the state are not supported with `lifecycle_events`"
+            );
+        }
+        check_lifecycle_actions(&state.entry_action, &state.exit_action);
+        (handler_call, entry_action_call, exit_action_call) =
+            lifecycle_calls(&handler_call, state_machine);
+    }
+
+    let entry_veto_call = None;
     let superstate_pat = parse_quote!(None);
+    let superstate_name = parse_quote!(None);
+    let superstate_initial_state = parse_quote!(None);
+    let const_step_call = None;
+    let pure_call = None;
+    let borrows_state = state.state_arg.is_some();
 
     State {
         variant,
@@ -490,7 +1108,15 @@ pub fn lower_state(state: &analyze::State, state_machine: &analyze::StateMachine
         handler_call,
         entry_action_call,
         exit_action_call,
+        entry_veto_call,
         superstate_pat,
+        terminal,
+        borrows_state,
+        entry_count,
+        superstate_name,
+        superstate_initial_state,
+        const_step_call,
+        pure_call,
     }
 }
 
@@ -530,7 +1156,7 @@ pub fn lower_superstate(
     let variant = parse_quote!(#superstate_name { #(#variant_fields),* });
     let pat = parse_quote!(#superstate_type::#superstate_name { #(#pat_fields),*});
 
-    let handler_call = match &superstate.is_async {
+    let mut handler_call = match &superstate.is_async {
         true => {
             parse_quote!(#shared_storage_path #shared_storage_turbofish ::#superstate_handler_name(#(#handler_inputs),*).await)
         }
@@ -539,9 +1165,24 @@ pub fn lower_superstate(
         }
     };
 
-    let entry_action_call = parse_quote!({});
-    let exit_action_call = parse_quote!({});
+    // Effects returned next to the response are merged into the collected effects.
+    if superstate.effects.is_some() {
+        handler_call = collect_effects(&handler_call);
+    }
+
+    let mut entry_action_call = parse_quote!({});
+    let mut exit_action_call = parse_quote!({});
+
+    // The entry and exit of the superstate are passed to the handler as lifecycle events.
+    if state_machine.lifecycle_events && superstate.event_arg.is_some() {
+        check_lifecycle_actions(&superstate.entry_action, &superstate.exit_action);
+        (handler_call, entry_action_call, exit_action_call) =
+            lifecycle_calls(&handler_call, state_machine);
+    }
+
     let superstate_pat = parse_quote!(None);
+    let children = Vec::new();
+    let child_constructors = Vec::new();
 
     Superstate {
         variant,
@@ -550,6 +1191,8 @@ pub fn lower_superstate(
         entry_action_call,
         exit_action_call,
         superstate_pat,
+        children,
+        child_constructors,
     }
 }
 
@@ -590,7 +1233,199 @@ pub fn lower_action(action: &analyze::Action, state_machine: &analyze::StateMach
         }
     };
 
-    Action { handler_call }
+    Action {
+        handler_call,
+        is_vetoable: action.is_vetoable,
+    }
+}
+
+/// Check that the initial state is created with the constructor of a state. When it
+/// names a superstate instead (e.g. `State::blinking()`), the initial state of that
+/// superstate is used.
+fn resolve_initial_state(initial_expr: &Expr, model: &Model, visited: &mut Vec<Ident>) -> Expr {
+    let state_ident = &model.state_machine.state_ident;
+    // Only expressions of the form `State::name(..)` can be checked.
+    let initial_state = match initial_expr {
+        Expr::Call(initial_state) => initial_state,
+        _ => return initial_expr.clone(),
+    };
+    let name = match initial_state.func.as_ref() {
+        Expr::Path(path)
+            if path.path.segments.len() == 2 && path.path.segments[0].ident == *state_ident =>
+        {
+            &path.path.segments[1].ident
+        }
+        _ => return initial_expr.clone(),
+    };
+
+    if model.states.contains_key(name) {
+        return initial_expr.clone();
+    }
+
+    match model.superstates.get(name) {
+        Some(_) if visited.contains(name) => abort!(
+            name,
+            "the initial state of superstate `{}` leads back to itself", name;
+            help = "set the initial state of one of the superstates to a state"
+        ),
+        Some(superstate) => match &superstate.initial {
+            Some(initial) if initial_state.args.is_empty() => {
+                visited.push(name.clone());
+                resolve_initial_state(&Expr::Call(initial.clone()), model, visited)
+            }
+            Some(_) => abort!(
+                initial_state.args,
+                "`{}` is a superstate and can not be constructed with arguments",
+                name
+            ),
+            None => abort!(
+                name,
+                "`{}` is a superstate without an initial state", name;
+                help = "add an initial state to the superstate `#[superstate(initial = \"{}::initial_state()\")]` or start in one of its states", state_ident
+            ),
+        },
+        None => {
+            let mut states: Vec<String> = model
+                .states
+                .keys()
+                .map(|state| format!("`{}::{}()`", state_ident, state))
+                .collect();
+            states.sort();
+            abort!(
+                name,
+                "`{}::{}` is not a state", state_ident, name;
+                help = "create the initial state with the constructor of a state: {}", states.join(", ")
+            )
+        }
+    }
+}
+
+/// Collect the variants of the event enum that the handlers match against. Patterns
+/// are matched on the name of the enum, so `Event::A` and `crate::Event::A` both
+/// cover the variant `A` of `crate::Event`.
+fn lower_event_coverage(event_type: &Type, event_patterns: &[Path]) -> EventCoverage {
+    let mut event_path = match event_type {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path.path.clone(),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => abort_call_site!(
+            "`check_event_coverage` requires the handlers to take an event";
+            help = "add an event argument to a handler (e.g. `event: &Event`)"
+        ),
+        _ => abort!(
+            event_type,
+            "`check_event_coverage` requires the event to be an enum"
+        ),
+    };
+
+    // Lifetimes can be elided in the generated check, type parameters can not.
+    for segment in event_path.segments.iter_mut() {
+        if let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments {
+            if arguments
+                .args
+                .iter()
+                .any(|argument| !matches!(argument, syn::GenericArgument::Lifetime(_)))
+            {
+                abort!(
+                    event_type,
+                    "`check_event_coverage` is not supported for events with type parameters"
+                );
+            }
+        }
+        segment.arguments = syn::PathArguments::None;
+    }
+
+    let event_name = &event_path.segments.last().unwrap().ident;
+    let mut variants: Vec<Ident> = Vec::new();
+    for path in event_patterns {
+        let mut segments = path.segments.iter().rev();
+        match (segments.next(), segments.next()) {
+            (Some(variant), Some(parent))
+                if parent.ident == *event_name && !variants.contains(&variant.ident) =>
+            {
+                variants.push(variant.ident.clone())
+            }
+            _ => continue,
+        }
+    }
+
+    EventCoverage {
+        event_path,
+        variants,
+    }
+}
+
+/// Merge the effects that a handler returns next to its response into the collected
+/// effects with `|=`, leaving the response. The effects are dropped when they are not
+/// collected.
+fn collect_effects(handler_call: &Expr) -> Expr {
+    parse_quote!({
+        let (effects, response) = #handler_call;
+        if let Some(collected_effects) = collected_effects.as_deref_mut() {
+            *collected_effects |= effects;
+        }
+        response
+    })
+}
+
+/// Wrap the handler call so it is passed a `Lifecycle` event, and create the calls
+/// that pass it the entry and exit. The responses to the entry and exit are ignored.
+fn lifecycle_calls(
+    handler_call: &Expr,
+    state_machine: &analyze::StateMachine,
+) -> (Expr, Expr, Expr) {
+    let event_ident = &state_machine.event_ident;
+    let event_call = parse_quote!({
+        let #event_ident = &statig::Lifecycle::Event(#event_ident);
+        #handler_call
+    });
+    let entry_call = parse_quote!({
+        let #event_ident = &statig::Lifecycle::Enter;
+        let _ = #handler_call;
+    });
+    let exit_call = parse_quote!({
+        let #event_ident = &statig::Lifecycle::Exit;
+        let _ = #handler_call;
+    });
+    (event_call, entry_call, exit_call)
+}
+
+/// Get the event type `Event` out of the `Lifecycle<Event>` that is taken by the handlers.
+fn lifecycle_event_type(lifecycle_type: &Type) -> Type {
+    let event_type = match lifecycle_type {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .filter(|segment| segment.ident == "Lifecycle")
+            .and_then(|segment| match &segment.arguments {
+                PathArguments::AngleBracketed(arguments) => {
+                    arguments.args.iter().find_map(|argument| match argument {
+                        GenericArgument::Type(event_type) => Some(event_type.clone()),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }),
+        _ => None,
+    };
+    match event_type {
+        Some(event_type) => event_type,
+        None => abort!(
+            lifecycle_type,
+            "with `lifecycle_events` the event must be taken as a `&Lifecycle<Event>`"
+        ),
+    }
+}
+
+/// With `lifecycle_events`, a handler that takes the event already reacts to the entry
+/// and exit of its state, so it can't have entry or exit actions as well.
+fn check_lifecycle_actions(entry_action: &Option<Ident>, exit_action: &Option<Ident>) {
+    if let Some(action) = entry_action.as_ref().or(exit_action.as_ref()) {
+        abort!(
+            action,
+            "entry and exit actions can not be combined with `lifecycle_events`";
+            help = "handle `Lifecycle::Enter` and `Lifecycle::Exit` in the handler instead"
+        );
+    }
 }
 
 fn fn_arg_to_ident(fn_arg: &FnArg) -> Ident {
@@ -699,16 +1534,29 @@ fn snake_case_to_pascal_case(snake: &Ident) -> Ident {
 #[cfg(test)]
 fn create_analyze_state_machine() -> analyze::StateMachine {
     analyze::StateMachine {
-        initial_state: parse_quote!(State::on()),
+        initial_state: Some(parse_quote!(State::on())),
+        initial_superstate: None,
         shared_storage_type: parse_quote!(Blinky),
         shared_storage_path: parse_quote!(Blinky),
         shared_storage_generics: parse_quote!(),
         state_ident: parse_quote!(State),
         state_derives: vec![parse_quote!(Copy), parse_quote!(Clone)],
+        jump_table: false,
+        state_repr: None,
+        state_constructor_trait: None,
         superstate_ident: parse_quote!(Superstate),
         superstate_derives: vec![parse_quote!(Copy), parse_quote!(Clone)],
         on_transition: None,
         on_dispatch: None,
+        on_snapshot: None,
+        ffi_events: Vec::new(),
+        catch_all: None,
+        require_handled: false,
+        check_event_coverage: false,
+        lifecycle_events: false,
+        effects: None,
+        const_step: false,
+        allow_single_async: false,
         visibility: parse_quote!(pub),
         event_ident: parse_quote!(input),
         context_ident: parse_quote!(context),
@@ -724,20 +1572,31 @@ fn create_lower_state_machine() -> StateMachine {
         shared_storage_type: parse_quote!(Blinky),
         shared_storage_generics: parse_quote!(),
         event_type: parse_quote!(()),
+        mut_event: false,
         context_type: parse_quote!(()),
+        effects_type: None,
         #[rustfmt::skip]
         state_ident: parse_quote!(State),
         state_derives: vec![parse_quote!(Copy), parse_quote!(Clone)],
+        jump_table: false,
+        state_repr: None,
+        state_constructor_trait: None,
         state_generics: Generics::default(),
         superstate_ident: parse_quote!(Superstate),
         superstate_derives: vec![parse_quote!(Copy), parse_quote!(Clone)],
         superstate_generics,
         on_transition: None,
         on_dispatch: None,
+        on_snapshot: None,
+        ffi_events: Vec::new(),
+        catch_all_call: None,
+        require_handled: false,
+        event_coverage: None,
         visibility: parse_quote!(pub),
         event_ident: parse_quote!(input),
         context_ident: parse_quote!(context),
         mode: Mode::Blocking,
+        single_async_function: None,
     }
 }
 
@@ -745,10 +1604,14 @@ fn create_lower_state_machine() -> StateMachine {
 fn create_analyze_state() -> analyze::State {
     analyze::State {
         handler_name: parse_quote!(on),
+        docs: vec![],
         superstate: parse_quote!(playing),
+        mixin_superstates: vec![],
         entry_action: parse_quote!(enter_on),
         exit_action: None,
         local_storage: vec![],
+        lazy_storage: vec![],
+        inline_actions: vec![],
         inputs: vec![
             parse_quote!(&mut self),
             parse_quote!(input: &Event),
@@ -764,6 +1627,8 @@ fn create_analyze_state() -> analyze::State {
             },
         ),
         context_arg: None,
+        state_arg: None,
+        entry_count_arg: None,
         state_inputs: vec![
             if let FnArg::Typed(pat_type) = parse_quote!(led: &mut bool) {
                 pat_type
@@ -776,7 +1641,12 @@ fn create_analyze_state() -> analyze::State {
                 panic!();
             },
         ],
+        transitions: vec![],
+        terminal: false,
+        fallback: None,
         is_async: false,
+        is_const: false,
+        effects: None,
     }
 }
 
@@ -791,12 +1661,20 @@ fn create_lower_state() -> State {
         handler_call: parse_quote!(Blinky::on(shared_storage, input, led, counter)),
         entry_action_call: parse_quote!({}),
         exit_action_call: parse_quote!({}),
+        entry_veto_call: None,
         superstate_pat: parse_quote!(None),
         constructor: parse_quote!(
             const fn on(led: bool, counter: usize) -> Self {
                 Self::On { led, counter }
             }
         ),
+        terminal: false,
+        borrows_state: false,
+        entry_count: false,
+        superstate_name: parse_quote!(None),
+        superstate_initial_state: parse_quote!(None),
+        const_step_call: None,
+        pure_call: None,
     }
 }
 
@@ -805,6 +1683,7 @@ fn create_linked_lower_state() -> State {
     let mut state = create_lower_state();
     state.superstate_pat = parse_quote!(Some(Superstate::Playing { led, counter }));
     state.entry_action_call = parse_quote!(Blinky::enter_on(shared_storage, led));
+    state.superstate_name = parse_quote!(Some("playing"));
     state
 }
 
@@ -813,6 +1692,7 @@ fn create_analyze_superstate() -> analyze::Superstate {
     analyze::Superstate {
         handler_name: parse_quote!(playing),
         superstate: None,
+        initial: None,
         entry_action: None,
         exit_action: None,
         local_storage: vec![],
@@ -844,6 +1724,8 @@ fn create_analyze_superstate() -> analyze::Superstate {
             },
         ],
         is_async: false,
+        is_const: false,
+        effects: None,
     }
 }
 
@@ -859,6 +1741,8 @@ fn create_lower_superstate() -> Superstate {
         entry_action_call: parse_quote!({}),
         exit_action_call: parse_quote!({}),
         superstate_pat: parse_quote!(None),
+        children: Vec::new(),
+        child_constructors: Vec::new(),
     }
 }
 
@@ -868,6 +1752,7 @@ fn create_analyze_action() -> analyze::Action {
         handler_name: parse_quote!(enter_on),
         inputs: vec![parse_quote!(&mut self), parse_quote!(led: &mut bool)],
         is_async: false,
+        is_vetoable: false,
     }
 }
 
@@ -875,6 +1760,7 @@ fn create_analyze_action() -> analyze::Action {
 fn create_lower_action() -> Action {
     Action {
         handler_call: parse_quote!(Blinky::enter_on(shared_storage, led)),
+        is_vetoable: false,
     }
 }
 
@@ -895,6 +1781,7 @@ fn create_analyze_model() -> analyze::Model {
             .into_iter()
             .map(|state| (state.handler_name.clone(), state))
             .collect(),
+        event_patterns: vec![],
     }
 }
 
@@ -956,3 +1843,144 @@ fn test_lower() {
 
     assert_eq!(actual, expected);
 }
+
+#[test]
+#[should_panic]
+fn test_lower_unknown_initial_state() {
+    let mut model = create_analyze_model();
+    model.state_machine.initial_state = Some(parse_quote!(State::of()));
+
+    lower(&model);
+}
+
+#[test]
+#[should_panic]
+fn test_lower_initial_superstate_without_initial() {
+    let mut model = create_analyze_model();
+    model.state_machine.initial_state = Some(parse_quote!(State::playing()));
+
+    lower(&model);
+}
+
+#[test]
+fn test_lower_initial_superstate() {
+    let mut model = create_analyze_model();
+    model.state_machine.initial_state = Some(parse_quote!(State::playing()));
+    let playing: Ident = parse_quote!(playing);
+    model.superstates.get_mut(&playing).unwrap().initial = Some(parse_quote!(State::on()));
+
+    let ir = lower(&model);
+
+    assert_eq!(ir.state_machine.initial_state, parse_quote!(State::on()));
+}
+
+#[test]
+#[should_panic]
+fn test_lower_const_step_non_const_handler() {
+    let mut model = create_analyze_model();
+    model.state_machine.const_step = true;
+
+    lower(&model);
+}
+
+#[test]
+fn test_lower_state_docs() {
+    let analyze_state_machine = create_analyze_state_machine();
+    let mut analyze_state = create_analyze_state();
+    analyze_state.docs = vec![parse_quote!(#[doc = " The led is on."])];
+
+    let actual = lower_state(&analyze_state, &analyze_state_machine);
+
+    assert_eq!(actual.variant.attrs, analyze_state.docs);
+    assert_eq!(actual.constructor.attrs, analyze_state.docs);
+}
+
+#[test]
+fn test_lower_event_coverage() {
+    let event_type: Type = parse_quote!(events::Event<'event>);
+    let event_patterns: Vec<Path> = vec![
+        parse_quote!(Event::Start),
+        parse_quote!(Speed::Fast),
+        parse_quote!(events::Event::Stop),
+        parse_quote!(Event::Start),
+    ];
+
+    let actual = lower_event_coverage(&event_type, &event_patterns);
+
+    let expected = EventCoverage {
+        event_path: parse_quote!(events::Event),
+        variants: vec![parse_quote!(Start), parse_quote!(Stop)],
+    };
+
+    assert_eq!(actual, expected);
+}
+
+#[test]
+#[should_panic]
+fn test_lower_event_coverage_generic_event() {
+    let event_type: Type = parse_quote!(Event<T>);
+
+    lower_event_coverage(&event_type, &[]);
+}
+
+#[test]
+fn test_lower_single_async_function() {
+    let mut model = create_analyze_model();
+    model
+        .actions
+        .values_mut()
+        .for_each(|action| action.is_async = true);
+
+    let ir = lower(&model);
+
+    assert_eq!(ir.state_machine.mode, Mode::Awaitable);
+    assert_eq!(
+        ir.state_machine.single_async_function,
+        Some(format_ident!("enter_on"))
+    );
+}
+
+#[test]
+fn test_lower_allow_single_async() {
+    let mut model = create_analyze_model();
+    model.state_machine.allow_single_async = true;
+    model
+        .actions
+        .values_mut()
+        .for_each(|action| action.is_async = true);
+
+    let ir = lower(&model);
+
+    assert_eq!(ir.state_machine.mode, Mode::Awaitable);
+    assert_eq!(ir.state_machine.single_async_function, None);
+}
+
+#[test]
+fn test_lower_mixin_superstate() {
+    let mut model = create_analyze_model();
+    let mut mixin = create_analyze_superstate();
+    mixin.handler_name = parse_quote!(logging);
+    model.superstates.insert(mixin.handler_name.clone(), mixin);
+    model
+        .states
+        .values_mut()
+        .for_each(|state| state.mixin_superstates = vec![parse_quote!(logging)]);
+
+    lower(&model);
+}
+
+#[test]
+#[should_panic]
+fn test_lower_mixin_superstate_with_entry_action() {
+    let mut model = create_analyze_model();
+    let mut mixin = create_analyze_superstate();
+    mixin.handler_name = parse_quote!(logging);
+    mixin.entry_action = Some(parse_quote!(enter_on));
+    model.superstates.insert(mixin.handler_name.clone(), mixin);
+    model
+        .states
+        .values_mut()
+        .for_each(|state| state.mixin_superstates = vec![parse_quote!(logging)]);
+
+    lower(&model);
+}
@@ -0,0 +1,84 @@
+use proc_macro2::TokenStream;
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Expr, Macro, Pat, PatPath, PatStruct, PatTupleStruct, Path, Token};
+
+/// Visitor to find the paths that patterns match against (e.g. `Event::TimerElapsed`),
+/// including the patterns of `matches!` invocations.
+#[derive(Debug, Default)]
+pub struct EventPatternVisitor {
+    found: Vec<Path>,
+}
+
+impl EventPatternVisitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn finish(self) -> Vec<Path> {
+        self.found
+    }
+}
+
+impl<'ast> Visit<'ast> for EventPatternVisitor {
+    fn visit_pat_path(&mut self, pat: &'ast PatPath) {
+        self.found.push(pat.path.clone());
+        visit::visit_pat_path(self, pat);
+    }
+
+    fn visit_pat_tuple_struct(&mut self, pat: &'ast PatTupleStruct) {
+        self.found.push(pat.path.clone());
+        visit::visit_pat_tuple_struct(self, pat);
+    }
+
+    fn visit_pat_struct(&mut self, pat: &'ast PatStruct) {
+        self.found.push(pat.path.clone());
+        visit::visit_pat_struct(self, pat);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if mac.path.is_ident("matches") {
+            let pats = mac.parse_body_with(|input: syn::parse::ParseStream| {
+                input.parse::<Expr>()?;
+                input.parse::<Token![,]>()?;
+                let pats = Punctuated::<Pat, Token![|]>::parse_separated_nonempty(input)?;
+                // Skip the optional guard.
+                input.parse::<TokenStream>()?;
+                Ok(pats)
+            });
+            if let Ok(pats) = pats {
+                for pat in &pats {
+                    self.visit_pat(pat);
+                }
+            }
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+#[test]
+fn event_pattern_visitor() {
+    use syn::{parse_quote, Block};
+
+    let block: Block = parse_quote!({
+        if matches!(event, Event::Reset | Event::Stop { .. } if true) {
+            return Handled;
+        }
+        match event {
+            Event::Start(Speed::Fast) => Handled,
+            _ => Super,
+        }
+    });
+
+    let mut visitor = EventPatternVisitor::new();
+    visitor.visit_block(&block);
+
+    let expected: Vec<Path> = vec![
+        parse_quote!(Event::Reset),
+        parse_quote!(Event::Stop),
+        parse_quote!(Event::Start),
+        parse_quote!(Speed::Fast),
+    ];
+
+    assert_eq!(visitor.finish(), expected);
+}
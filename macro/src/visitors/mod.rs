@@ -1,5 +1,9 @@
+mod event_pattern_visitor;
 mod generic_param_visitor;
 mod lifetime_visitor;
+mod self_path_visitor;
 
+pub use event_pattern_visitor::*;
 pub use generic_param_visitor::*;
 pub use lifetime_visitor::*;
+pub use self_path_visitor::*;
@@ -0,0 +1,50 @@
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{parse_quote, Expr, ExprPath, PathSegment, Token, Type};
+
+// Visit all paths in an expression that start with `Self` and qualify them with the
+// given type. For instance `Self::START` becomes `<Blinky>::START`. This keeps the
+// expression valid when it's used in an impl block of another type.
+pub struct SelfPathVisitor<'a> {
+    ty: &'a Type,
+}
+
+impl<'a> SelfPathVisitor<'a> {
+    pub fn new(ty: &'a Type) -> Self {
+        Self { ty }
+    }
+
+    pub fn replace_expr(&mut self, expr: &mut Expr) {
+        self.visit_expr_mut(expr);
+    }
+}
+
+impl<'a> VisitMut for SelfPathVisitor<'a> {
+    fn visit_expr_path_mut(&mut self, expr_path: &mut ExprPath) {
+        let path = &expr_path.path;
+        if expr_path.qself.is_none()
+            && path.leading_colon.is_none()
+            && path.segments.len() > 1
+            && path.segments[0].ident == "Self"
+        {
+            let ty = self.ty;
+            let rest: Punctuated<PathSegment, Token![::]> =
+                path.segments.iter().skip(1).cloned().collect();
+            let attrs = &expr_path.attrs;
+            *expr_path = parse_quote!(#(#attrs)* <#ty>::#rest);
+        }
+        visit_mut::visit_expr_path_mut(self, expr_path);
+    }
+}
+
+#[test]
+fn self_path_visitor() {
+    let ty: Type = parse_quote!(Blinky<T>);
+    let mut expr: Expr = parse_quote!(Self::start(Self::SPEED, State::off(), Self));
+
+    SelfPathVisitor::new(&ty).replace_expr(&mut expr);
+
+    let expected: Expr = parse_quote!(<Blinky<T>>::start(<Blinky<T>>::SPEED, State::off(), Self));
+
+    assert_eq!(expr, expected);
+}
@@ -1,6 +1,9 @@
+use core::any::Any;
 use core::future::Future;
 use core::pin::Pin;
 
+use alloc::boxed::Box;
+
 use crate::awaitable::{Superstate, SuperstateExt};
 use crate::IntoStateMachine;
 use crate::Response;
@@ -21,6 +24,48 @@ where
         context: &'fut mut M::Context<'_>,
     ) -> Pin<Box<dyn Future<Output = Response<Self>> + 'fut + Send>>;
 
+    /// Call the handler for the current state and let it handle the given event,
+    /// merging the effects it returns into `effects` when they are collected. By
+    /// default this calls [`call_handler`](State::call_handler).
+    #[allow(unused)]
+    fn call_handler_with_effects<'fut>(
+        &'fut mut self,
+        shared_storage: &'fut mut M,
+        event: &'fut M::Event<'_>,
+        context: &'fut mut M::Context<'_>,
+        effects: Option<&'fut mut (dyn Any + Send)>,
+    ) -> Pin<Box<dyn Future<Output = Response<Self>> + 'fut + Send>> {
+        self.call_handler(shared_storage, event, context)
+    }
+
+    /// Call the handler for the current state and let it handle the given event,
+    /// passing `entry_count` to a handler that takes the number of times its state
+    /// has been entered. By default this calls
+    /// [`call_handler_with_effects`](State::call_handler_with_effects).
+    #[allow(unused)]
+    fn call_handler_with_entry_count<'fut>(
+        &'fut mut self,
+        shared_storage: &'fut mut M,
+        event: &'fut M::Event<'_>,
+        context: &'fut mut M::Context<'_>,
+        effects: Option<&'fut mut (dyn Any + Send)>,
+        entry_count: u32,
+    ) -> Pin<Box<dyn Future<Output = Response<Self>> + 'fut + Send>> {
+        self.call_handler_with_effects(shared_storage, event, context, effects)
+    }
+
+    #[allow(unused)]
+    /// Call the catch-all handler with an event that was deferred by the current state
+    /// and all of its superstates.
+    fn call_catch_all<'fut>(
+        &'fut mut self,
+        shared_storage: &'fut mut M,
+        event: &'fut M::Event<'_>,
+        context: &'fut mut M::Context<'_>,
+    ) -> Pin<Box<dyn Future<Output = Response<Self>> + 'fut + Send>> {
+        Box::pin(core::future::ready(Response::Super))
+    }
+
     #[allow(unused)]
     /// Call the entry action for the current state.
     fn call_entry_action<'fut>(
@@ -31,6 +76,20 @@ where
         Box::pin(core::future::ready(()))
     }
 
+    /// Call the entry action for the current state and return whether the state
+    /// accepts being entered. Returning `false` vetoes the transition into this state.
+    fn try_call_entry_action<'fut>(
+        &'fut mut self,
+        shared_storage: &'fut mut M,
+        context: &'fut mut M::Context<'_>,
+    ) -> Pin<Box<dyn Future<Output = bool> + 'fut + Send>> {
+        let entry_action = self.call_entry_action(shared_storage, context);
+        Box::pin(async move {
+            entry_action.await;
+            true
+        })
+    }
+
     #[allow(unused)]
     /// Call the exit action for the current state.
     fn call_exit_action<'fut>(
@@ -45,6 +104,23 @@ where
     fn superstate(&mut self) -> Option<M::Superstate<'_>> {
         None
     }
+
+    /// Return the initial state of the superstate of the current state, if there is one.
+    fn superstate_initial_state(&self) -> Option<Self> {
+        None
+    }
+
+    /// Check whether the current state is a terminal state. A terminal state
+    /// considers every event handled and never transitions to another state.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Return the index of the counter that keeps the number of times the current
+    /// state has been entered, if its handler takes the entry count.
+    fn entry_counter(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Extensions for `State` trait.
@@ -109,11 +185,21 @@ where
         shared_storage: &'fut mut M,
         event: &'fut M::Event<'_>,
         context: &'fut mut M::Context<'_>,
+        mut effects: Option<&'fut mut (dyn Any + Send)>,
+        entry_count: u32,
     ) -> Pin<Box<dyn Future<Output = Response<Self>> + 'fut + Send>> {
         let future = async move {
             M::ON_DISPATCH(shared_storage, StateOrSuperstate::State(self), event);
 
-            let response = self.call_handler(shared_storage, event, context).await;
+            let response = self
+                .call_handler_with_entry_count(
+                    shared_storage,
+                    event,
+                    context,
+                    effects.as_deref_mut(),
+                    entry_count,
+                )
+                .await;
 
             match response {
                 Response::Handled => Response::Handled,
@@ -125,11 +211,14 @@ where
                             event,
                         );
 
-                        superstate.handle(shared_storage, event, context).await
+                        superstate
+                            .handle(shared_storage, event, context, effects)
+                            .await
                     }
                     None => Response::Super,
                 },
                 Response::Transition(state) => Response::Transition(state),
+                Response::TransitionToSuperstate => Response::TransitionToSuperstate,
             }
         };
         Box::pin(future)
@@ -158,6 +247,37 @@ where
         Box::pin(future)
     }
 
+    /// Same as [`enter`](StateExt::enter), but the entry action of the current state can
+    /// veto the entry. When it does, the superstates that were entered along the way are
+    /// exited again and `false` is returned.
+    fn try_enter<'fut>(
+        &'fut mut self,
+        shared_storage: &'fut mut M,
+        context: &'fut mut M::Context<'_>,
+        levels: usize,
+    ) -> Pin<Box<dyn Future<Output = bool> + 'fut + Send>> {
+        let future = async move {
+            if levels == 0 {
+                return true;
+            }
+
+            if let Some(mut superstate) = self.superstate() {
+                superstate.enter(shared_storage, context, levels - 1).await;
+            }
+
+            if self.try_call_entry_action(shared_storage, context).await {
+                return true;
+            }
+
+            if let Some(mut superstate) = self.superstate() {
+                superstate.exit(shared_storage, context, levels - 1).await;
+            }
+
+            false
+        };
+        Box::pin(future)
+    }
+
     /// Starting from the current state, climb a given amount of levels and execute all the
     /// the exit actions while going up to a certain superstate.
     fn exit<'fut>(
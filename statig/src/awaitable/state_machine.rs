@@ -1,7 +1,8 @@
 use core::fmt::Debug;
+use core::future::Future;
 
-use super::awaitable;
-use crate::{Inner, IntoStateMachine};
+use super::awaitable::{self, StateExt as _};
+use crate::{Inner, IntoStateMachine, Unhandled};
 
 /// A state machine where the shared storage is of type `Self`.
 pub trait IntoStateMachineExt: IntoStateMachine
@@ -15,10 +16,7 @@ where
     where
         Self: Sized,
     {
-        let inner = Inner {
-            shared_storage: self,
-            state: Self::INITIAL,
-        };
+        let inner = Inner::new(self, Self::INITIAL);
         StateMachine {
             inner,
             initialized: false,
@@ -28,10 +26,7 @@ where
     /// Create an uninitialized state machine that must be explicitly initialized with
     /// [`init`](UninitializedStateMachine::init).
     fn uninitialized_state_machine(self) -> UninitializedStateMachine<Self> {
-        let inner = Inner {
-            shared_storage: self,
-            state: Self::INITIAL,
-        };
+        let inner = Inner::new(self, Self::INITIAL);
         UninitializedStateMachine { inner }
     }
 }
@@ -83,6 +78,31 @@ where
         }
     }
 
+    /// Turn the state machine into an [`InitializedStateMachine`]. If the state machine
+    /// is still uninitialized, it will be initialized first.
+    pub async fn into_initialized(self) -> InitializedStateMachine<M>
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.into_initialized_with_context(&mut ()).await
+    }
+
+    /// Turn the state machine into an [`InitializedStateMachine`]. If the state machine
+    /// is still uninitialized, it will be initialized first with the given context.
+    pub async fn into_initialized_with_context(
+        mut self,
+        context: &mut M::Context<'_>,
+    ) -> InitializedStateMachine<M>
+    where
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.init_with_context(context).await;
+        InitializedStateMachine { inner: self.inner }
+    }
+
     /// Handle an event. If the state machine is still uninitialized, it will be initialized
     /// before handling the event.
     pub async fn handle(&mut self, event: &M::Event<'_>)
@@ -107,6 +127,55 @@ where
         self.inner.async_handle_with_context(event, context).await;
     }
 
+    /// Handle an event and return an error with the event if it was not handled by
+    /// any state or superstate. If the state machine is still uninitialized, it will
+    /// be initialized before handling the event.
+    pub async fn try_handle<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'e> M::Event<'e>: Send + Sync,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+    {
+        self.try_handle_with_context(event, &mut ()).await
+    }
+
+    /// Handle an event and return an error with the event if it was not handled by
+    /// any state or superstate. If the state machine is still uninitialized, it will
+    /// be initialized before handling the event.
+    pub async fn try_handle_with_context<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+        context: &mut M::Context<'_>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+        for<'e> M::Event<'e>: Send + Sync,
+    {
+        if !self.initialized {
+            self.inner.async_init_with_context(context).await;
+            self.initialized = true;
+        }
+        match self.inner.async_handle_with_context(event, context).await {
+            true => Ok(()),
+            false => Err(Unhandled { event }),
+        }
+    }
+
+    /// Handle an event with a context that is created with `Default::default()`. If the
+    /// state machine is still uninitialized, it will be initialized before handling the event.
+    pub async fn handle_default_context(&mut self, event: &M::Event<'_>)
+    where
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Default + Send + Sync,
+    {
+        self.handle_with_context(event, &mut Default::default())
+            .await;
+    }
+
     pub async fn step(&mut self)
     where
         for<'evt, 'ctx> M: IntoStateMachine<Event<'evt> = (), Context<'ctx> = ()>,
@@ -129,6 +198,34 @@ where
     pub fn state(&self) -> &M::State {
         &self.inner.state
     }
+
+    /// Check whether the state machine has reached a terminal state. Once it has,
+    /// handling more events has no effect.
+    pub fn is_terminal(&self) -> bool {
+        awaitable::State::is_terminal(&self.inner.state)
+    }
+
+    /// Get the number of superstates that enclose the current state. This is `0` when
+    /// the current state has no superstate.
+    pub fn superstate_depth(&self) -> usize
+    where
+        M::State: Clone,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        // Creating a superstate requires mutable access to the state, so work on a copy.
+        self.inner.state.clone().depth() - 1
+    }
+
+    /// Check whether the current state has a superstate.
+    pub fn is_nested(&self) -> bool
+    where
+        M::State: Clone,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.superstate_depth() > 0
+    }
 }
 
 impl<M> Clone for StateMachine<M>
@@ -160,15 +257,26 @@ where
 {
 }
 
+impl<M> Debug for StateMachine<M>
+where
+    M: IntoStateMachine + Debug,
+    M::State: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StateMachine")
+            .field("initialized", &self.initialized)
+            .field("shared_storage", &self.inner.shared_storage as &dyn Debug)
+            .field("state", &self.inner.state as &dyn Debug)
+            .finish()
+    }
+}
+
 impl<M> Default for StateMachine<M>
 where
     M: IntoStateMachine + Default,
 {
     fn default() -> Self {
-        let inner = Inner {
-            shared_storage: M::default(),
-            state: M::INITIAL,
-        };
+        let inner = Inner::new(M::default(), M::INITIAL);
         Self {
             inner,
             initialized: false,
@@ -257,13 +365,312 @@ where
     /// Handle the given event.
     pub async fn handle_with_context(&mut self, event: &M::Event<'_>, context: &mut M::Context<'_>)
     where
-        M: IntoStateMachine,
         for<'evt> M::Event<'evt>: Send + Sync,
         for<'ctx> M::Context<'ctx>: Send + Sync,
     {
         self.inner.async_handle_with_context(event, context).await;
     }
 
+    /// Handle the given event and return an error with the event if it was not handled
+    /// by any state or superstate.
+    pub async fn try_handle<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'e> M::Event<'e>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.try_handle_with_context(event, &mut ()).await
+    }
+
+    /// Handle the given event with the given context and return an error with the
+    /// event if it was not handled by any state or superstate.
+    pub async fn try_handle_with_context<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+        context: &mut M::Context<'_>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'e> M::Event<'e>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        match self.inner.async_handle_with_context(event, context).await {
+            true => Ok(()),
+            false => Err(Unhandled { event }),
+        }
+    }
+
+    /// Handle the given event with a context that is created with `Default::default()`.
+    pub async fn handle_default_context(&mut self, event: &M::Event<'_>)
+    where
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Default + Send + Sync,
+    {
+        self.handle_with_context(event, &mut Default::default())
+            .await;
+    }
+
+    /// Handle the given event and merge the effects that the handlers return next to
+    /// their response (e.g. `(Effects, Response<State>)`) into `effects` with `|=`.
+    pub async fn handle_with_effects(&mut self, event: &M::Event<'_>, effects: &mut M::Effects)
+    where
+        M: crate::HasEffects,
+        M::Effects: Send,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+    {
+        self.handle_with_context_and_effects(event, &mut (), effects)
+            .await;
+    }
+
+    /// Handle the given event with the given context and merge the effects that the
+    /// handlers return into `effects`, see [`handle_with_effects`](Self::handle_with_effects).
+    pub async fn handle_with_context_and_effects(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: &mut M::Effects,
+    ) where
+        M: crate::HasEffects,
+        M::Effects: Send,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.inner
+            .async_handle_with_effects(event, context, Some(effects))
+            .await;
+    }
+
+    /// Handle the given event and return the wall-clock time it took. This includes
+    /// the time spent in the handlers and, when a transition is taken, in the exit
+    /// and entry actions and `on_transition`, as well as any time the handlers spent
+    /// waiting.
+    #[cfg(feature = "std")]
+    pub async fn handle_timed(&mut self, event: &M::Event<'_>) -> std::time::Duration
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.handle_with_context_timed(event, &mut ()).await
+    }
+
+    /// Handle the given event with the given context and return the wall-clock time
+    /// it took, see [`handle_timed`](Self::handle_timed).
+    #[cfg(feature = "std")]
+    pub async fn handle_with_context_timed(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> std::time::Duration
+    where
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        let start = std::time::Instant::now();
+        self.handle_with_context(event, context).await;
+        start.elapsed()
+    }
+
+    /// Register a callback that is called after every transition with the source
+    /// and target state, next to the `on_transition` callback of the state machine.
+    /// This lets tools such as debuggers observe a state machine at runtime. A
+    /// previously registered observer is replaced. The observer is not cloned
+    /// along with the state machine.
+    #[cfg(feature = "std")]
+    pub fn set_transition_observer(&mut self, observer: crate::TransitionObserver<M>) {
+        self.inner.observer = Some(observer);
+    }
+
+    /// Remove the callback that was registered with
+    /// [`set_transition_observer`](Self::set_transition_observer) and return it.
+    #[cfg(feature = "std")]
+    pub fn take_transition_observer(&mut self) -> Option<crate::TransitionObserver<M>> {
+        self.inner.observer.take()
+    }
+
+    /// Forget how many times the states have been entered, so the states that take an
+    /// `entry_count` count again from 1 on their next entry. The current state keeps
+    /// the count it was entered with.
+    pub fn reset_entry_counts(&mut self) {
+        self.inner.entry_counts = Default::default();
+    }
+
+    /// Handle the given event and then every event that is posted to the
+    /// [`EventSink`](crate::EventSink) in the context, until the sink is empty.
+    /// Events that were already waiting in the sink are handled first.
+    ///
+    /// At most [`limit`](crate::EventSink::limit) events are taken from the sink.
+    /// When the limit is reached before the sink is empty, the remaining events are
+    /// left in the sink and an error with their number is returned. The given event
+    /// is always handled.
+    pub async fn handle_with_sink<'ctx>(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'ctx>,
+    ) -> Result<(), crate::SinkLimitReached>
+    where
+        M::Context<'ctx>: AsMut<crate::EventSink<M::Event<'static>>>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx2> M::Context<'ctx2>: Send + Sync,
+    {
+        let mut budget = context.as_mut().limit();
+        // When the limit is reached on the waiting events, the event is still handled
+        // and the error is returned after it.
+        let _ = self.handle_posted_events(context, &mut budget).await;
+        self.handle_with_context(event, context).await;
+        self.handle_posted_events(context, &mut budget).await
+    }
+
+    /// Handle the events in the sink until it is empty or the budget is used up.
+    async fn handle_posted_events<'ctx>(
+        &mut self,
+        context: &mut M::Context<'ctx>,
+        budget: &mut usize,
+    ) -> Result<(), crate::SinkLimitReached>
+    where
+        M::Context<'ctx>: AsMut<crate::EventSink<M::Event<'static>>>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx2> M::Context<'ctx2>: Send + Sync,
+    {
+        while !context.as_mut().is_empty() {
+            if *budget == 0 {
+                let remaining = context.as_mut().len();
+                return Err(crate::SinkLimitReached { remaining });
+            }
+            *budget -= 1;
+            if let Some(event) = context.as_mut().pop() {
+                self.handle_with_context(&event, context).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle the given event, but give up when `timeout` completes before the
+    /// handlers have responded. The timeout can be any future, such as the sleep
+    /// future of the async runtime that is used.
+    ///
+    /// When the timeout wins, the handler future is dropped and no transition is
+    /// made. Once a handler has responded, the transition it causes is always
+    /// completed, even if the timeout completes in the meantime.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # use futures::executor;
+    /// # #[derive(Default)]
+    /// # struct Sensor;
+    /// #
+    /// #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq)))]
+    /// impl Sensor {
+    ///     #[state]
+    ///     async fn idle(event: &bool) -> Response<State> {
+    ///         if *event {
+    ///             // A read that never completes.
+    ///             core::future::pending::<()>().await;
+    ///         }
+    ///         Transition(State::measured())
+    ///     }
+    ///
+    ///     #[state]
+    ///     fn measured() -> Response<State> {
+    ///         Handled
+    ///     }
+    /// }
+    ///
+    /// executor::block_on(async {
+    ///     let mut state_machine = Sensor.uninitialized_state_machine().init().await;
+    ///
+    ///     let result = state_machine.handle_with_timeout(&true, async {}).await;
+    ///     assert_eq!(result, Err(Timeout));
+    ///     assert_eq!(state_machine.state(), &State::idle());
+    ///
+    ///     let result = state_machine
+    ///         .handle_with_timeout(&false, core::future::pending())
+    ///         .await;
+    ///     assert_eq!(result, Ok(()));
+    ///     assert_eq!(state_machine.state(), &State::measured());
+    /// });
+    /// ```
+    pub async fn handle_with_timeout<T>(
+        &mut self,
+        event: &M::Event<'_>,
+        timeout: T,
+    ) -> Result<(), Timeout>
+    where
+        T: Future<Output = ()>,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.handle_with_context_and_timeout(event, &mut (), timeout)
+            .await
+    }
+
+    /// Handle the given event with the given context, but give up when `timeout`
+    /// completes before the handlers have responded. See
+    /// [`handle_with_timeout`](Self::handle_with_timeout).
+    pub async fn handle_with_context_and_timeout<T>(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        timeout: T,
+    ) -> Result<(), Timeout>
+    where
+        T: Future<Output = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.inner
+            .async_handle_with_timeout(event, context, timeout)
+            .await
+    }
+
+    /// Reconstruct the event with the given tag from its raw payload and handle it.
+    /// Returns `false` without handling anything if the tag is unknown.
+    ///
+    /// # Safety
+    ///
+    /// See [`FfiEvents::try_from_tag`](crate::FfiEvents::try_from_tag).
+    pub async unsafe fn handle_raw(&mut self, tag: u32, payload: *const u8) -> bool
+    where
+        M: crate::FfiEvents<M::Event<'static>>,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+    {
+        self.handle_raw_with_context(tag, payload, &mut ()).await
+    }
+
+    /// Reconstruct the event with the given tag from its raw payload and handle it
+    /// with the given context. Returns `false` without handling anything if the tag
+    /// is unknown.
+    ///
+    /// # Safety
+    ///
+    /// See [`FfiEvents::try_from_tag`](crate::FfiEvents::try_from_tag).
+    pub async unsafe fn handle_raw_with_context(
+        &mut self,
+        tag: u32,
+        payload: *const u8,
+        context: &mut M::Context<'_>,
+    ) -> bool
+    where
+        M: crate::FfiEvents<M::Event<'static>>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        match M::try_from_tag(tag, payload) {
+            Some(event) => {
+                self.handle_with_context(&event, context).await;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// This is the same as `handle(())` in the case `Event` is of type `()`.
     pub async fn step(&mut self)
     where
@@ -286,6 +693,96 @@ where
     pub fn state(&self) -> &M::State {
         &self.inner.state
     }
+
+    /// Check whether the state machine has reached a terminal state. Once it has,
+    /// handling more events has no effect.
+    pub fn is_terminal(&self) -> bool {
+        awaitable::State::is_terminal(&self.inner.state)
+    }
+
+    /// Check whether the state machine is done, which is the case once it has reached
+    /// a terminal state. Reads naturally when driving the state machine in a loop,
+    /// e.g. `while !state_machine.is_done() { .. }`.
+    pub fn is_done(&self) -> bool {
+        self.is_terminal()
+    }
+
+    /// Get a mutable reference to the shared storage of the state machine.
+    ///
+    /// States never borrow from the shared storage, so it can safely be modified
+    /// in between events.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # #[derive(Default)]
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()")]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     async fn on(&mut self, event: &Event) -> Response<State> { Handled }
+    /// # }
+    /// #
+    /// # let future = async {
+    /// let mut state_machine = Blinky::default().uninitialized_state_machine().init().await;
+    ///
+    /// state_machine.storage_mut().led = true;
+    ///
+    /// assert!(state_machine.led);
+    /// # };
+    /// ```
+    pub fn storage_mut(&mut self) -> &mut M {
+        &mut self.inner.shared_storage
+    }
+
+    /// Get a reference to the shared storage and the current state at the same time.
+    pub fn parts(&self) -> (&M, &M::State) {
+        (&self.inner.shared_storage, &self.inner.state)
+    }
+
+    /// Get a mutable reference to the shared storage together with a reference to
+    /// the current state. Like with [`storage_mut`](Self::storage_mut), the shared
+    /// storage can safely be modified, but the state can only be read.
+    pub fn parts_mut(&mut self) -> (&mut M, &M::State) {
+        (&mut self.inner.shared_storage, &self.inner.state)
+    }
+
+    /// Call the given closure with a reference to the shared storage and return its result.
+    pub fn map_storage<R>(&self, f: impl FnOnce(&M) -> R) -> R {
+        f(&self.inner.shared_storage)
+    }
+
+    /// Call the given closure with a mutable reference to the shared storage and return
+    /// its result.
+    pub fn modify_storage<R>(&mut self, f: impl FnOnce(&mut M) -> R) -> R {
+        f(&mut self.inner.shared_storage)
+    }
+
+    /// Get the number of superstates that enclose the current state. This is `0` when
+    /// the current state has no superstate.
+    pub fn superstate_depth(&self) -> usize
+    where
+        M::State: Clone,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        // Creating a superstate requires mutable access to the state, so work on a copy.
+        self.inner.state.clone().depth() - 1
+    }
+
+    /// Check whether the current state has a superstate.
+    pub fn is_nested(&self) -> bool
+    where
+        M::State: Clone,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        self.superstate_depth() > 0
+    }
 }
 
 impl<M> Clone for InitializedStateMachine<M>
@@ -363,6 +860,25 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<M> InitializedStateMachine<M>
+where
+    M: IntoStateMachine,
+{
+    /// Serialize only the current state, leaving out the shared storage. This
+    /// means the shared storage does not have to implement `Serialize`.
+    ///
+    /// The state can be restored onto a new state machine with
+    /// [`UninitializedStateMachine::deserialize_state`].
+    pub fn serialize_state<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        M::State: serde::Serialize,
+    {
+        self.inner.serialize_state(serializer)
+    }
+}
+
 #[cfg(feature = "bevy")]
 impl<M> bevy_ecs::component::Component for InitializedStateMachine<M>
 where
@@ -390,6 +906,15 @@ where
     M::State: awaitable::State<M> + 'static + Send,
     for<'sub> M::Superstate<'sub>: awaitable::Superstate<M> + Send,
 {
+    /// Create a state machine from its shared storage and current state, for
+    /// example after restoring them from a custom persistence format. Nothing is
+    /// executed until the state machine is initialized, which runs the entry
+    /// actions into the given state.
+    pub fn from_parts(shared_storage: M, state: M::State) -> Self {
+        let inner = Inner::new(shared_storage, state);
+        UninitializedStateMachine { inner }
+    }
+
     /// Initialize the state machine by executing all entry actions towards
     /// the initial state.
     ///
@@ -425,6 +950,20 @@ where
         state_machine
     }
 
+    /// Initialize the state machine and then call `f` with the shared storage and
+    /// the initial state, for one-time setup that isn't an entry action.
+    pub async fn init_with(self, f: impl FnOnce(&mut M, &M::State)) -> InitializedStateMachine<M>
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        for<'ctx> M::Context<'ctx>: Send + Sync,
+    {
+        let mut state_machine = self.init().await;
+        let inner = &mut state_machine.inner;
+        f(&mut inner.shared_storage, &inner.state);
+        state_machine
+    }
+
     /// Initialize the state machine by executing all entry actions towards
     /// the initial state.
     ///
@@ -458,6 +997,59 @@ where
         state_machine.inner.async_init_with_context(context).await;
         state_machine
     }
+
+    /// Initialize the state machine with a context that is then kept inside the
+    /// state machine, so events can be handled without passing in the context
+    /// every time.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// #[derive(Default)]
+    /// struct Blinky;
+    ///
+    /// struct Led {
+    ///     toggles: usize,
+    /// }
+    ///
+    /// #[state_machine(initial = "State::on()")]
+    /// impl Blinky {
+    ///     #[state]
+    ///     async fn on(context: &mut Led) -> Response<State> {
+    ///         context.toggles += 1;
+    ///         Transition(State::off())
+    ///     }
+    ///
+    ///     #[state]
+    ///     async fn off(context: &mut Led) -> Response<State> {
+    ///         context.toggles += 1;
+    ///         Transition(State::on())
+    ///     }
+    /// }
+    ///
+    /// # let future = async {
+    /// let mut state_machine = Blinky
+    ///     .uninitialized_state_machine()
+    ///     .with_context(Led { toggles: 0 })
+    ///     .await;
+    ///
+    /// state_machine.handle(&()).await;
+    /// state_machine.handle(&()).await;
+    ///
+    /// assert_eq!(state_machine.context().toggles, 2);
+    /// # };
+    /// ```
+    pub async fn with_context<C>(self, mut context: C) -> ManagedContextStateMachine<M, C>
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = C>,
+        for<'evt> M::Event<'evt>: Send + Sync,
+        C: Send + Sync,
+    {
+        let state_machine = self.init_with_context(&mut context).await;
+        ManagedContextStateMachine {
+            state_machine,
+            context,
+        }
+    }
 }
 
 impl<M> Clone for UninitializedStateMachine<M>
@@ -543,3 +1135,158 @@ where
         Ok(UninitializedStateMachine { inner })
     }
 }
+
+#[cfg(feature = "serde")]
+impl<M> UninitializedStateMachine<M>
+where
+    M: IntoStateMachine,
+{
+    /// Serialize only the current state, leaving out the shared storage. This
+    /// means the shared storage does not have to implement `Serialize`.
+    pub fn serialize_state<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        M::State: serde::Serialize,
+    {
+        self.inner.serialize_state(serializer)
+    }
+
+    /// Deserialize a state that was serialized with `serialize_state` and make it
+    /// the current state. The shared storage is left untouched, so it can be
+    /// reconstructed from its own configuration. The entry actions into the
+    /// restored state are executed once the state machine is initialized.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub enum Event {
+    /// #     TimerElapsed,
+    /// # }
+    /// #
+    /// # #[state_machine(
+    /// #     initial = "State::on()",
+    /// #     state(derive(serde::Serialize, serde::Deserialize, PartialEq, Debug))
+    /// # )]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     async fn on(event: &Event) -> Response<State> { Transition(State::off()) }
+    /// #
+    /// #     #[state]
+    /// #     fn off(event: &Event) -> Response<State> { Transition(State::on()) }
+    /// # }
+    /// #
+    /// # let future = async {
+    /// let mut state_machine = Blinky { led: false }.uninitialized_state_machine().init().await;
+    /// state_machine.handle(&Event::TimerElapsed).await;
+    ///
+    /// let mut serialized = Vec::new();
+    /// state_machine
+    ///     .serialize_state(&mut serde_json::Serializer::new(&mut serialized))
+    ///     .unwrap();
+    ///
+    /// let mut restored = Blinky { led: false }.uninitialized_state_machine();
+    /// restored
+    ///     .deserialize_state(&mut serde_json::Deserializer::from_slice(&serialized))
+    ///     .unwrap();
+    ///
+    /// let restored = restored.init().await;
+    /// assert_eq!(restored.state(), &State::off());
+    /// # };
+    /// ```
+    pub fn deserialize_state<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        M::State: serde::Deserialize<'de>,
+    {
+        self.inner.deserialize_state(deserializer)
+    }
+}
+
+/// An initialized state machine that owns its context.
+///
+/// Created with [`with_context`](UninitializedStateMachine::with_context).
+pub struct ManagedContextStateMachine<M, C>
+where
+    M: IntoStateMachine,
+{
+    state_machine: InitializedStateMachine<M>,
+    context: C,
+}
+
+impl<M, C> ManagedContextStateMachine<M, C>
+where
+    for<'ctx> M: IntoStateMachine<Context<'ctx> = C> + Send,
+    M::State: awaitable::State<M> + 'static + Send,
+    for<'sub> M::Superstate<'sub>: awaitable::Superstate<M> + Send,
+{
+    /// Handle the given event with the owned context.
+    pub async fn handle(&mut self, event: &M::Event<'_>)
+    where
+        for<'evt> M::Event<'evt>: Send + Sync,
+        C: Send + Sync,
+    {
+        self.state_machine
+            .handle_with_context(event, &mut self.context)
+            .await;
+    }
+
+    /// Get an immutable reference to the current state of the state machine.
+    pub fn state(&self) -> &M::State {
+        self.state_machine.state()
+    }
+
+    /// Get an immutable reference to the context.
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    /// Get a mutable reference to the context.
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    /// Split the state machine from its context.
+    pub fn into_parts(self) -> (InitializedStateMachine<M>, C) {
+        (self.state_machine, self.context)
+    }
+}
+
+impl<M, C> Debug for ManagedContextStateMachine<M, C>
+where
+    M: IntoStateMachine + Debug,
+    M::State: Debug,
+    C: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ManagedContextStateMachine")
+            .field("state_machine", &self.state_machine)
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+impl<M, C> core::ops::Deref for ManagedContextStateMachine<M, C>
+where
+    M: IntoStateMachine,
+{
+    type Target = M;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state_machine
+    }
+}
+
+/// Error returned when the timeout of
+/// [`handle_with_timeout`](InitializedStateMachine::handle_with_timeout) completed
+/// before the event was handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout;
+
+impl core::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("timed out while handling the event")
+    }
+}
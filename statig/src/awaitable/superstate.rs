@@ -1,7 +1,10 @@
+use core::any::Any;
 use core::cmp::Ordering;
 use core::future::Future;
 use core::pin::Pin;
 
+use alloc::boxed::Box;
+
 use crate::IntoStateMachine;
 use crate::Response;
 use crate::StateOrSuperstate;
@@ -19,6 +22,20 @@ where
         context: &'fut mut M::Context<'_>,
     ) -> Pin<Box<dyn Future<Output = Response<M::State>> + 'fut + Send>>;
 
+    /// Call the handler for the current superstate, merging the effects it returns
+    /// into `effects` when they are collected. By default this calls
+    /// [`call_handler`](Superstate::call_handler).
+    #[allow(unused)]
+    fn call_handler_with_effects<'fut>(
+        &'fut mut self,
+        shared_storage: &'fut mut M,
+        event: &'fut M::Event<'_>,
+        context: &'fut mut M::Context<'_>,
+        effects: Option<&'fut mut (dyn Any + Send)>,
+    ) -> Pin<Box<dyn Future<Output = Response<M::State>> + 'fut + Send>> {
+        self.call_handler(shared_storage, event, context)
+    }
+
     #[allow(unused)]
     /// Call the entry action for the current superstate.
     fn call_entry_action<'fut>(
@@ -109,9 +126,12 @@ where
         shared_storage: &'fut mut M,
         event: &'fut M::Event<'_>,
         context: &'fut mut M::Context<'_>,
+        mut effects: Option<&'fut mut (dyn Any + Send)>,
     ) -> Pin<Box<dyn Future<Output = Response<M::State>> + 'fut + Send>> {
         Box::pin(async move {
-            let response = self.call_handler(shared_storage, event, context).await;
+            let response = self
+                .call_handler_with_effects(shared_storage, event, context, effects.as_deref_mut())
+                .await;
 
             match response {
                 Response::Handled => Response::Handled,
@@ -123,11 +143,14 @@ where
                             event,
                         );
 
-                        superstate.handle(shared_storage, event, context).await
+                        superstate
+                            .handle(shared_storage, event, context, effects)
+                            .await
                     }
                     None => Response::Super,
                 },
                 Response::Transition(state) => Response::Transition(state),
+                Response::TransitionToSuperstate => Response::TransitionToSuperstate,
             }
         })
     }
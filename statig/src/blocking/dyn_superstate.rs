@@ -0,0 +1,126 @@
+use crate::blocking::Superstate;
+use crate::{IntoStateMachine, Response};
+
+/// An object safe version of [`Superstate`], for hierarchies that are only known
+/// at runtime (e.g. superstates provided by plugins).
+///
+/// A state machine uses trait objects as its superstates by setting
+/// `type Superstate<'sub> = &'sub mut dyn DynSuperstate<Self>`. Every call to a
+/// superstate then goes through a vtable, and because trait objects have no
+/// discriminant, superstates are told apart by their [`name`](DynSuperstate::name)
+/// when looking for the common ancestor of a transition. When the state type is
+/// not an enum either, [`State::is_same`](crate::blocking::State::is_same) should be
+/// implemented as well. The state type can't borrow data, it must be `'static`.
+///
+/// For hierarchies that are known at compile time the superstate enum generated
+/// by the macro is faster, as its handlers are called directly and can be inlined.
+///
+/// ```
+/// # use statig::blocking::{self, *};
+/// struct Plugin {
+///     name: &'static str,
+///     handled: usize,
+/// }
+///
+/// impl DynSuperstate<Machine> for Plugin {
+///     fn name(&self) -> &'static str {
+///         self.name
+///     }
+///
+///     fn call_handler(&mut self, _: &mut Machine, _: &(), _: &mut ()) -> Response<State> {
+///         self.handled += 1;
+///         Handled
+///     }
+/// }
+///
+/// struct Machine;
+///
+/// struct State {
+///     plugin: Plugin,
+/// }
+///
+/// impl IntoStateMachine for Machine {
+///     type State = State;
+///     type Superstate<'sub> = &'sub mut dyn DynSuperstate<Self>;
+///     type Event<'evt> = ();
+///     type Context<'ctx> = ();
+///     const INITIAL: State = State {
+///         plugin: Plugin { name: "plugin", handled: 0 },
+///     };
+/// }
+///
+/// impl blocking::State<Machine> for State {
+///     fn call_handler(&mut self, _: &mut Machine, _: &(), _: &mut ()) -> Response<State> {
+///         Super
+///     }
+///
+///     fn superstate(&mut self) -> Option<&mut dyn DynSuperstate<Machine>> {
+///         Some(&mut self.plugin)
+///     }
+/// }
+///
+/// let mut state_machine = Machine.uninitialized_state_machine().init();
+/// state_machine.handle(&());
+///
+/// assert_eq!(state_machine.state().plugin.handled, 1);
+/// ```
+pub trait DynSuperstate<M>
+where
+    M: IntoStateMachine,
+{
+    /// The name of the superstate, which must be unique within the hierarchy.
+    fn name(&self) -> &'static str;
+
+    /// Call the handler for the superstate.
+    fn call_handler(
+        &mut self,
+        shared_storage: &mut M,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> Response<M::State>;
+
+    #[allow(unused)]
+    /// Call the entry action for the superstate.
+    fn call_entry_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {}
+
+    #[allow(unused)]
+    /// Call the exit action for the superstate.
+    fn call_exit_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {}
+
+    /// Return the superstate of the superstate, if there is one.
+    fn superstate(&mut self) -> Option<&mut dyn DynSuperstate<M>> {
+        None
+    }
+}
+
+impl<M> Superstate<M> for &mut dyn DynSuperstate<M>
+where
+    M: IntoStateMachine,
+    M::State: 'static,
+    M: for<'sub> IntoStateMachine<Superstate<'sub> = &'sub mut dyn DynSuperstate<M>>,
+{
+    fn call_handler(
+        &mut self,
+        shared_storage: &mut M,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> Response<M::State> {
+        (**self).call_handler(shared_storage, event, context)
+    }
+
+    fn call_entry_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {
+        (**self).call_entry_action(shared_storage, context)
+    }
+
+    fn call_exit_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {
+        (**self).call_exit_action(shared_storage, context)
+    }
+
+    fn superstate(&mut self) -> Option<M::Superstate<'_>> {
+        (**self).superstate()
+    }
+
+    fn is_same(&self, other: &M::Superstate<'_>) -> bool {
+        self.name() == other.name()
+    }
+}
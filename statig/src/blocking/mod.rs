@@ -1,5 +1,7 @@
 //! Module for blocking (sync) mode.
 
+mod dyn_superstate;
+mod pure_state_machine;
 mod state;
 mod state_machine;
 mod superstate;
@@ -7,6 +9,8 @@ mod superstate;
 pub use crate::Response::{self, *};
 pub use crate::*;
 
+pub use dyn_superstate::*;
+pub use pure_state_machine::*;
 pub use state::*;
 pub use state_machine::*;
 pub use superstate::*;
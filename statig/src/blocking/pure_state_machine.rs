@@ -0,0 +1,13 @@
+use crate::{IntoStateMachine, Response};
+
+/// A state machine whose handlers only need shared access to the shared storage and
+/// the state, so events can be classified through a shared reference.
+///
+/// The `state_machine` macro implements this trait for blocking state machines when
+/// every handler takes `&self` or no receiver at all, takes no context, only
+/// borrows its local storage immutably and the superstates have no local storage.
+pub trait PureStateMachine: IntoStateMachine {
+    /// Let the handlers of the given state and its superstates respond to the event.
+    fn classify_event(&self, state: &Self::State, event: &Self::Event<'_>)
+        -> Response<Self::State>;
+}
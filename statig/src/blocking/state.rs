@@ -1,3 +1,5 @@
+use core::any::Any;
+
 use crate::blocking::Superstate;
 use crate::blocking::SuperstateExt;
 use crate::IntoStateMachine;
@@ -18,10 +20,75 @@ where
         context: &mut M::Context<'_>,
     ) -> Response<Self>;
 
+    /// Call the handler for the current state and let it handle the given event,
+    /// merging the effects it returns into `effects` when they are collected. By
+    /// default this calls [`call_handler`](State::call_handler).
+    #[allow(unused)]
+    fn call_handler_with_effects(
+        &mut self,
+        shared_storage: &mut M,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: Option<&mut dyn Any>,
+    ) -> Response<Self> {
+        self.call_handler(shared_storage, event, context)
+    }
+
+    /// Call the handler for the current state and let it handle the given event,
+    /// passing `entry_count` to a handler that takes the number of times its state
+    /// has been entered. By default this calls
+    /// [`call_handler_with_effects`](State::call_handler_with_effects).
+    #[allow(unused)]
+    fn call_handler_with_entry_count(
+        &mut self,
+        shared_storage: &mut M,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: Option<&mut dyn Any>,
+        entry_count: u32,
+    ) -> Response<Self> {
+        self.call_handler_with_effects(shared_storage, event, context, effects)
+    }
+
+    /// Call the handler for the current state and let it handle the given event,
+    /// which the handler is allowed to mutate. By default this calls
+    /// [`call_handler`](State::call_handler).
+    fn call_handler_mut(
+        &mut self,
+        shared_storage: &mut M,
+        event: &mut M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> Response<Self> {
+        self.call_handler(shared_storage, event, context)
+    }
+
+    #[allow(unused)]
+    /// Call the catch-all handler with an event that was deferred by the current state
+    /// and all of its superstates.
+    fn call_catch_all(
+        &mut self,
+        shared_storage: &mut M,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> Response<Self> {
+        Response::Super
+    }
+
     #[allow(unused)]
     /// Call the entry action for the current state.
     fn call_entry_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {}
 
+    /// Call the entry action for the current state and return whether the state
+    /// accepts being entered. Returning `false` vetoes the transition into this state.
+    fn try_call_entry_action(
+        &mut self,
+        shared_storage: &mut M,
+        context: &mut M::Context<'_>,
+    ) -> bool {
+        self.call_entry_action(shared_storage, context);
+        true
+    }
+
     #[allow(unused)]
     /// Call the exit action for the current state.
     fn call_exit_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {}
@@ -30,6 +97,30 @@ where
     fn superstate(&mut self) -> Option<M::Superstate<'_>> {
         None
     }
+
+    /// Return the initial state of the superstate of the current state, if there is one.
+    fn superstate_initial_state(&self) -> Option<Self> {
+        None
+    }
+
+    /// Check whether `self` and `other` are the same state when determining which
+    /// entry and exit actions a transition runs. By default the discriminants of
+    /// the state enum are compared.
+    fn is_same(&self, other: &Self) -> bool {
+        core::mem::discriminant(self) == core::mem::discriminant(other)
+    }
+
+    /// Check whether the current state is a terminal state. A terminal state
+    /// considers every event handled and never transitions to another state.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+
+    /// Return the index of the counter that keeps the number of times the current
+    /// state has been entered, if its handler takes the entry count.
+    fn entry_counter(&self) -> Option<usize> {
+        None
+    }
 }
 
 /// Extensions for `State` trait.
@@ -41,7 +132,7 @@ where
 {
     /// Check if two states are the same.
     fn same_state(lhs: &Self, rhs: &Self) -> bool {
-        core::mem::discriminant(lhs) == core::mem::discriminant(rhs)
+        lhs.is_same(rhs)
     }
 
     /// Get the depth of the current state.
@@ -92,13 +183,21 @@ where
         shared_storage: &mut M,
         event: &M::Event<'_>,
         context: &mut M::Context<'_>,
+        mut effects: Option<&mut dyn Any>,
+        entry_count: u32,
     ) -> Response<Self>
     where
         Self: Sized,
     {
         M::ON_DISPATCH(shared_storage, StateOrSuperstate::State(self), event);
 
-        let response = self.call_handler(shared_storage, event, context);
+        let response = self.call_handler_with_entry_count(
+            shared_storage,
+            event,
+            context,
+            effects.as_deref_mut(),
+            entry_count,
+        );
 
         match response {
             Response::Handled => Response::Handled,
@@ -110,11 +209,46 @@ where
                         event,
                     );
 
-                    superstate.handle(shared_storage, event, context)
+                    superstate.handle(shared_storage, event, context, effects)
                 }
                 None => Response::Super,
             },
             Response::Transition(state) => Response::Transition(state),
+            Response::TransitionToSuperstate => Response::TransitionToSuperstate,
+        }
+    }
+
+    /// Same as [`handle`](StateExt::handle), but the handlers are allowed to mutate
+    /// the event.
+    fn handle_mut(
+        &mut self,
+        shared_storage: &mut M,
+        event: &mut M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> Response<Self>
+    where
+        Self: Sized,
+    {
+        M::ON_DISPATCH(shared_storage, StateOrSuperstate::State(self), event);
+
+        let response = self.call_handler_mut(shared_storage, event, context);
+
+        match response {
+            Response::Handled => Response::Handled,
+            Response::Super => match self.superstate() {
+                Some(mut superstate) => {
+                    M::ON_DISPATCH(
+                        shared_storage,
+                        StateOrSuperstate::Superstate(&superstate),
+                        event,
+                    );
+
+                    superstate.handle_mut(shared_storage, event, context)
+                }
+                None => Response::Super,
+            },
+            Response::Transition(state) => Response::Transition(state),
+            Response::TransitionToSuperstate => Response::TransitionToSuperstate,
         }
     }
 
@@ -133,6 +267,34 @@ where
         }
     }
 
+    /// Same as [`enter`](StateExt::enter), but the entry action of the current state can
+    /// veto the entry. When it does, the superstates that were entered along the way are
+    /// exited again and `false` is returned.
+    fn try_enter(
+        &mut self,
+        shared_storage: &mut M,
+        context: &mut M::Context<'_>,
+        levels: usize,
+    ) -> bool {
+        if levels == 0 {
+            return true;
+        }
+
+        if let Some(mut superstate) = self.superstate() {
+            superstate.enter(shared_storage, context, levels - 1);
+        }
+
+        if self.try_call_entry_action(shared_storage, context) {
+            return true;
+        }
+
+        if let Some(mut superstate) = self.superstate() {
+            superstate.exit(shared_storage, context, levels - 1);
+        }
+
+        false
+    }
+
     /// Starting from the current state, climb a given amount of levels and execute all the
     /// the exit actions while going up to a certain superstate.
     fn exit(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>, levels: usize) {
@@ -1,7 +1,7 @@
 use core::fmt::Debug;
 
-use super::blocking;
-use crate::{Inner, IntoStateMachine};
+use super::blocking::{self, StateExt as _};
+use crate::{Inner, IntoStateMachine, Response, Unhandled};
 
 /// A state machine where the shared storage is of type `Self`.
 pub trait IntoStateMachineExt: IntoStateMachine
@@ -13,10 +13,7 @@ where
     where
         Self: Sized,
     {
-        let inner = Inner {
-            shared_storage: self,
-            state: Self::INITIAL,
-        };
+        let inner = Inner::new(self, Self::INITIAL);
         StateMachine {
             inner,
             initialized: false,
@@ -26,10 +23,7 @@ where
     /// Create an uninitialized state machine that must be explicitly initialized with
     /// [`init`](UninitializedStateMachine::init).
     fn uninitialized_state_machine(self) -> UninitializedStateMachine<Self> {
-        let inner = Inner {
-            shared_storage: self,
-            state: Self::INITIAL,
-        };
+        let inner = Inner::new(self, Self::INITIAL);
         UninitializedStateMachine { inner }
     }
 }
@@ -74,6 +68,49 @@ where
         }
     }
 
+    /// Turn the state machine into an [`InitializedStateMachine`]. If the state machine
+    /// is still uninitialized, it will be initialized first.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # #[derive(Default)]
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()")]
+    /// # impl Blinky {
+    /// #     #[state(entry_action = "enter_on")]
+    /// #     fn on(event: &Event) -> Response<State> { Handled }
+    /// #
+    /// #     #[action]
+    /// #     fn enter_on(&mut self) { self.led = true; }
+    /// # }
+    /// #
+    /// let state_machine: InitializedStateMachine<Blinky> =
+    ///     Blinky::default().state_machine().into_initialized();
+    ///
+    /// assert!(state_machine.led);
+    /// ```
+    pub fn into_initialized(self) -> InitializedStateMachine<M>
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+    {
+        self.into_initialized_with_context(&mut ())
+    }
+
+    /// Turn the state machine into an [`InitializedStateMachine`]. If the state machine
+    /// is still uninitialized, it will be initialized first with the given context.
+    pub fn into_initialized_with_context(
+        mut self,
+        context: &mut M::Context<'_>,
+    ) -> InitializedStateMachine<M> {
+        self.init_with_context(context);
+        InitializedStateMachine { inner: self.inner }
+    }
+
     /// Handle an event. If the state machine is still uninitialized, it will be initialized
     /// before handling the event.
     pub fn handle(&mut self, event: &M::Event<'_>)
@@ -93,6 +130,50 @@ where
         self.inner.handle_with_context(event, context);
     }
 
+    /// Handle an event and return an error with the event if it was not handled by
+    /// any state or superstate. If the state machine is still uninitialized, it will
+    /// be initialized before handling the event.
+    pub fn try_handle<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+    {
+        self.try_handle_with_context(event, &mut ())
+    }
+
+    /// Handle an event and return an error with the event if it was not handled by
+    /// any state or superstate. If the state machine is still uninitialized, it will
+    /// be initialized before handling the event.
+    pub fn try_handle_with_context<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+        context: &mut M::Context<'_>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+    {
+        if !self.initialized {
+            self.inner.init_with_context(context);
+            self.initialized = true;
+        }
+        match self.inner.handle_with_context(event, context) {
+            true => Ok(()),
+            false => Err(Unhandled { event }),
+        }
+    }
+
+    /// Handle an event with a context that is created with `Default::default()`. If the
+    /// state machine is still uninitialized, it will be initialized before handling the event.
+    pub fn handle_default_context(&mut self, event: &M::Event<'_>)
+    where
+        for<'ctx> M::Context<'ctx>: Default,
+    {
+        self.handle_with_context(event, &mut Default::default());
+    }
+
     pub fn step(&mut self)
     where
         for<'evt, 'ctx> M: IntoStateMachine<Event<'evt> = (), Context<'ctx> = ()>,
@@ -111,6 +192,30 @@ where
     pub fn state(&self) -> &M::State {
         &self.inner.state
     }
+
+    /// Check whether the state machine has reached a terminal state. Once it has,
+    /// handling more events has no effect.
+    pub fn is_terminal(&self) -> bool {
+        blocking::State::is_terminal(&self.inner.state)
+    }
+
+    /// Get the number of superstates that enclose the current state. This is `0` when
+    /// the current state has no superstate.
+    pub fn superstate_depth(&self) -> usize
+    where
+        M::State: Clone,
+    {
+        // Creating a superstate requires mutable access to the state, so work on a copy.
+        self.inner.state.clone().depth() - 1
+    }
+
+    /// Check whether the current state has a superstate.
+    pub fn is_nested(&self) -> bool
+    where
+        M::State: Clone,
+    {
+        self.superstate_depth() > 0
+    }
 }
 
 impl<M> Clone for StateMachine<M>
@@ -142,15 +247,26 @@ where
 {
 }
 
+impl<M> Debug for StateMachine<M>
+where
+    M: IntoStateMachine + Debug,
+    M::State: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("StateMachine")
+            .field("initialized", &self.initialized)
+            .field("shared_storage", &self.inner.shared_storage as &dyn Debug)
+            .field("state", &self.inner.state as &dyn Debug)
+            .finish()
+    }
+}
+
 impl<M> Default for StateMachine<M>
 where
     M: IntoStateMachine + Default,
 {
     fn default() -> Self {
-        let inner = Inner {
-            shared_storage: M::default(),
-            state: M::INITIAL,
-        };
+        let inner = Inner::new(M::default(), M::INITIAL);
         Self {
             inner,
             initialized: false,
@@ -237,12 +353,381 @@ where
     /// Handle the given event.
     pub fn handle_with_context(&mut self, event: &M::Event<'_>, context: &mut M::Context<'_>)
     where
-        M: IntoStateMachine,
         for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
     {
         self.inner.handle_with_context(event, context);
     }
 
+    /// Get the response of the current state and its superstates to the given event
+    /// without acting on it, so no transition is taken and no actions are run. Only a
+    /// shared reference to the state machine is needed, so it can be shared while
+    /// events are classified. `on_dispatch` is not called and the catch-all state is
+    /// not consulted.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// #[derive(Default)]
+    /// struct Filter {
+    ///     threshold: u32,
+    /// }
+    ///
+    /// #[state_machine(initial = "State::open()", state(derive(Debug, PartialEq)))]
+    /// impl Filter {
+    ///     #[state]
+    ///     fn open(&self, event: &u32) -> Response<State> {
+    ///         match *event > self.threshold {
+    ///             true => Transition(State::closed()),
+    ///             false => Handled,
+    ///         }
+    ///     }
+    ///
+    ///     #[state]
+    ///     fn closed() -> Response<State> {
+    ///         Handled
+    ///     }
+    /// }
+    ///
+    /// let state_machine = Filter { threshold: 10 }.uninitialized_state_machine().init();
+    ///
+    /// assert_eq!(state_machine.classify(&3), Handled);
+    /// assert_eq!(state_machine.classify(&12), Transition(State::closed()));
+    /// assert_eq!(state_machine.state(), &State::open());
+    /// ```
+    pub fn classify(&self, event: &M::Event<'_>) -> Response<M::State>
+    where
+        M: blocking::PureStateMachine,
+    {
+        M::classify_event(&self.inner.shared_storage, &self.inner.state, event)
+    }
+
+    /// Handle the given event and return an error with the event if it was not handled
+    /// by any state or superstate.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// #[derive(Default)]
+    /// struct Door;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Event {
+    ///     Open,
+    ///     Knock,
+    /// }
+    ///
+    /// #[state_machine(initial = "State::closed()", require_handled)]
+    /// impl Door {
+    ///     #[state]
+    ///     fn closed(event: &Event) -> Response<State> {
+    ///         match event {
+    ///             Event::Open => Handled,
+    ///             _ => Super,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let mut state_machine = Door.uninitialized_state_machine().init();
+    ///
+    /// assert!(state_machine.try_handle(&Event::Open).is_ok());
+    ///
+    /// let unhandled = state_machine.try_handle(&Event::Knock).unwrap_err();
+    /// assert_eq!(unhandled.event, &Event::Knock);
+    /// ```
+    pub fn try_handle<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.try_handle_with_context(event, &mut ())
+    }
+
+    /// Handle the given event with the given context and return an error with the
+    /// event if it was not handled by any state or superstate.
+    pub fn try_handle_with_context<'evt>(
+        &mut self,
+        event: &'evt M::Event<'evt>,
+        context: &mut M::Context<'_>,
+    ) -> Result<(), Unhandled<'evt, M>>
+    where
+        M: crate::RequireHandled,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        match self.inner.handle_with_context(event, context) {
+            true => Ok(()),
+            false => Err(Unhandled { event }),
+        }
+    }
+
+    /// Handle the given event with a context that is created with `Default::default()`.
+    pub fn handle_default_context(&mut self, event: &M::Event<'_>)
+    where
+        for<'ctx> M::Context<'ctx>: Default,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.handle_with_context(event, &mut Default::default());
+    }
+
+    /// Handle the given event and merge the effects that the handlers return next to
+    /// their response (e.g. `(Effects, Response<State>)`) into `effects` with `|=`.
+    pub fn handle_with_effects(&mut self, event: &M::Event<'_>, effects: &mut M::Effects)
+    where
+        M: crate::HasEffects,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.handle_with_context_and_effects(event, &mut (), effects);
+    }
+
+    /// Handle the given event with the given context and merge the effects that the
+    /// handlers return into `effects`, see [`handle_with_effects`](Self::handle_with_effects).
+    pub fn handle_with_context_and_effects(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: &mut M::Effects,
+    ) where
+        M: crate::HasEffects,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.inner
+            .handle_with_effects(event, context, Some(effects));
+    }
+
+    /// Handle the given event and return the wall-clock time it took. This includes
+    /// the time spent in the handlers and, when a transition is taken, in the exit
+    /// and entry actions and `on_transition`.
+    #[cfg(feature = "std")]
+    pub fn handle_timed(&mut self, event: &M::Event<'_>) -> std::time::Duration
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.handle_with_context_timed(event, &mut ())
+    }
+
+    /// Handle the given event with the given context and return the wall-clock time
+    /// it took, see [`handle_timed`](Self::handle_timed).
+    #[cfg(feature = "std")]
+    pub fn handle_with_context_timed(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> std::time::Duration
+    where
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        let start = std::time::Instant::now();
+        self.handle_with_context(event, context);
+        start.elapsed()
+    }
+
+    /// Register a callback that is called after every transition with the source
+    /// and target state, next to the `on_transition` callback of the state machine.
+    /// This lets tools such as debuggers observe a state machine at runtime. A
+    /// previously registered observer is replaced. The observer is not cloned
+    /// along with the state machine.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # use std::sync::{Arc, Mutex};
+    /// # #[derive(Default)]
+    /// # pub struct Blinky;
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()", state(derive(Debug, Clone)))]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     fn on(event: &Event) -> Response<State> { Transition(State::off()) }
+    /// #
+    /// #     #[state]
+    /// #     fn off(event: &Event) -> Response<State> { Transition(State::on()) }
+    /// # }
+    /// #
+    /// let transitions = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let mut state_machine = Blinky.uninitialized_state_machine().init();
+    /// let log = transitions.clone();
+    /// state_machine.set_transition_observer(Box::new(move |source, target| {
+    ///     log.lock().unwrap().push(format!("{source:?} -> {target:?}"));
+    /// }));
+    ///
+    /// state_machine.handle(&Event);
+    ///
+    /// assert_eq!(*transitions.lock().unwrap(), ["On -> Off"]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn set_transition_observer(&mut self, observer: crate::TransitionObserver<M>) {
+        self.inner.observer = Some(observer);
+    }
+
+    /// Remove the callback that was registered with
+    /// [`set_transition_observer`](Self::set_transition_observer) and return it.
+    #[cfg(feature = "std")]
+    pub fn take_transition_observer(&mut self) -> Option<crate::TransitionObserver<M>> {
+        self.inner.observer.take()
+    }
+
+    /// Forget how many times the states have been entered, so the states that take an
+    /// `entry_count` count again from 1 on their next entry. The current state keeps
+    /// the count it was entered with.
+    pub fn reset_entry_counts(&mut self) {
+        self.inner.entry_counts = Default::default();
+    }
+
+    /// Handle the given event and let the handlers mutate it, for example to build
+    /// up a response. This is how state machines with handlers that take the event
+    /// as `&mut` must be driven, but it works for any state machine.
+    ///
+    /// # Panics
+    ///
+    /// The other ways of submitting events panic when a handler takes the event as
+    /// `&mut`.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// #[derive(Default)]
+    /// struct Server;
+    ///
+    /// struct Request {
+    ///     response: Vec<&'static str>,
+    /// }
+    ///
+    /// #[state_machine(initial = "State::ready()")]
+    /// impl Server {
+    ///     #[state(superstate = "online")]
+    ///     fn ready(event: &mut Request) -> Response<State> {
+    ///         event.response.push("ready");
+    ///         Super
+    ///     }
+    ///
+    ///     #[superstate]
+    ///     fn online(event: &Request) -> Response<State> {
+    ///         Handled
+    ///     }
+    /// }
+    ///
+    /// let mut state_machine = Server.uninitialized_state_machine().init();
+    /// let mut request = Request { response: Vec::new() };
+    ///
+    /// state_machine.handle_mut(&mut request);
+    ///
+    /// assert_eq!(request.response, ["ready"]);
+    /// ```
+    pub fn handle_mut(&mut self, event: &mut M::Event<'_>)
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.handle_mut_with_context(event, &mut ());
+    }
+
+    /// Handle the given event with the given context and let the handlers mutate
+    /// the event.
+    pub fn handle_mut_with_context(
+        &mut self,
+        event: &mut M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) where
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.inner.handle_mut_with_context(event, context);
+    }
+
+    /// Handle the given event and then every event that is posted to the
+    /// [`EventSink`](crate::EventSink) in the context, until the sink is empty.
+    /// Events that were already waiting in the sink are handled first.
+    ///
+    /// At most [`limit`](crate::EventSink::limit) events are taken from the sink.
+    /// When the limit is reached before the sink is empty, the remaining events are
+    /// left in the sink and an error with their number is returned. The given event
+    /// is always handled.
+    #[cfg(feature = "alloc")]
+    pub fn handle_with_sink<'ctx>(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'ctx>,
+    ) -> Result<(), crate::SinkLimitReached>
+    where
+        M::Context<'ctx>: AsMut<crate::EventSink<M::Event<'static>>>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        let mut budget = context.as_mut().limit();
+        // When the limit is reached on the waiting events, the event is still handled
+        // and the error is returned after it.
+        let _ = self.handle_posted_events(context, &mut budget);
+        self.handle_with_context(event, context);
+        self.handle_posted_events(context, &mut budget)
+    }
+
+    /// Handle the events in the sink until it is empty or the budget is used up.
+    #[cfg(feature = "alloc")]
+    fn handle_posted_events<'ctx>(
+        &mut self,
+        context: &mut M::Context<'ctx>,
+        budget: &mut usize,
+    ) -> Result<(), crate::SinkLimitReached>
+    where
+        M::Context<'ctx>: AsMut<crate::EventSink<M::Event<'static>>>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        while !context.as_mut().is_empty() {
+            if *budget == 0 {
+                let remaining = context.as_mut().len();
+                return Err(crate::SinkLimitReached { remaining });
+            }
+            *budget -= 1;
+            if let Some(event) = context.as_mut().pop() {
+                self.handle_with_context(&event, context);
+            }
+        }
+        Ok(())
+    }
+
+    /// Reconstruct the event with the given tag from its raw payload and handle it.
+    /// Returns `false` without handling anything if the tag is unknown.
+    ///
+    /// # Safety
+    ///
+    /// See [`FfiEvents::try_from_tag`](crate::FfiEvents::try_from_tag).
+    pub unsafe fn handle_raw(&mut self, tag: u32, payload: *const u8) -> bool
+    where
+        M: crate::FfiEvents<M::Event<'static>>,
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.handle_raw_with_context(tag, payload, &mut ())
+    }
+
+    /// Reconstruct the event with the given tag from its raw payload and handle it
+    /// with the given context. Returns `false` without handling anything if the tag
+    /// is unknown.
+    ///
+    /// # Safety
+    ///
+    /// See [`FfiEvents::try_from_tag`](crate::FfiEvents::try_from_tag).
+    pub unsafe fn handle_raw_with_context(
+        &mut self,
+        tag: u32,
+        payload: *const u8,
+        context: &mut M::Context<'_>,
+    ) -> bool
+    where
+        M: crate::FfiEvents<M::Event<'static>>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        match M::try_from_tag(tag, payload) {
+            Some(event) => {
+                self.handle_with_context(&event, context);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// This is the same as `handle(())` in the case `Event` is of type `()`.
     pub fn step(&mut self)
     where
@@ -265,6 +750,115 @@ where
     pub fn state(&self) -> &M::State {
         &self.inner.state
     }
+
+    /// Check whether the state machine has reached a terminal state. Once it has,
+    /// handling more events has no effect.
+    pub fn is_terminal(&self) -> bool {
+        blocking::State::is_terminal(&self.inner.state)
+    }
+
+    /// Check whether the state machine is done, which is the case once it has reached
+    /// a terminal state. Reads naturally when driving the state machine in a loop,
+    /// e.g. `while !state_machine.is_done() { .. }`.
+    pub fn is_done(&self) -> bool {
+        self.is_terminal()
+    }
+
+    /// Get a mutable reference to the shared storage of the state machine.
+    ///
+    /// States never borrow from the shared storage, so it can safely be modified
+    /// in between events.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # #[derive(Default)]
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()")]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     fn on(&mut self, event: &Event) -> Response<State> { Handled }
+    /// # }
+    /// #
+    /// let mut state_machine = Blinky::default().uninitialized_state_machine().init();
+    ///
+    /// state_machine.storage_mut().led = true;
+    ///
+    /// assert!(state_machine.led);
+    /// ```
+    pub fn storage_mut(&mut self) -> &mut M {
+        &mut self.inner.shared_storage
+    }
+
+    /// Get a reference to the shared storage and the current state at the same time.
+    pub fn parts(&self) -> (&M, &M::State) {
+        (&self.inner.shared_storage, &self.inner.state)
+    }
+
+    /// Get a mutable reference to the shared storage together with a reference to
+    /// the current state. Like with [`storage_mut`](Self::storage_mut), the shared
+    /// storage can safely be modified, but the state can only be read.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # #[derive(Default)]
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()", state(derive(PartialEq)))]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     fn on(&mut self, event: &Event) -> Response<State> { Handled }
+    /// # }
+    /// #
+    /// let mut state_machine = Blinky::default().uninitialized_state_machine().init();
+    ///
+    /// let (blinky, state) = state_machine.parts_mut();
+    /// blinky.led = state == &State::on();
+    ///
+    /// assert!(state_machine.led);
+    /// ```
+    pub fn parts_mut(&mut self) -> (&mut M, &M::State) {
+        (&mut self.inner.shared_storage, &self.inner.state)
+    }
+
+    /// Call the given closure with a reference to the shared storage and return its result.
+    pub fn map_storage<R>(&self, f: impl FnOnce(&M) -> R) -> R {
+        f(&self.inner.shared_storage)
+    }
+
+    /// Call the given closure with a mutable reference to the shared storage and return
+    /// its result.
+    pub fn modify_storage<R>(&mut self, f: impl FnOnce(&mut M) -> R) -> R {
+        f(&mut self.inner.shared_storage)
+    }
+
+    /// Get the number of superstates that enclose the current state. This is `0` when
+    /// the current state has no superstate.
+    pub fn superstate_depth(&self) -> usize
+    where
+        M::State: Clone,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        // Creating a superstate requires mutable access to the state, so work on a copy.
+        self.inner.state.clone().depth() - 1
+    }
+
+    /// Check whether the current state has a superstate.
+    pub fn is_nested(&self) -> bool
+    where
+        M::State: Clone,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        self.superstate_depth() > 0
+    }
 }
 
 impl<M> Clone for InitializedStateMachine<M>
@@ -342,6 +936,25 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
+impl<M> InitializedStateMachine<M>
+where
+    M: IntoStateMachine,
+{
+    /// Serialize only the current state, leaving out the shared storage. This
+    /// means the shared storage does not have to implement `Serialize`.
+    ///
+    /// The state can be restored onto a new state machine with
+    /// [`UninitializedStateMachine::deserialize_state`].
+    pub fn serialize_state<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        M::State: serde::Serialize,
+    {
+        self.inner.serialize_state(serializer)
+    }
+}
+
 #[cfg(feature = "bevy")]
 impl<M> bevy_ecs::component::Component for InitializedStateMachine<M>
 where
@@ -368,6 +981,44 @@ where
     M: IntoStateMachine,
     M::State: blocking::State<M>,
 {
+    /// Create a state machine from its shared storage and current state, for
+    /// example after restoring them from a custom persistence format. Nothing is
+    /// executed until the state machine is initialized, which runs the entry
+    /// actions into the given state.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # use statig::blocking::UninitializedStateMachine;
+    /// # #[derive(Default)]
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()", state(derive(PartialEq, Debug)))]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     fn on(event: &Event) -> Response<State> { Transition(State::off()) }
+    /// #
+    /// #     #[state(entry_action = "enter_off")]
+    /// #     fn off(event: &Event) -> Response<State> { Transition(State::on()) }
+    /// #
+    /// #     #[action]
+    /// #     fn enter_off(&mut self) { self.led = false; }
+    /// # }
+    /// #
+    /// let blinky = Blinky { led: true };
+    /// let state_machine = UninitializedStateMachine::from_parts(blinky, State::off()).init();
+    ///
+    /// assert_eq!(state_machine.state(), &State::off());
+    /// assert!(!state_machine.led);
+    /// ```
+    pub fn from_parts(shared_storage: M, state: M::State) -> Self {
+        let inner = Inner::new(shared_storage, state);
+        UninitializedStateMachine { inner }
+    }
+
     /// Initialize the state machine by executing all entry actions towards
     /// the initial state.
     ///
@@ -402,6 +1053,49 @@ where
         state_machine
     }
 
+    /// Initialize the state machine and then call `f` with the shared storage and
+    /// the initial state, for one-time setup that isn't an entry action.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # #[derive(Default)]
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// #     started_in: Option<State>,
+    /// # }
+    /// #
+    /// # pub struct Event;
+    /// #
+    /// # #[state_machine(initial = "State::on()", state(derive(Debug, Clone, PartialEq)))]
+    /// # impl Blinky {
+    /// #     #[state(entry_action = "enter_on")]
+    /// #     fn on(event: &Event) -> Response<State> { Handled }
+    /// #
+    /// #     #[action]
+    /// #     fn enter_on(&mut self) { self.led = true; }
+    /// # }
+    /// #
+    /// let state_machine = Blinky::default()
+    ///     .uninitialized_state_machine()
+    ///     .init_with(|blinky, state| {
+    ///         // The entry actions have already been executed.
+    ///         assert!(blinky.led);
+    ///         blinky.started_in = Some(state.clone());
+    ///     });
+    ///
+    /// assert_eq!(state_machine.started_in, Some(State::on()));
+    /// ```
+    pub fn init_with(self, f: impl FnOnce(&mut M, &M::State)) -> InitializedStateMachine<M>
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = ()>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        let mut state_machine = self.init();
+        let inner = &mut state_machine.inner;
+        f(&mut inner.shared_storage, &inner.state);
+        state_machine
+    }
+
     /// Initialize the state machine by executing all entry actions towards
     /// the initial state.
     ///
@@ -434,6 +1128,55 @@ where
         state_machine.inner.init_with_context(context);
         state_machine
     }
+
+    /// Initialize the state machine with a context that is then kept inside the
+    /// state machine, so events can be handled without passing in the context
+    /// every time.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// #[derive(Default)]
+    /// struct Blinky;
+    ///
+    /// struct Led {
+    ///     toggles: usize,
+    /// }
+    ///
+    /// #[state_machine(initial = "State::on()")]
+    /// impl Blinky {
+    ///     #[state]
+    ///     fn on(context: &mut Led) -> Response<State> {
+    ///         context.toggles += 1;
+    ///         Transition(State::off())
+    ///     }
+    ///
+    ///     #[state]
+    ///     fn off(context: &mut Led) -> Response<State> {
+    ///         context.toggles += 1;
+    ///         Transition(State::on())
+    ///     }
+    /// }
+    ///
+    /// let mut state_machine = Blinky
+    ///     .uninitialized_state_machine()
+    ///     .with_context(Led { toggles: 0 });
+    ///
+    /// state_machine.handle(&());
+    /// state_machine.handle(&());
+    ///
+    /// assert_eq!(state_machine.context().toggles, 2);
+    /// ```
+    pub fn with_context<C>(self, mut context: C) -> ManagedContextStateMachine<M, C>
+    where
+        for<'ctx> M: IntoStateMachine<Context<'ctx> = C>,
+        for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+    {
+        let state_machine = self.init_with_context(&mut context);
+        ManagedContextStateMachine {
+            state_machine,
+            context,
+        }
+    }
 }
 
 impl<M> Clone for UninitializedStateMachine<M>
@@ -519,3 +1262,139 @@ where
         Ok(UninitializedStateMachine { inner })
     }
 }
+
+#[cfg(feature = "serde")]
+impl<M> UninitializedStateMachine<M>
+where
+    M: IntoStateMachine,
+{
+    /// Serialize only the current state, leaving out the shared storage. This
+    /// means the shared storage does not have to implement `Serialize`.
+    pub fn serialize_state<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        M::State: serde::Serialize,
+    {
+        self.inner.serialize_state(serializer)
+    }
+
+    /// Deserialize a state that was serialized with `serialize_state` and make it
+    /// the current state. The shared storage is left untouched, so it can be
+    /// reconstructed from its own configuration. The entry actions into the
+    /// restored state are executed once the state machine is initialized.
+    ///
+    /// ```
+    /// # use statig::prelude::*;
+    /// # pub struct Blinky {
+    /// #     led: bool,
+    /// # }
+    /// #
+    /// # pub enum Event {
+    /// #     TimerElapsed,
+    /// # }
+    /// #
+    /// # #[state_machine(
+    /// #     initial = "State::on()",
+    /// #     state(derive(serde::Serialize, serde::Deserialize, PartialEq, Debug))
+    /// # )]
+    /// # impl Blinky {
+    /// #     #[state]
+    /// #     fn on(event: &Event) -> Response<State> { Transition(State::off()) }
+    /// #
+    /// #     #[state]
+    /// #     fn off(event: &Event) -> Response<State> { Transition(State::on()) }
+    /// # }
+    /// #
+    /// let mut state_machine = Blinky { led: false }.uninitialized_state_machine().init();
+    /// state_machine.handle(&Event::TimerElapsed);
+    ///
+    /// let mut serialized = Vec::new();
+    /// state_machine
+    ///     .serialize_state(&mut serde_json::Serializer::new(&mut serialized))
+    ///     .unwrap();
+    ///
+    /// let mut restored = Blinky { led: false }.uninitialized_state_machine();
+    /// restored
+    ///     .deserialize_state(&mut serde_json::Deserializer::from_slice(&serialized))
+    ///     .unwrap();
+    ///
+    /// let restored = restored.init();
+    /// assert_eq!(restored.state(), &State::off());
+    /// ```
+    pub fn deserialize_state<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        M::State: serde::Deserialize<'de>,
+    {
+        self.inner.deserialize_state(deserializer)
+    }
+}
+
+/// An initialized state machine that owns its context.
+///
+/// Created with [`with_context`](UninitializedStateMachine::with_context).
+pub struct ManagedContextStateMachine<M, C>
+where
+    M: IntoStateMachine,
+{
+    state_machine: InitializedStateMachine<M>,
+    context: C,
+}
+
+impl<M, C> ManagedContextStateMachine<M, C>
+where
+    for<'ctx> M: IntoStateMachine<Context<'ctx> = C>,
+    M::State: blocking::State<M>,
+    for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+{
+    /// Handle the given event with the owned context.
+    pub fn handle(&mut self, event: &M::Event<'_>) {
+        self.state_machine
+            .handle_with_context(event, &mut self.context);
+    }
+
+    /// Get an immutable reference to the current state of the state machine.
+    pub fn state(&self) -> &M::State {
+        self.state_machine.state()
+    }
+
+    /// Get an immutable reference to the context.
+    pub fn context(&self) -> &C {
+        &self.context
+    }
+
+    /// Get a mutable reference to the context.
+    pub fn context_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    /// Split the state machine from its context.
+    pub fn into_parts(self) -> (InitializedStateMachine<M>, C) {
+        (self.state_machine, self.context)
+    }
+}
+
+impl<M, C> Debug for ManagedContextStateMachine<M, C>
+where
+    M: IntoStateMachine + Debug,
+    M::State: Debug,
+    C: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ManagedContextStateMachine")
+            .field("state_machine", &self.state_machine)
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+impl<M, C> core::ops::Deref for ManagedContextStateMachine<M, C>
+where
+    M: IntoStateMachine,
+{
+    type Target = M;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state_machine
+    }
+}
@@ -1,4 +1,6 @@
+use core::any::Any;
 use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 
 use crate::IntoStateMachine;
 use crate::Response;
@@ -17,6 +19,32 @@ where
         context: &mut M::Context<'_>,
     ) -> Response<M::State>;
 
+    /// Call the handler for the current superstate, merging the effects it returns
+    /// into `effects` when they are collected. By default this calls
+    /// [`call_handler`](Superstate::call_handler).
+    #[allow(unused)]
+    fn call_handler_with_effects(
+        &mut self,
+        shared_storage: &mut M,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: Option<&mut dyn Any>,
+    ) -> Response<M::State> {
+        self.call_handler(shared_storage, event, context)
+    }
+
+    /// Call the handler for the current superstate with an event that the handler
+    /// is allowed to mutate. By default this calls
+    /// [`call_handler`](Superstate::call_handler).
+    fn call_handler_mut(
+        &mut self,
+        shared_storage: &mut M,
+        event: &mut M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> Response<M::State> {
+        self.call_handler(shared_storage, event, context)
+    }
+
     #[allow(unused)]
     /// Call the entry action for the current superstate.
     fn call_entry_action(&mut self, shared_storage: &mut M, context: &mut M::Context<'_>) {}
@@ -32,6 +60,48 @@ where
     {
         None
     }
+
+    /// Check whether `self` and `other` are the same superstate when looking for
+    /// the common ancestor of the source and target of a transition. By default
+    /// the discriminants of the superstate enum are compared.
+    fn is_same(&self, other: &M::Superstate<'_>) -> bool
+    where
+        Self: Sized,
+    {
+        DiscriminantBytes::of(self) == DiscriminantBytes::of(other)
+    }
+}
+
+/// The bytes that the discriminant of a value hashes to. Superstates that borrow
+/// from different states have different types, so their discriminants can't be
+/// compared directly, but the bytes they hash to can.
+#[derive(Default, PartialEq, Eq)]
+struct DiscriminantBytes {
+    bytes: [u8; 16],
+    len: usize,
+}
+
+impl DiscriminantBytes {
+    fn of<T>(value: &T) -> Self {
+        let mut bytes = Self::default();
+        core::mem::discriminant(value).hash(&mut bytes);
+        bytes
+    }
+}
+
+impl Hasher for DiscriminantBytes {
+    fn finish(&self) -> u64 {
+        0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if let Some(slot) = self.bytes.get_mut(self.len) {
+                *slot = *byte;
+            }
+            self.len += 1;
+        }
+    }
 }
 
 /// Extensions for `Superstate` trait.
@@ -43,16 +113,7 @@ where
     for<'b> M::Superstate<'b>: Superstate<M>,
 {
     fn same_state(lhs: &M::Superstate<'_>, rhs: &M::Superstate<'_>) -> bool {
-        use core::mem::{discriminant, transmute, Discriminant};
-
-        // Generic associated types are invariant over any lifetime arguments, so the
-        // compiler won't allow us to compare them directly. Instead we need to coerce them
-        // to have the same lifetime by transmuting them to the same type.
-
-        let lhs: Discriminant<M::Superstate<'_>> = unsafe { transmute(discriminant(lhs)) };
-        let rhs: Discriminant<M::Superstate<'_>> = unsafe { transmute(discriminant(rhs)) };
-
-        lhs == rhs
+        lhs.is_same(rhs)
     }
 
     /// Get the depth of the current superstate.
@@ -93,11 +154,45 @@ where
         shared_storage: &mut M,
         event: &M::Event<'_>,
         context: &mut M::Context<'_>,
+        mut effects: Option<&mut dyn Any>,
+    ) -> Response<M::State>
+    where
+        Self: Sized,
+    {
+        let response =
+            self.call_handler_with_effects(shared_storage, event, context, effects.as_deref_mut());
+
+        match response {
+            Response::Handled => Response::Handled,
+            Response::Super => match self.superstate() {
+                Some(mut superstate) => {
+                    M::ON_DISPATCH(
+                        shared_storage,
+                        StateOrSuperstate::Superstate(&superstate),
+                        event,
+                    );
+
+                    superstate.handle(shared_storage, event, context, effects)
+                }
+                None => Response::Super,
+            },
+            Response::Transition(state) => Response::Transition(state),
+            Response::TransitionToSuperstate => Response::TransitionToSuperstate,
+        }
+    }
+
+    /// Same as [`handle`](SuperstateExt::handle), but the handlers are allowed to
+    /// mutate the event.
+    fn handle_mut(
+        &mut self,
+        shared_storage: &mut M,
+        event: &mut M::Event<'_>,
+        context: &mut M::Context<'_>,
     ) -> Response<M::State>
     where
         Self: Sized,
     {
-        let response = self.call_handler(shared_storage, event, context);
+        let response = self.call_handler_mut(shared_storage, event, context);
 
         match response {
             Response::Handled => Response::Handled,
@@ -109,11 +204,12 @@ where
                         event,
                     );
 
-                    superstate.handle(shared_storage, event, context)
+                    superstate.handle_mut(shared_storage, event, context)
                 }
                 None => Response::Super,
             },
             Response::Transition(state) => Response::Transition(state),
+            Response::TransitionToSuperstate => Response::TransitionToSuperstate,
         }
     }
 
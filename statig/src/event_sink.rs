@@ -0,0 +1,157 @@
+use alloc::collections::VecDeque;
+use core::fmt::Debug;
+
+/// A queue that handlers can post follow-up events to.
+///
+/// Events posted to the sink are not handled immediately. Instead they are
+/// handled one by one after the event that is currently being handled has been
+/// fully processed, so every event runs to completion. To make the sink
+/// available to handlers it must be part of the context, which is done by
+/// implementing `AsMut<EventSink<Event>>` for the context type (the sink itself
+/// can also be used as the context). Events are then handled with
+/// [`handle_with_sink`](crate::blocking::InitializedStateMachine::handle_with_sink).
+///
+/// Handlers that keep posting events could keep `handle_with_sink` busy forever,
+/// so it gives up after [`limit`](EventSink::limit) posted events have been
+/// handled and leaves the rest in the sink.
+///
+/// ```
+/// # use statig::prelude::*;
+/// #[derive(Default)]
+/// struct Connection;
+///
+/// enum Event {
+///     Connect,
+///     Handshake,
+/// }
+///
+/// #[state_machine(initial = "State::disconnected()", state(derive(Debug, PartialEq)))]
+/// impl Connection {
+///     #[state]
+///     fn disconnected(context: &mut EventSink<Event>, event: &Event) -> Response<State> {
+///         match event {
+///             Event::Connect => {
+///                 context.post(Event::Handshake);
+///                 Transition(State::connected())
+///             }
+///             _ => Handled,
+///         }
+///     }
+///
+///     #[state]
+///     fn connected(event: &Event) -> Response<State> {
+///         match event {
+///             Event::Handshake => Transition(State::ready()),
+///             _ => Handled,
+///         }
+///     }
+///
+///     #[state]
+///     fn ready() -> Response<State> {
+///         Handled
+///     }
+/// }
+///
+/// let mut sink = EventSink::new();
+/// let mut state_machine = Connection
+///     .uninitialized_state_machine()
+///     .init_with_context(&mut sink);
+///
+/// state_machine.handle_with_sink(&Event::Connect, &mut sink).unwrap();
+///
+/// assert_eq!(state_machine.state(), &State::ready());
+/// ```
+pub struct EventSink<E> {
+    events: VecDeque<E>,
+    limit: usize,
+}
+
+impl<E> EventSink<E> {
+    /// The number of posted events that are handled by a single call to
+    /// `handle_with_sink` for sinks created with [`new`](EventSink::new).
+    pub const DEFAULT_LIMIT: usize = 1024;
+
+    /// Create an empty event sink.
+    pub fn new() -> Self {
+        Self::with_limit(Self::DEFAULT_LIMIT)
+    }
+
+    /// Create an empty event sink of which at most `limit` posted events are
+    /// handled by a single call to `handle_with_sink`.
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// Get the number of posted events that are handled by a single call to
+    /// `handle_with_sink`.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Post an event that will be handled after the current event has been
+    /// processed.
+    pub fn post(&mut self, event: E) {
+        self.events.push_back(event);
+    }
+
+    /// Take the next posted event out of the sink.
+    pub fn pop(&mut self) -> Option<E> {
+        self.events.pop_front()
+    }
+
+    /// Get the number of events that are waiting to be handled.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Check whether there are no events waiting to be handled.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl<E> Default for EventSink<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E> AsMut<EventSink<E>> for EventSink<E> {
+    fn as_mut(&mut self) -> &mut EventSink<E> {
+        self
+    }
+}
+
+impl<E> Debug for EventSink<E>
+where
+    E: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EventSink")
+            .field("events", &self.events)
+            .field("limit", &self.limit)
+            .finish()
+    }
+}
+
+/// Error returned by `handle_with_sink` when the [`limit`](EventSink::limit) of the
+/// sink was reached before it was empty, which usually means that handlers keep
+/// posting events to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinkLimitReached {
+    /// The number of events that were left in the sink.
+    pub remaining: usize,
+}
+
+impl core::fmt::Display for SinkLimitReached {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "the event sink limit was reached with {} events left",
+            self.remaining
+        )
+    }
+}
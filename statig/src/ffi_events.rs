@@ -0,0 +1,71 @@
+/// Trait for reconstructing events that are posted by foreign code as an
+/// integer tag and a pointer to their payload.
+///
+/// This is implemented by the `state_machine` macro when the
+/// `ffi_events(...)` attribute is set and is meant for `#[repr(C)]` or
+/// `#[repr(u32)]` events that cross an FFI boundary. The events are then
+/// handled with
+/// [`handle_raw`](crate::blocking::InitializedStateMachine::handle_raw).
+///
+/// ```
+/// # use statig::prelude::*;
+/// #[derive(Default)]
+/// struct Heater {
+///     target: u16,
+/// }
+///
+/// #[repr(C)]
+/// enum Event {
+///     PowerOff,
+///     SetTarget(u16),
+/// }
+///
+/// #[state_machine(
+///     initial = "State::on()",
+///     state(derive(Debug, PartialEq)),
+///     ffi_events(
+///         "Event::PowerOff",
+///         "Event::SetTarget(unsafe { payload.cast::<u16>().read_unaligned() })"
+///     )
+/// )]
+/// impl Heater {
+///     #[state]
+///     fn on(&mut self, event: &Event) -> Response<State> {
+///         match event {
+///             Event::PowerOff => Transition(State::off()),
+///             Event::SetTarget(target) => {
+///                 self.target = *target;
+///                 Handled
+///             }
+///         }
+///     }
+///
+///     #[state]
+///     fn off() -> Response<State> {
+///         Handled
+///     }
+/// }
+///
+/// let mut state_machine = Heater::default().uninitialized_state_machine().init();
+/// let target: u16 = 21;
+///
+/// unsafe {
+///     assert!(state_machine.handle_raw(1, (&target as *const u16).cast()));
+///     assert!(!state_machine.handle_raw(2, core::ptr::null()));
+///     assert!(state_machine.handle_raw(0, core::ptr::null()));
+/// }
+///
+/// assert_eq!(state_machine.target, 21);
+/// assert_eq!(state_machine.state(), &State::off());
+/// ```
+pub trait FfiEvents<E> {
+    /// Reconstruct the event with the given tag, or return `None` if the tag is
+    /// unknown.
+    ///
+    /// # Safety
+    ///
+    /// `payload` must point to a valid value of the payload type that belongs to
+    /// the event with the given tag. Tags of events without a payload don't read
+    /// from `payload`, so it may be null for those.
+    unsafe fn try_from_tag(tag: u32, payload: *const u8) -> Option<E>;
+}
@@ -1,3 +1,11 @@
+#[cfg(feature = "async")]
+use alloc::boxed::Box;
+use core::any::Any;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::task::Poll;
+
 #[cfg(feature = "async")]
 use crate::awaitable::{self, StateExt as _};
 use crate::blocking::{self, StateExt as _};
@@ -10,6 +18,62 @@ where
 {
     pub shared_storage: M,
     pub state: M::State,
+    /// Observer that is called after every transition, next to `ON_TRANSITION`.
+    #[cfg(feature = "std")]
+    pub observer: Option<TransitionObserver<M>>,
+    /// The number of times the states that take their entry count have been entered.
+    pub entry_counts: [u32; crate::__private::MAX_ENTRY_COUNTS],
+    /// The number of times the current state had been entered when it was entered.
+    pub entry_count: u32,
+}
+
+/// A callback that is registered at runtime to observe transitions, see
+/// [`set_transition_observer`](crate::blocking::InitializedStateMachine::set_transition_observer).
+/// It is called with the source and the target state.
+///
+/// The observer must be `Send` and `Sync` because it is stored in the state machine,
+/// which would otherwise no longer be `Send` and `Sync` for blocking and async state
+/// machines alike.
+#[cfg(feature = "std")]
+pub type TransitionObserver<M> = alloc::boxed::Box<
+    dyn FnMut(&<M as IntoStateMachine>::State, &<M as IntoStateMachine>::State) + Send + Sync,
+>;
+
+impl<M> Inner<M>
+where
+    M: IntoStateMachine,
+{
+    pub fn new(shared_storage: M, state: M::State) -> Self {
+        Self {
+            shared_storage,
+            state,
+            #[cfg(feature = "std")]
+            observer: None,
+            entry_counts: [0; crate::__private::MAX_ENTRY_COUNTS],
+            entry_count: 0,
+        }
+    }
+
+    /// Count the entry of the new current state, which has the given counter if it
+    /// takes its entry count.
+    fn count_entry(&mut self, counter: Option<usize>) {
+        self.entry_count = match counter {
+            Some(index) => {
+                self.entry_counts[index] = self.entry_counts[index].saturating_add(1);
+                self.entry_counts[index]
+            }
+            None => 0,
+        };
+    }
+
+    /// Call the transition callbacks with the source and target of a transition.
+    fn on_transition(&mut self, source: &M::State) {
+        M::ON_TRANSITION(&mut self.shared_storage, source, &self.state);
+        #[cfg(feature = "std")]
+        if let Some(observer) = &mut self.observer {
+            observer(source, &self.state);
+        }
+    }
 }
 
 impl<M> Inner<M>
@@ -23,20 +87,88 @@ where
         let enter_levels = self.state.depth();
         self.state
             .enter(&mut self.shared_storage, context, enter_levels);
+        self.count_entry(blocking::State::entry_counter(&self.state));
+    }
+
+    /// Handle the given event. Returns `false` if the event reached the top of the
+    /// hierarchy without being handled.
+    pub fn handle_with_context(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) -> bool {
+        self.handle_with_effects(event, context, None)
+    }
+
+    /// Handle the given event and merge the effects that the handlers return into
+    /// `effects`, when they are collected. Returns `false` if the event reached the
+    /// top of the hierarchy without being handled.
+    pub fn handle_with_effects(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: Option<&mut dyn Any>,
+    ) -> bool {
+        let response = match self.state.handle(
+            &mut self.shared_storage,
+            event,
+            context,
+            effects,
+            self.entry_count,
+        ) {
+            Response::Super => blocking::State::call_catch_all(
+                &mut self.state,
+                &mut self.shared_storage,
+                event,
+                context,
+            ),
+            response => response,
+        };
+        let handled = !matches!(response, Response::Super);
+        self.handle_response(response, context);
+        handled
+    }
+
+    /// Handle the given event that handlers are allowed to mutate.
+    pub fn handle_mut_with_context(
+        &mut self,
+        event: &mut M::Event<'_>,
+        context: &mut M::Context<'_>,
+    ) {
+        let response = match self
+            .state
+            .handle_mut(&mut self.shared_storage, event, context)
+        {
+            Response::Super => blocking::State::call_catch_all(
+                &mut self.state,
+                &mut self.shared_storage,
+                event,
+                context,
+            ),
+            response => response,
+        };
+        self.handle_response(response, context);
     }
 
-    /// Handle the given event.
-    pub fn handle_with_context(&mut self, event: &M::Event<'_>, context: &mut M::Context<'_>) {
-        let response = self.state.handle(&mut self.shared_storage, event, context);
+    /// Act on the response of the state handlers.
+    fn handle_response(&mut self, response: Response<M::State>, context: &mut M::Context<'_>) {
         match response {
             Response::Super => {}
             Response::Handled => {}
             Response::Transition(state) => self.transition(state, context),
+            // Without an initial state to go to the event is considered handled.
+            Response::TransitionToSuperstate => {
+                if let Some(state) = blocking::State::superstate_initial_state(&self.state) {
+                    self.transition(state, context);
+                }
+            }
         }
     }
 
     /// Transition from the current state to the given target state.
     pub fn transition(&mut self, mut target: M::State, context: &mut M::Context<'_>) {
+        M::ON_SNAPSHOT(&self.shared_storage, &self.state);
+
         // Get the transition path we need to perform from one state to the next.
         let (exit_levels, enter_levels) = self.state.transition_path(&mut target);
 
@@ -48,10 +180,20 @@ where
         core::mem::swap(&mut self.state, &mut target);
 
         // Perform the entry actions from the common ancestor state into the new state.
-        self.state
-            .enter(&mut self.shared_storage, context, enter_levels);
-
-        M::ON_TRANSITION(&mut self.shared_storage, &target, &self.state);
+        let entered = self
+            .state
+            .try_enter(&mut self.shared_storage, context, enter_levels);
+
+        if entered {
+            self.count_entry(blocking::State::entry_counter(&self.state));
+            self.on_transition(&target);
+        } else {
+            // The new state vetoed its entry, so we roll back to the previous state by
+            // re-entering the states that were exited on the way to the common ancestor.
+            core::mem::swap(&mut self.state, &mut target);
+            self.state
+                .enter(&mut self.shared_storage, context, exit_levels);
+        }
     }
 }
 
@@ -69,26 +211,110 @@ where
         self.state
             .enter(&mut self.shared_storage, context, enter_levels)
             .await;
+        self.count_entry(awaitable::State::entry_counter(&self.state));
     }
 
     pub async fn async_handle_with_context(
         &mut self,
         event: &M::Event<'_>,
         context: &mut M::Context<'_>,
-    ) {
-        let response = self
+    ) -> bool {
+        self.async_handle_with_effects(event, context, None).await
+    }
+
+    pub async fn async_handle_with_effects(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: Option<&mut (dyn Any + Send)>,
+    ) -> bool {
+        let response = self.async_dispatch(event, context, effects).await;
+        let handled = !matches!(response, Response::Super);
+        self.async_handle_response(response, context).await;
+        handled
+    }
+
+    /// Handle the given event, but give up if `timeout` completes before the handlers
+    /// have returned a response. Once a response is returned, the transition it causes
+    /// is always completed.
+    pub async fn async_handle_with_timeout<T>(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        timeout: T,
+    ) -> Result<(), awaitable::Timeout>
+    where
+        T: Future<Output = ()>,
+    {
+        let response = {
+            let mut dispatch = Box::pin(self.async_dispatch(event, context, None));
+            let mut timeout = Box::pin(timeout);
+            core::future::poll_fn(|cx| {
+                if let Poll::Ready(response) = dispatch.as_mut().poll(cx) {
+                    return Poll::Ready(Ok(response));
+                }
+                timeout.as_mut().poll(cx).map(|_| Err(awaitable::Timeout))
+            })
+            .await?
+        };
+        self.async_handle_response(response, context).await;
+        Ok(())
+    }
+
+    /// Let the state handlers respond to the given event.
+    async fn async_dispatch(
+        &mut self,
+        event: &M::Event<'_>,
+        context: &mut M::Context<'_>,
+        effects: Option<&mut (dyn Any + Send)>,
+    ) -> Response<M::State> {
+        match self
             .state
-            .handle(&mut self.shared_storage, event, context)
-            .await;
+            .handle(
+                &mut self.shared_storage,
+                event,
+                context,
+                effects,
+                self.entry_count,
+            )
+            .await
+        {
+            Response::Super => {
+                awaitable::State::call_catch_all(
+                    &mut self.state,
+                    &mut self.shared_storage,
+                    event,
+                    context,
+                )
+                .await
+            }
+            response => response,
+        }
+    }
+
+    /// Act on the response of the state handlers.
+    async fn async_handle_response(
+        &mut self,
+        response: Response<M::State>,
+        context: &mut M::Context<'_>,
+    ) {
         match response {
             Response::Super => {}
             Response::Handled => {}
             Response::Transition(state) => self.async_transition(state, context).await,
+            // Without an initial state to go to the event is considered handled.
+            Response::TransitionToSuperstate => {
+                if let Some(state) = awaitable::State::superstate_initial_state(&self.state) {
+                    self.async_transition(state, context).await;
+                }
+            }
         }
     }
 
     /// Transition from the current state to the given target state.
     pub async fn async_transition(&mut self, mut target: M::State, context: &mut M::Context<'_>) {
+        M::ON_SNAPSHOT(&self.shared_storage, &self.state);
+
         // Get the transition path we need to perform from one state to the next.
         let (exit_levels, enter_levels) = self.state.transition_path(&mut target);
 
@@ -101,11 +327,22 @@ where
         core::mem::swap(&mut self.state, &mut target);
 
         // Perform the entry actions from the common ancestor state into the new state.
-        self.state
-            .enter(&mut self.shared_storage, context, enter_levels)
+        let entered = self
+            .state
+            .try_enter(&mut self.shared_storage, context, enter_levels)
             .await;
 
-        M::ON_TRANSITION(&mut self.shared_storage, &target, &self.state);
+        if entered {
+            self.count_entry(awaitable::State::entry_counter(&self.state));
+            self.on_transition(&target);
+        } else {
+            // The new state vetoed its entry, so we roll back to the previous state by
+            // re-entering the states that were exited on the way to the common ancestor.
+            core::mem::swap(&mut self.state, &mut target);
+            self.state
+                .enter(&mut self.shared_storage, context, exit_levels)
+                .await;
+        }
     }
 }
 
@@ -115,10 +352,11 @@ where
     M::State: Clone,
 {
     fn clone(&self) -> Self {
-        Self {
-            shared_storage: self.shared_storage.clone(),
-            state: self.state.clone(),
-        }
+        // The transition observer is not cloned.
+        let mut inner = Self::new(self.shared_storage.clone(), self.state.clone());
+        inner.entry_counts = self.entry_counts;
+        inner.entry_count = self.entry_count;
+        inner
     }
 }
 
@@ -139,6 +377,31 @@ where
 {
 }
 
+#[cfg(feature = "serde")]
+impl<M> Inner<M>
+where
+    M: IntoStateMachine,
+{
+    /// Serialize only the state, leaving out the shared storage.
+    pub fn serialize_state<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+        M::State: serde::Serialize,
+    {
+        serde::Serialize::serialize(&self.state, serializer)
+    }
+
+    /// Deserialize a state and replace the current state with it.
+    pub fn deserialize_state<'de, D>(&mut self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: serde::Deserializer<'de>,
+        M::State: serde::Deserialize<'de>,
+    {
+        self.state = serde::Deserialize::deserialize(deserializer)?;
+        Ok(())
+    }
+}
+
 #[cfg(feature = "serde")]
 impl<M> serde::Serialize for Inner<M>
 where
@@ -191,6 +454,20 @@ where
                         formatter.write_str("`shared_storage` or `state`")
                     }
 
+                    fn visit_u64<E>(self, value: u64) -> Result<Field, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            0 => Ok(Field::SharedStorage),
+                            1 => Ok(Field::State),
+                            _ => Err(serde::de::Error::invalid_value(
+                                serde::de::Unexpected::Unsigned(value),
+                                &"field index 0 <= i < 2",
+                            )),
+                        }
+                    }
+
                     fn visit_str<E>(self, value: &str) -> Result<Field, E>
                     where
                         E: serde::de::Error,
@@ -201,6 +478,23 @@ where
                             _ => Err(serde::de::Error::unknown_field(value, FIELDS)),
                         }
                     }
+
+                    fn visit_bytes<E>(self, value: &[u8]) -> Result<Field, E>
+                    where
+                        E: serde::de::Error,
+                    {
+                        match value {
+                            b"shared_storage" => Ok(Field::SharedStorage),
+                            b"state" => Ok(Field::State),
+                            _ => match core::str::from_utf8(value) {
+                                Ok(value) => Err(serde::de::Error::unknown_field(value, FIELDS)),
+                                Err(_) => Err(serde::de::Error::invalid_value(
+                                    serde::de::Unexpected::Bytes(value),
+                                    &self,
+                                )),
+                            },
+                        }
+                    }
                 }
 
                 deserializer.deserialize_identifier(FieldVisitor)
@@ -230,10 +524,7 @@ where
                 let state = seq
                     .next_element()?
                     .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
-                let inner = Inner {
-                    shared_storage,
-                    state,
-                };
+                let inner = Inner::new(shared_storage, state);
                 Ok(inner)
             }
 
@@ -262,10 +553,7 @@ where
                 let shared_storage = shared_storage
                     .ok_or_else(|| serde::de::Error::missing_field("shared_storage"))?;
                 let state = state.ok_or_else(|| serde::de::Error::missing_field("state"))?;
-                let inner = Inner {
-                    shared_storage,
-                    state,
-                };
+                let inner = Inner::new(shared_storage, state);
                 Ok(inner)
             }
         }
@@ -29,4 +29,38 @@ where
 
     /// Method that is called *after* every transition.
     const ON_TRANSITION: fn(&mut Self, &Self::State, &Self::State) = |_, _, _| {};
+
+    /// Method that is called *before* every transition with the shared storage and
+    /// the state, so they can be cloned to take a snapshot (e.g. for undo). It is also
+    /// called when the target state vetoes its entry, in which case the state machine
+    /// stays in the state of the snapshot.
+    const ON_SNAPSHOT: fn(&Self, &Self::State) = |_, _| {};
+}
+
+/// Trait for state machines whose handlers return effects next to their response.
+///
+/// Implemented by the `#[state_machine]` macro when a handler returns effects, which
+/// enables `handle_with_effects` on the state machine.
+///
+/// ```compile_fail
+/// # use statig::prelude::*;
+/// # #[derive(Default)]
+/// # struct Door;
+/// #[state_machine(initial = "State::closed()")]
+/// impl Door {
+///     #[state]
+///     fn closed(event: &bool) -> Response<State> {
+///         Handled
+///     }
+/// }
+///
+/// let mut state_machine = Door.uninitialized_state_machine().init();
+///
+/// // Does not compile, `Door` does not implement `HasEffects`.
+/// state_machine.handle_with_effects(&false, &mut ());
+/// ```
+pub trait HasEffects: IntoStateMachine {
+    /// Effects that handlers return next to their response, which are collected
+    /// with `|=` when events are handled with `handle_with_effects`.
+    type Effects: 'static;
 }
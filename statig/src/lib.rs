@@ -9,7 +9,7 @@
 //! - Compatible with `#![no_std]`, state machines are defined in ROM and no heap memory allocations.
 //! - (Optional) macro's for reducing boilerplate.
 //! - Support for generics.
-//! - Support for async handlers and actions (only on `std`).
+//! - Support for async handlers and actions (requires `alloc`).
 //!
 //! ## Statig in action
 //!
@@ -331,7 +331,24 @@
 //! ```
 //!
 //! `counter` is only available in the `led_on` state but can also be accessed in
-//! its superstates and actions.
+//! its superstates and actions. Local storage can't be named `shared_storage` or
+//! after the event and context inputs (`event` and `context` by default), as
+//! these names are used by the generated code.
+//!
+//! A state handler can also take a reference to the current state by adding a
+//! `state: &State` input, for example to log it. Because the state is then
+//! borrowed immutably, the local storage of that state must also be borrowed
+//! immutably (`counter: &u32`).
+//!
+//! A state handler can also take an `entry_count: u32` input to get the number of
+//! times its state has been entered, for example to give up after a few retries.
+//! Every state keeps its own count, which starts at 1 on the first entry and is kept
+//! when the state is exited, so it keeps counting when the state is entered again
+//! later. The counts are kept by the state machine, not in the state, until they are
+//! cleared with
+//! [`reset_entry_counts`](crate::blocking::InitializedStateMachine::reset_entry_counts),
+//! so they are not serialized and `State::connecting()` equals a state that has been
+//! entered a few times. Up to 8 states can take their entry count.
 //!
 //! ### Context
 //!
@@ -375,6 +392,63 @@
 //! state_machine.handle_with_context(&Event::TimerElapsed, &mut context);
 //! ```
 //!
+//! ### Effects
+//!
+//! Instead of performing side effects themselves, handlers can return them next to
+//! their response as `(Effects, Response<State>)`. The effects are collected with
+//! `|=` into the [`Effects`](crate::HasEffects::Effects) type of the state
+//! machine, which is passed to [`handle_with_effects`](crate::blocking::InitializedStateMachine::handle_with_effects)
+//! (or `handle_with_context_and_effects` next to a context). The caller can then
+//! perform them, or assert on them in tests. Effects collected with plain `handle`
+//! are dropped.
+//!
+//! The effects type defaults to the type returned by the handlers. Set it with
+//! `#[state_machine(effects = "...")]` when handlers return something else that
+//! is collected into it with `|=`. The macro then implements [`HasEffects`] for the
+//! state machine, which requires the effects type to be `'static`.
+//!
+//! ```rust
+//! # use statig::prelude::*;
+//! #[derive(Clone, Copy, Debug, Default, PartialEq)]
+//! pub struct Effects(u8);
+//!
+//! impl Effects {
+//!     const NONE: Effects = Effects(0);
+//!     const TOGGLE_LED: Effects = Effects(1);
+//! }
+//!
+//! impl core::ops::BitOrAssign for Effects {
+//!     fn bitor_assign(&mut self, rhs: Self) {
+//!         self.0 |= rhs.0;
+//!     }
+//! }
+//! #
+//! # #[derive(Default)]
+//! # pub struct Blinky;
+//! #
+//! # pub enum Event {
+//! #     TimerElapsed,
+//! #     ButtonPressed
+//! # }
+//!
+//! #[state_machine(initial = "State::blinking()")]
+//! impl Blinky {
+//!     #[state]
+//!     fn blinking(event: &Event) -> (Effects, Response<State>) {
+//!         match event {
+//!             Event::TimerElapsed => (Effects::TOGGLE_LED, Handled),
+//!             Event::ButtonPressed => (Effects::NONE, Handled),
+//!         }
+//!     }
+//! }
+//!
+//! let mut state_machine = Blinky.uninitialized_state_machine().init();
+//!
+//! let mut effects = Effects::default();
+//! state_machine.handle_with_effects(&Event::TimerElapsed, &mut effects);
+//! assert_eq!(effects, Effects::TOGGLE_LED);
+//! ```
+//!
 //! ### Introspection
 //!
 //! For logging purposes you can define two callbacks that will be called at specific
@@ -383,6 +457,12 @@
 //! - `on_dispatch` is called before an event is dispatched to a specific state or superstate.
 //! - `on_transition` is called after a transition has occurred.
 //!
+//! A third callback, `on_snapshot`, is called before every transition with the
+//! shared storage and the current state. Cloning them there is an easy way to
+//! keep an undo history. Whether the target state vetoes its entry is only known
+//! once the transition is underway, so `on_snapshot` is also called for vetoed
+//! transitions. The state machine then stays in the state of the snapshot.
+//!
 //! ```
 //! # use statig::prelude::*;
 //! # use statig::blocking::StateOrSuperstate;
@@ -419,7 +499,11 @@
 //!
 //! All handlers and actions can be made async. The `#[state_machine]` macro will
 //! automatically detect that async functions are being used and generate an async state
-//! machine. (This is only available on `std` for now and requires the `async` feature to be enabled).
+//! machine. (This requires a global allocator and the `async` feature to be enabled).
+//!
+//! Because a single async function is easily added by mistake, the macro warns when
+//! it is the only one that makes the state machine async. Add
+//! `#[state_machine(allow_single_async)]` when that is intended.
 //!
 //! ```rust
 //! # use statig::prelude::*;
@@ -445,7 +529,7 @@
 //!         }
 //!     }
 //! #    #[state]
-//! #    fn led_off() -> Response<State> {
+//! #    async fn led_off() -> Response<State> {
 //! #        Handled
 //! #    }
 //! }
@@ -674,10 +758,22 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(incomplete_features)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+mod event_sink;
+mod ffi_events;
 mod inner;
 mod into_state_machine;
+mod lifecycle;
+mod macros;
 mod response;
 mod state_or_superstate;
+mod unhandled;
+
+#[cfg(feature = "testing")]
+pub mod testing;
 
 /// Macro for deriving the state and superstate enum.
 ///
@@ -687,9 +783,39 @@ mod state_or_superstate;
 /// names '`State`' and '`Superstate`'. Next to that the macro will also
 /// implement the [`State`](crate::blocking::State) trait for the state enum and the
 /// [`Superstate`](crate::blocking::Superstate) trait for the superstate enum.
+/// The state enum also gets a constructor for every state and a
+/// `const fn superstate_name(&self) -> Option<&'static str>` that returns the
+/// name of the superstate handler of the state. The superstate enum gets a
+/// `const fn children(&self) -> &'static [&'static str]` that returns the names
+/// of the states and superstates directly below it, in declaration order. For
+/// every superstate with child states whose constructor takes no arguments, the
+/// state enum gets an associated const such as `BLINKING_CHILDREN: [fn() -> State; N]`
+/// holding those constructors, so a superstate handler can pick a child by index.
+/// When none of the superstates borrow fields from their states, the superstate
+/// enum also gets `COUNT: usize` and `VARIANTS: [Superstate; COUNT]` associated
+/// consts, mirroring `strum`'s `EnumCount` and `VariantArray`, so every superstate
+/// can be iterated over.
+///
+/// When no handler mutates the shared storage or its local storage and none of
+/// them take the context, the macro implements
+/// [`PureStateMachine`](crate::blocking::PureStateMachine) so events can be
+/// classified through a shared reference with
+/// [`classify`](crate::blocking::InitializedStateMachine::classify).
 ///
 /// To override the default configuration you can use the following attributes.
 ///
+/// - `#[state_machine(initial_superstate = "superstate_name")]`
+///
+///   Instead of `initial`, start in the initial state of the given superstate,
+///   which is declared with `#[superstate(initial = "State::initial_state()")]`.
+///   Naming a superstate that has an initial state in `initial` (e.g.
+///   `initial = "State::blinking()"`) does the same. Any other `State::name()`
+///   in `initial` must be the constructor of a state. Paths relative to `Self`
+///   (e.g. `initial = "Self::START"`) refer to the type the state machine is
+///   implemented on, so associated consts and functions can be used as well.
+///
+///   <br/>
+///
 /// - `#[state_machine(state(name = "CustomStateName"))]`
 ///
 ///   Set the name of the state enum to a custom name.
@@ -721,6 +847,155 @@ mod state_or_superstate;
 ///   _Default_: `()`
 ///
 ///   <br/>
+///
+/// - `#[state_machine(derive(SomeTrait, AnotherTrait))]`
+///
+///   Apply the derive macro with the passed traits to both the state and the
+///   superstate enum. Derives that are specific to one of the enums can still
+///   be added with `state(derive(..))` or `superstate(derive(..))`.
+///
+///   _Default_: `()`
+///
+///   <br/>
+///
+/// - `#[state_machine(state(jump_table))]`
+///
+///   Dispatch events to the state handlers through a table of function pointers
+///   that is indexed by the current state, instead of matching on the state enum
+///   with every handler call inlined. For state machines with many states this
+///   can reduce code size at the cost of an indirect call. Only supported on
+///   blocking state machines without generics.
+///
+///   _Default_: disabled
+///
+///   <br/>
+///
+/// - `#[state_machine(state(repr = "u8"))]`
+///
+///   Apply `#[repr(u8)]` (or any other primitive integer type) to the state enum
+///   and give the variants explicit discriminants, starting from zero in the
+///   order the state handlers are declared. A `const fn discriminant(&self)`
+///   is generated to get the discriminant, for example to expose the current
+///   state over FFI. Only the layout of state machines without state-local
+///   storage is compatible with a plain integer.
+///
+///   _Default_: `()`
+///
+///   <br/>
+///
+/// - `#[state_machine(state(constructor_trait = "BlinkyStates"))]`
+///
+///   Generate a trait with the given name that has a method for every state
+///   constructor and implement it for the state enum. Generic code can then be
+///   written against the trait instead of a concrete state enum. Not supported
+///   when the state enum is generic.
+///
+///   <br/>
+///
+/// - `#[state_machine(catch_all = "state_name")]`
+///
+///   Use the handler of the given state as a catch-all. Events that are deferred
+///   by the current state and all of its superstates are passed to this handler,
+///   unless the catch-all state is itself the current state. The catch-all state
+///   can not have state-local storage.
+///
+///   <br/>
+///
+/// - `#[state_machine(effects = "Effects")]`
+///
+///   Set the type the effects returned by handlers are collected into with `|=`.
+///   Handlers return them as `(Effect, Response<State>)`, see
+///   [`handle_with_effects`](crate::blocking::InitializedStateMachine::handle_with_effects).
+///
+///   _Default_: the effects type returned by the handlers. [`HasEffects`] is only
+///   implemented when the state machine has effects.
+///
+///   <br/>
+///
+/// - `#[state_machine(require_handled)]`
+///
+///   Require the caller to deal with events that reach the top of the hierarchy
+///   without being handled. The state machine implements [`RequireHandled`], so it
+///   can be driven with
+///   [`try_handle`](crate::blocking::InitializedStateMachine::try_handle), which
+///   returns the event in an [`Unhandled`] error. `handle` is still available and
+///   ignores unhandled events.
+///
+///   <br/>
+///
+/// - `#[state_machine(check_event_coverage)]`
+///
+///   Fail to compile when a variant of the event enum is not matched by any
+///   state or superstate handler, which catches events that were added but
+///   forgotten. Variants count as matched when a pattern names them with the
+///   name of the enum (e.g. `Event::Stop`, also inside `matches!`) or when they
+///   are the event of a transition. Catch-all patterns such as `_` are not
+///   counted. Not supported for events with type parameters.
+///
+///   _Default_: disabled
+///
+///   <br/>
+///
+/// - `#[state_machine(lifecycle_events)]`
+///
+///   Pass the entry and exit of a state to its handler instead of to separate
+///   actions. Handlers then take the event as `&Lifecycle<Event>` and receive
+///   [`Lifecycle::Enter`], [`Lifecycle::Exit`] or [`Lifecycle::Event`]. This keeps
+///   everything a state does in a single function, at the cost of every handler
+///   matching on the lifecycle. The response to `Enter` and `Exit` is ignored, so
+///   those can't trigger a transition, and handlers that take the event can't
+///   also have entry or exit actions. Not supported when the event is taken as
+///   `&mut`.
+///
+///   _Default_: disabled
+///
+///   <br/>
+///
+/// - `#[state_machine(allow_single_async)]`
+///
+///   Don't warn when a single async handler or action makes the whole state
+///   machine async, because it is meant to be async.
+///
+///   _Default_: disabled
+///
+///   <br/>
+///
+/// - `#[state_machine(const_step)]`
+///
+///   Add a `const fn step(self, event) -> Self` to the state enum that returns the
+///   state that is reached by handling the event, so transitions can be evaluated
+///   at compile time. Every state and superstate handler must be a `const fn` that
+///   only takes the event, and there can be no entry, exit or transition actions.
+///   The state-local storage must not need to be dropped, as `step` consumes the
+///   state in a const context.
+///
+///   _Default_: disabled
+///
+///   <br/>
+///
+/// - `#[state_machine(ffi_events("Event::Stop", "Event::Speed(unsafe { *payload })"))]`
+///
+///   Implement [`FfiEvents`] so events posted by foreign code as a `u32` tag and
+///   a `payload: *const u8` can be handled with
+///   [`handle_raw`](crate::blocking::InitializedStateMachine::handle_raw). Each
+///   expression creates the event for the tag equal to its position in the list
+///   and can read the payload through `payload`. Meant for `#[repr(C)]` or
+///   `#[repr(u32)]` events.
+///
+///   <br/>
+///
+/// - `#[transitions(event = Event, Idle: Event::Start => Running, Running: Event::Stop => Idle)]`
+///
+///   Declare the transitions of simple states as a table on the impl block, below
+///   `#[state_machine]`. Each entry takes the transition to the target state when
+///   the event is equal (`==`) to the given expression, so the event must implement
+///   `PartialEq`. The source state (`Idle:`) carries over to the following entries.
+///   States are written as their variant and must be constructed without
+///   arguments. Table transitions are checked before the state handler, states
+///   without a handler get one that defers every other event to their superstate.
+///   `event = Event` is only needed when no handler takes the event.
+///
+///   <br/>
 #[cfg(feature = "macro")]
 pub use statig_macro::state_machine;
 
@@ -743,9 +1018,28 @@ pub use statig_macro::state_machine;
 ///
 ///   <br/>
 ///
+/// - `#[state(superstate("connected", "authenticated"))]`
+///
+///   Set the superstate of the state together with a number of mixin superstates.
+///   The first superstate is the parent of the state in the hierarchy, the others
+///   are only used for handling events. An event that is deferred by the state
+///   handler is offered to the handlers of the mixin superstates in the order they
+///   are listed and, if none of them handles it, to the parent superstate and its
+///   ancestors. Mixins are not part of the hierarchy: they are never entered or
+///   exited, so they can't have entry or exit actions, their own superstates are
+///   not used, `on_dispatch` is not called for them and they don't count towards
+///   the depth of the state.
+///
+///   <br/>
+///
 /// - `#[state(entry_action = "entry_action_name")]`
 ///
-///   Set the entry action of the state.
+///   Set the entry action of the state. When the action returns a `bool`, returning
+///   `false` vetoes entering the state. The transition is then rolled back: the
+///   superstates that were already entered are exited again, the states that were
+///   exited are entered again and the state machine stays in the source state
+///   without calling `on_transition`. Vetoes are ignored when the state machine
+///   is initialized.
 ///
 ///   <br/>
 ///
@@ -755,11 +1049,75 @@ pub use statig_macro::state_machine;
 ///
 ///   <br/>
 ///
+/// - `#[state(entry = "self.led = true", exit = "*counter = 0")]`
+///
+///   Declare the entry or exit action inline instead of in a separate `#[action]`
+///   method. The statements can use `self` and take the local storage of the state
+///   by mutable reference, except for lazy storage. They can't use the event or the
+///   context. Can't be combined with `entry_action` or `exit_action`.
+///
+///   <br/>
+///
+/// - `#[state(context = "world")]`
+///
+///   Use a different name for the context input of this handler.
+///
+///   _Default_: the `context_identifier` of the state machine
+///
+///   <br/>
+///
 /// - `#[state(local_storage("field_name_a: FieldTypeA", "field_name_b: FieldTypeB"))]`
 ///
 ///   Add local storage to this state. These will be added as fields to the enum variant.
 ///
 ///   <br/>
+///
+/// - `#[state(lazy_storage("buf: Vec<u8> = make_buf()"))]`
+///
+///   Add local storage that is built the first time the handler of the state
+///   takes it instead of when the state is created. The field is added to the enum
+///   variant as an `Option<Vec<u8>>` that starts out as `None` and is left out of
+///   the state constructor. The expression is evaluated in the module of the state
+///   machine right before the handler is called, and the storage is then kept in
+///   the state. The handler takes the storage as usual (`buf: &mut Vec<u8>`).
+///
+///   The storage is built at most once for every state value. A transition to the
+///   state creates a new state value that starts out without storage again.
+///   The storage is kept inline and needs no allocator, unless its type allocates
+///   itself like `Vec` does, which requires `alloc`. Lazy storage can't be shared
+///   with superstates, taken by actions or taken by a handler that also takes the
+///   state.
+///
+///   <br/>
+///
+/// - `#[state(on(event = "Event::A", action = "action_name", target = "State::target()"))]`
+///
+///   Declare a transition that is taken when the event matches the given pattern. These
+///   are checked before the event is passed to the state handler. The optional action is
+///   called before the transition is performed, so before any exit actions are run.
+///   Bindings in the event pattern can be used in the target expression.
+///
+///   <br/>
+///
+/// - `#[state(terminal)]`
+///
+///   Mark the state as a terminal state. Every event is considered handled once
+///   the state machine has reached it, so the handler must not return a response.
+///   Use [`is_terminal`](crate::blocking::InitializedStateMachine::is_terminal) or
+///   [`is_done`](crate::blocking::InitializedStateMachine::is_done) to check whether
+///   the state machine is done. The state enum also gets a `const fn is_final(&self)`
+///   that returns `true` for terminal states.
+///
+///   <br/>
+///
+/// - `#[state(fallback = "State::idle()")]`
+///
+///   Set the response that is used when the handler returns `Super`. A state
+///   expression turns the response into a transition to that state, while
+///   `"Handled"` marks the event as handled. `"Super"` keeps the default
+///   behavior of deferring the event to the superstate.
+///
+///   <br/>
 #[cfg(feature = "macro")]
 pub use statig_macro::state;
 
@@ -782,6 +1140,17 @@ pub use statig_macro::state;
 ///
 ///   <br/>
 ///
+/// - `#[superstate(initial = "State::initial_state()")]`
+///
+///   Set the initial state of the superstate. This state is used as the initial
+///   state of the state machine when the superstate is passed to
+///   `#[state_machine(initial_superstate = "superstate_name")]`, and as the target
+///   when a state of the superstate returns
+///   [`Response::transition_to_superstate()`](crate::Response::transition_to_superstate).
+///   Without an initial state that response is handled without a transition.
+///
+///   <br/>
+///
 /// - `#[superstate(entry_action = "entry_action_name")]`
 ///
 ///   Set the entry action of the superstate.
@@ -803,6 +1172,14 @@ pub use statig_macro::state;
 ///   associated lifetime `'a`.
 ///
 ///   <br/>
+///
+/// - `#[superstate(context = "world")]`
+///
+///   Use a different name for the context input of this handler.
+///
+///   _Default_: the `context_identifier` of the state machine
+///
+///   <br/>
 #[cfg(feature = "macro")]
 pub use statig_macro::superstate;
 
@@ -818,8 +1195,17 @@ pub mod prelude {
     #[cfg(feature = "async")]
     pub use crate::awaitable::{IntoStateMachineExt as _, StateExt as _, *};
     pub use crate::blocking::{IntoStateMachineExt as _, StateExt as _, *};
+    // Re-exported explicitly so it does not clash with the awaitable one, which
+    // is available as `statig::awaitable::ManagedContextStateMachine`.
+    pub use crate::blocking::ManagedContextStateMachine;
+    #[cfg(feature = "alloc")]
+    pub use crate::EventSink;
+    pub use crate::HasEffects;
+    pub use crate::Lifecycle;
+    pub use crate::RequireHandled;
     pub use crate::Response::{self, *};
     pub use crate::StateOrSuperstate;
+    pub use crate::{handled, transition};
     #[cfg(feature = "macro")]
     pub use statig_macro::state_machine;
 }
@@ -829,8 +1215,34 @@ pub mod blocking;
 #[cfg(feature = "async")]
 pub mod awaitable;
 
+#[cfg(feature = "std")]
+pub use inner::TransitionObserver;
 pub(crate) use inner::*;
 
+#[cfg(feature = "alloc")]
+pub use event_sink::*;
+pub use ffi_events::*;
 pub use into_state_machine::*;
+pub use lifecycle::*;
 pub use response::*;
 pub use state_or_superstate::*;
+pub use unhandled::*;
+
+/// Items used by the code generated by the `state_machine` macro. Not part
+/// of the public API.
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "alloc")]
+    pub use alloc::boxed::Box;
+
+    /// The number of states that can take their entry count.
+    pub const MAX_ENTRY_COUNTS: usize = 8;
+
+    /// Called in a constant by state machines with states that take their entry
+    /// count, so they fail to compile with a clear error when there are too many.
+    pub const fn check_entry_counts(count: usize) {
+        if count > MAX_ENTRY_COUNTS {
+            panic!("at most 8 states can take an `entry_count`");
+        }
+    }
+}
@@ -0,0 +1,20 @@
+/// Event passed to the handlers of a state machine with
+/// `#[state_machine(lifecycle_events)]`, so a single handler can react to the
+/// entry and exit of its state next to the events it handles.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lifecycle<'a, E> {
+    /// The state is being entered.
+    Enter,
+    /// The state is being exited.
+    Exit,
+    /// An event is being handled by the state.
+    Event(&'a E),
+}
+
+impl<'a, E> Clone for Lifecycle<'a, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, E> Copy for Lifecycle<'a, E> {}
@@ -0,0 +1,69 @@
+/// Shorthand for [`Response::Transition`](crate::Response::Transition).
+///
+/// ```
+/// # use statig::prelude::*;
+/// # #[derive(Default)]
+/// # pub struct Blinky;
+/// #
+/// # pub enum Event {
+/// #     TimerElapsed,
+/// #     ButtonPressed,
+/// # }
+/// #
+/// # #[state_machine(initial = "State::led_on()", state(derive(Debug, PartialEq)))]
+/// # impl Blinky {
+/// #[state]
+/// fn led_on(event: &Event) -> Response<State> {
+///     match event {
+///         Event::TimerElapsed => transition!(State::led_off()),
+///         Event::ButtonPressed => handled!(),
+///     }
+/// }
+/// #
+/// #     #[state]
+/// #     fn led_off(event: &Event) -> Response<State> {
+/// #         transition!(State::led_on())
+/// #     }
+/// # }
+/// #
+/// # let mut state_machine = Blinky.uninitialized_state_machine().init();
+/// # state_machine.handle(&Event::TimerElapsed);
+/// # assert_eq!(state_machine.state(), &State::led_off());
+/// ```
+#[macro_export]
+macro_rules! transition {
+    ($state:expr $(,)?) => {
+        $crate::Response::Transition($state)
+    };
+}
+
+/// Shorthand for [`Response::Handled`](crate::Response::Handled).
+///
+/// ```
+/// # use statig::prelude::*;
+/// # #[derive(Default)]
+/// # pub struct Counter {
+/// #     count: u32,
+/// # }
+/// #
+/// # pub struct Event;
+/// #
+/// # #[state_machine(initial = "State::counting()")]
+/// # impl Counter {
+/// #[state]
+/// fn counting(&mut self, event: &Event) -> Response<State> {
+///     self.count += 1;
+///     handled!()
+/// }
+/// # }
+/// #
+/// # let mut state_machine = Counter::default().uninitialized_state_machine().init();
+/// # state_machine.handle(&Event);
+/// # assert_eq!(state_machine.count, 1);
+/// ```
+#[macro_export]
+macro_rules! handled {
+    () => {
+        $crate::Response::Handled
+    };
+}
@@ -8,6 +8,141 @@ pub enum Response<S> {
     Super,
     /// Transition to the given state.
     Transition(S),
+    /// Transition to the initial state of the superstate of the current state.
+    ///
+    /// The initial state is set with `#[superstate(initial = "...")]`. When the
+    /// current state has no superstate, or its superstate has no initial state,
+    /// no transition is taken and the event is considered handled.
+    TransitionToSuperstate,
+}
+
+impl<S> Response<S> {
+    /// Create a [`Transition`](Response::Transition) to a value that can be
+    /// converted into the state type.
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// struct Timeout(u32);
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum State {
+    ///     Waiting { remaining: u32 },
+    /// }
+    ///
+    /// impl From<Timeout> for State {
+    ///     fn from(timeout: Timeout) -> Self {
+    ///         State::Waiting { remaining: timeout.0 }
+    ///     }
+    /// }
+    ///
+    /// let response = Response::<State>::transition_into(Timeout(10));
+    ///
+    /// assert_eq!(response, Response::Transition(State::Waiting { remaining: 10 }));
+    /// ```
+    pub fn transition_into<T>(value: T) -> Self
+    where
+        T: Into<S>,
+    {
+        Self::Transition(value.into())
+    }
+
+    /// Create a [`TransitionToSuperstate`](Response::TransitionToSuperstate), which
+    /// leaves the current state and re-enters the initial state of its superstate.
+    /// When there is no such initial state the event is handled without a transition.
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// let response = Response::<()>::transition_to_superstate();
+    ///
+    /// assert_eq!(response, Response::TransitionToSuperstate);
+    /// ```
+    pub fn transition_to_superstate() -> Self {
+        Self::TransitionToSuperstate
+    }
+
+    /// Returns `true` if the response is [`Handled`](Response::Handled).
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// let response: Response<()> = Response::Handled;
+    ///
+    /// assert!(response.is_handled());
+    /// ```
+    pub fn is_handled(&self) -> bool {
+        matches!(self, Self::Handled)
+    }
+
+    /// Returns `true` if the response is [`Super`](Response::Super).
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// let response: Response<()> = Response::Super;
+    ///
+    /// assert!(response.is_super());
+    /// ```
+    pub fn is_super(&self) -> bool {
+        matches!(self, Self::Super)
+    }
+
+    /// Returns `true` if the response is a [`Transition`](Response::Transition).
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// let response = Response::Transition("led_off");
+    ///
+    /// assert!(response.is_transition());
+    /// ```
+    pub fn is_transition(&self) -> bool {
+        matches!(self, Self::Transition(_))
+    }
+
+    /// Returns the target state if the response is a [`Transition`](Response::Transition).
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// let response = Response::Transition("led_off");
+    ///
+    /// assert_eq!(response.target(), Some(&"led_off"));
+    /// assert_eq!(Response::<&str>::Handled.target(), None);
+    /// ```
+    pub fn target(&self) -> Option<&S> {
+        match self {
+            Self::Transition(target) => Some(target),
+            _ => None,
+        }
+    }
+
+    /// Map the target state of a [`Transition`](Response::Transition) to another
+    /// type, for example to lift the response of a nested state machine into the
+    /// state type of the outer state machine. Other responses are passed through
+    /// unchanged.
+    ///
+    /// ```
+    /// # use statig::Response;
+    /// #[derive(Debug, PartialEq)]
+    /// enum Inner {
+    ///     Idle,
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// enum Outer {
+    ///     Nested(Inner),
+    /// }
+    ///
+    /// let response = Response::Transition(Inner::Idle).map(Outer::Nested);
+    /// assert_eq!(response, Response::Transition(Outer::Nested(Inner::Idle)));
+    ///
+    /// assert_eq!(Response::<Inner>::Handled.map(Outer::Nested), Response::Handled);
+    /// assert_eq!(Response::<Inner>::Super.map(Outer::Nested), Response::Super);
+    /// ```
+    pub fn map<T>(self, f: impl FnOnce(S) -> T) -> Response<T> {
+        match self {
+            Self::Handled => Response::Handled,
+            Self::Super => Response::Super,
+            Self::Transition(target) => Response::Transition(f(target)),
+            Self::TransitionToSuperstate => Response::TransitionToSuperstate,
+        }
+    }
 }
 
 impl<S> PartialEq for Response<S>
@@ -19,6 +154,7 @@ where
             (Self::Handled, Self::Handled) => true,
             (Self::Super, Self::Super) => true,
             (Self::Transition(s), Self::Transition(o)) => s == o,
+            (Self::TransitionToSuperstate, Self::TransitionToSuperstate) => true,
             _ => false,
         }
     }
@@ -38,6 +174,7 @@ where
                 .debug_tuple("Transition")
                 .field(state as &dyn Debug)
                 .finish(),
+            Self::TransitionToSuperstate => f.debug_tuple("TransitionToSuperstate").finish(),
         }
     }
 }
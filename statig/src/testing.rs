@@ -0,0 +1,47 @@
+//! Utilities for testing state machines. Only available with the `testing`
+//! feature.
+
+use crate::blocking::{self, StateExt as _};
+use crate::IntoStateMachine;
+
+/// Compute the levels that are exited and entered when transitioning from one
+/// state to another, as `(exit_levels, enter_levels)`.
+///
+/// Finding the superstates of a state borrows its state-local storage mutably,
+/// so the path is computed on clones of the given states.
+///
+/// ```
+/// # use statig::prelude::*;
+/// # use statig::testing::compute_transition_path;
+/// # #[derive(Default)]
+/// # pub struct Blinky;
+/// #
+/// # #[state_machine(initial = "State::led_on()", state(derive(Clone)))]
+/// # impl Blinky {
+/// #     #[state(superstate = "blinking")]
+/// #     fn led_on() -> Response<State> { Handled }
+/// #
+/// #     #[state(superstate = "blinking")]
+/// #     fn led_off() -> Response<State> { Handled }
+/// #
+/// #     #[superstate]
+/// #     fn blinking() -> Response<State> { Handled }
+/// #
+/// #     #[state]
+/// #     fn not_blinking() -> Response<State> { Handled }
+/// # }
+/// #
+/// let path = compute_transition_path::<Blinky>(&State::led_on(), &State::led_off());
+/// assert_eq!(path, (1, 1));
+///
+/// let path = compute_transition_path::<Blinky>(&State::led_on(), &State::not_blinking());
+/// assert_eq!(path, (2, 1));
+/// ```
+pub fn compute_transition_path<M>(from: &M::State, to: &M::State) -> (usize, usize)
+where
+    M: IntoStateMachine,
+    M::State: blocking::State<M> + Clone,
+    for<'sub> M::Superstate<'sub>: blocking::Superstate<M>,
+{
+    from.clone().transition_path(&mut to.clone())
+}
@@ -0,0 +1,79 @@
+use core::fmt::Debug;
+
+use crate::IntoStateMachine;
+
+/// Marker trait for state machines that require the caller to deal with events that
+/// reach the top of the hierarchy without being handled.
+///
+/// `try_handle` and its variants require this trait. The `state_machine` macro only
+/// implements it when the state machine is declared with
+/// `#[state_machine(require_handled)]`. `handle` remains available and ignores
+/// unhandled events as before.
+///
+/// ```compile_fail
+/// # use statig::prelude::*;
+/// # #[derive(Default)]
+/// # struct Door;
+/// #[state_machine(initial = "State::closed()")]
+/// impl Door {
+///     #[state]
+///     fn closed(event: &bool) -> Response<State> {
+///         match event {
+///             true => Handled,
+///             false => Super,
+///         }
+///     }
+/// }
+///
+/// let mut state_machine = Door.uninitialized_state_machine().init();
+///
+/// // Does not compile, `Door` does not implement `RequireHandled`.
+/// state_machine.try_handle(&false);
+/// ```
+pub trait RequireHandled: IntoStateMachine {}
+
+/// Error returned by `try_handle` when an event reached the top of the hierarchy
+/// without being handled by any state or superstate.
+///
+/// State machines that are declared with `#[state_machine(require_handled)]`
+/// implement [`RequireHandled`] and can be driven with `try_handle`, so the caller
+/// has to decide what to do with such events.
+pub struct Unhandled<'a, M>
+where
+    M: IntoStateMachine,
+{
+    /// The event that was not handled.
+    pub event: &'a M::Event<'a>,
+}
+
+impl<'a, M> Clone for Unhandled<'a, M>
+where
+    M: IntoStateMachine,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, M> Copy for Unhandled<'a, M> where M: IntoStateMachine {}
+
+impl<'a, M> Debug for Unhandled<'a, M>
+where
+    M: IntoStateMachine,
+    M::Event<'a>: Debug,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Unhandled")
+            .field("event", &self.event)
+            .finish()
+    }
+}
+
+impl<'a, M> core::fmt::Display for Unhandled<'a, M>
+where
+    M: IntoStateMachine,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the event was not handled by any state or superstate")
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    /// A world whose parts are borrowed separately by the event and the context.
+    struct World {
+        input: Vec<u8>,
+        score: u32,
+    }
+
+    struct Input<'a> {
+        bytes: &'a [u8],
+    }
+
+    struct Score<'a> {
+        total: &'a mut u32,
+    }
+
+    #[derive(Default)]
+    struct Scorer;
+
+    #[state_machine(initial = "State::counting()", state(derive(Debug, PartialEq, Eq)))]
+    impl Scorer {
+        #[state(superstate = "scoring")]
+        fn counting(event: &Input<'_>, context: &mut Score<'_>) -> Response<State> {
+            match event.bytes {
+                [] => Super,
+                bytes => {
+                    *context.total += bytes.len() as u32;
+                    Handled
+                }
+            }
+        }
+
+        #[superstate]
+        fn scoring(event: &Input<'_>, context: &mut Score<'_>) -> Response<State> {
+            *context.total += 100;
+            match event.bytes.is_empty() {
+                true => Transition(State::done()),
+                false => Handled,
+            }
+        }
+
+        #[state]
+        fn done() -> Response<State> {
+            Handled
+        }
+    }
+
+    #[test]
+    fn event_and_context_borrow_the_same_world() {
+        let mut world = World {
+            input: vec![1, 2, 3],
+            score: 0,
+        };
+        let mut state_machine =
+            Scorer
+                .uninitialized_state_machine()
+                .init_with_context(&mut Score {
+                    total: &mut world.score,
+                });
+
+        let World { input, score } = &mut world;
+        state_machine.handle_with_context(&Input { bytes: input }, &mut Score { total: score });
+        assert_eq!(world.score, 3);
+
+        world.input.clear();
+        let World { input, score } = &mut world;
+        state_machine.handle_with_context(&Input { bytes: input }, &mut Score { total: score });
+        assert_eq!(world.score, 103);
+        assert_eq!(state_machine.state(), &State::done());
+    }
+}
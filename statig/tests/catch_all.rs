@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Repl {
+        unknown: Vec<&'static str>,
+    }
+
+    enum Event {
+        Command(&'static str),
+        Quit,
+    }
+
+    #[state_machine(
+        initial = "State::prompt()",
+        state(derive(Debug, PartialEq, Eq)),
+        catch_all = "unknown"
+    )]
+    impl Repl {
+        #[state(superstate = "running")]
+        fn prompt(event: &Event) -> Response<State> {
+            match event {
+                Event::Command("help") => Handled,
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn running(event: &Event) -> Response<State> {
+            match event {
+                Event::Quit => Transition(State::unknown()),
+                _ => Super,
+            }
+        }
+
+        #[state]
+        fn unknown(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Command(command) => {
+                    self.unknown.push(command);
+                    Handled
+                }
+                Event::Quit => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn unhandled_events_reach_catch_all() {
+        let mut state_machine = Repl::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Command("help"));
+        state_machine.handle(&Event::Command("ls"));
+
+        assert_eq!(state_machine.unknown, ["ls"]);
+        assert_eq!(state_machine.state(), &State::prompt());
+    }
+
+    #[test]
+    fn catch_all_state_is_not_called_twice() {
+        let mut state_machine = Repl::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Quit);
+        assert_eq!(state_machine.state(), &State::unknown());
+
+        state_machine.handle(&Event::Command("cd"));
+        state_machine.handle(&Event::Quit);
+
+        assert_eq!(state_machine.unknown, ["cd"]);
+        assert_eq!(state_machine.state(), &State::unknown());
+    }
+}
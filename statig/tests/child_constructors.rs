@@ -0,0 +1,73 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    pub struct Menu;
+
+    pub enum Event {
+        Go(usize),
+        Back,
+    }
+
+    #[state_machine(initial = "State::home()", state(derive(Debug, PartialEq, Eq)))]
+    impl Menu {
+        #[state]
+        fn home(event: &Event) -> Response<State> {
+            match event {
+                Event::Go(_) => Transition(State::settings()),
+                Event::Back => Handled,
+            }
+        }
+
+        #[state(superstate = "pages")]
+        fn settings() -> Response<State> {
+            Super
+        }
+
+        #[state(superstate = "pages", local_storage("visits: usize"))]
+        fn about() -> Response<State> {
+            Super
+        }
+
+        #[state(superstate = "pages")]
+        fn help() -> Response<State> {
+            Super
+        }
+
+        #[superstate]
+        fn pages(event: &Event) -> Response<State> {
+            match event {
+                Event::Go(index) => match State::PAGES_CHILDREN.get(*index) {
+                    Some(constructor) => Transition(constructor()),
+                    None => Handled,
+                },
+                Event::Back => Transition(State::home()),
+            }
+        }
+    }
+
+    #[test]
+    fn constructors_skip_states_with_local_storage() {
+        assert_eq!(State::PAGES_CHILDREN.len(), 2);
+        assert_eq!(State::PAGES_CHILDREN[0](), State::settings());
+        assert_eq!(State::PAGES_CHILDREN[1](), State::help());
+    }
+
+    #[test]
+    fn superstate_picks_child_by_index() {
+        let mut state_machine = Menu.uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Go(0));
+        assert_eq!(state_machine.state(), &State::settings());
+
+        state_machine.handle(&Event::Go(1));
+        assert_eq!(state_machine.state(), &State::help());
+
+        state_machine.handle(&Event::Go(5));
+        assert_eq!(state_machine.state(), &State::help());
+
+        state_machine.handle(&Event::Back);
+        assert_eq!(state_machine.state(), &State::home());
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Classifier {
+        limit: u32,
+    }
+
+    #[derive(Debug)]
+    enum Event {
+        Reading(u32),
+        Reset,
+    }
+
+    #[state_machine(initial = "State::normal()", state(derive(Debug, PartialEq, Eq)))]
+    impl Classifier {
+        #[state(superstate = "monitoring")]
+        fn normal(&self, event: &Event) -> Response<State> {
+            match event {
+                Event::Reading(value) if *value > self.limit => Transition(State::alarm(*value)),
+                Event::Reading(_) => Handled,
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "monitoring")]
+        fn alarm(peak: &u32, event: &Event) -> Response<State> {
+            match event {
+                Event::Reading(value) if value > peak => Transition(State::alarm(*value)),
+                Event::Reading(_) => Handled,
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn monitoring(event: &Event) -> Response<State> {
+            match event {
+                Event::Reset => Transition(State::normal()),
+                _ => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn classify_does_not_act_on_the_response() {
+        let mut state_machine = Classifier { limit: 10 }
+            .uninitialized_state_machine()
+            .init();
+
+        let shared = &state_machine;
+        assert_eq!(shared.classify(&Event::Reading(4)), Handled);
+        assert_eq!(
+            shared.classify(&Event::Reading(11)),
+            Transition(State::alarm(11))
+        );
+        assert_eq!(shared.classify(&Event::Reset), Transition(State::normal()));
+        assert_eq!(shared.state(), &State::normal());
+
+        state_machine.handle(&Event::Reading(20));
+
+        assert_eq!(state_machine.classify(&Event::Reading(15)), Handled);
+        assert_eq!(
+            state_machine.classify(&Event::Reading(25)),
+            Transition(State::alarm(25))
+        );
+        assert_eq!(state_machine.state(), &State::alarm(20));
+    }
+}
@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct TrafficLight;
+
+    enum Event {
+        Timer,
+        Fault,
+        Repair,
+    }
+
+    #[state_machine(
+        initial = "State::red()",
+        state(derive(Debug, Clone, PartialEq, Eq)),
+        const_step
+    )]
+    impl TrafficLight {
+        #[state(superstate = "operational")]
+        const fn red(event: &Event) -> Response<State> {
+            match event {
+                Event::Timer => Transition(State::green()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "operational")]
+        const fn green(event: &Event) -> Response<State> {
+            match event {
+                Event::Timer => Transition(State::yellow()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "operational")]
+        const fn yellow(event: &Event) -> Response<State> {
+            match event {
+                Event::Timer => Transition(State::red()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        const fn operational(event: &Event) -> Response<State> {
+            match event {
+                Event::Fault => Transition(State::blinking()),
+                _ => Handled,
+            }
+        }
+
+        #[state(on(event = "Event::Repair", target = "State::red()"))]
+        const fn blinking() -> Response<State> {
+            Handled
+        }
+    }
+
+    const AFTER_TWO_TIMERS: State = State::red().step(&Event::Timer).step(&Event::Timer);
+
+    #[test]
+    fn const_step() {
+        assert_eq!(AFTER_TWO_TIMERS, State::yellow());
+        assert_eq!(State::green().step(&Event::Fault), State::blinking());
+        assert_eq!(State::green().step(&Event::Repair), State::green());
+        assert_eq!(State::blinking().step(&Event::Timer), State::blinking());
+        assert_eq!(State::blinking().step(&Event::Repair), State::red());
+    }
+
+    #[test]
+    fn step_matches_state_machine() {
+        let mut state_machine = TrafficLight.uninitialized_state_machine().init();
+        let events = [Event::Timer, Event::Fault, Event::Timer, Event::Repair];
+
+        for event in &events {
+            let expected = state_machine.state().clone().step(event);
+            state_machine.handle(event);
+            assert_eq!(state_machine.state(), &expected);
+        }
+    }
+
+    mod local_storage {
+        use statig::prelude::*;
+
+        #[derive(Default)]
+        struct Greeter;
+
+        #[state_machine(initial = "State::idle()", state(derive(Debug, Clone, PartialEq, Eq)))]
+        impl Greeter {
+            #[state]
+            const fn idle(event: &bool) -> Response<State> {
+                match event {
+                    true => Transition(State::greeting(String::new())),
+                    false => Handled,
+                }
+            }
+
+            #[state(local_storage("name: String"))]
+            const fn greeting(event: &bool) -> Response<State> {
+                match event {
+                    true => Handled,
+                    false => Transition(State::idle()),
+                }
+            }
+        }
+
+        #[test]
+        fn const_handlers_with_non_copy_local_storage() {
+            let mut state_machine = Greeter.uninitialized_state_machine().init();
+
+            state_machine.handle(&true);
+            assert_eq!(state_machine.state(), &State::greeting(String::new()));
+
+            state_machine.handle(&false);
+            assert_eq!(state_machine.state(), &State::idle());
+        }
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod tests {
+    mod blinky {
+        use statig::prelude::*;
+
+        #[derive(Default)]
+        pub struct Blinky;
+
+        #[state_machine(
+            initial = "State::idle()",
+            state(derive(Debug, PartialEq, Eq), constructor_trait = "BlinkyStates")
+        )]
+        impl Blinky {
+            #[state]
+            fn idle() -> Response<State> {
+                Transition(State::blinking(3))
+            }
+
+            #[state]
+            fn blinking(remaining: &mut u32) -> Response<State> {
+                *remaining -= 1;
+                Handled
+            }
+        }
+    }
+
+    mod buzzer {
+        use statig::prelude::*;
+
+        #[derive(Default)]
+        pub struct Buzzer;
+
+        #[state_machine(
+            initial = "State::idle()",
+            state(derive(Debug, PartialEq, Eq), constructor_trait = "BuzzerStates")
+        )]
+        impl Buzzer {
+            #[state]
+            fn idle() -> Response<State> {
+                Transition(State::buzzing())
+            }
+
+            #[state]
+            fn buzzing() -> Response<State> {
+                Handled
+            }
+        }
+    }
+
+    use blinky::BlinkyStates;
+    use buzzer::BuzzerStates;
+
+    trait Idle {
+        fn idle() -> Self;
+    }
+
+    impl Idle for blinky::State {
+        fn idle() -> Self {
+            <Self as BlinkyStates>::idle()
+        }
+    }
+
+    impl Idle for buzzer::State {
+        fn idle() -> Self {
+            <Self as BuzzerStates>::idle()
+        }
+    }
+
+    fn reset<S: Idle>(state: &mut S) {
+        *state = S::idle();
+    }
+
+    #[test]
+    fn trait_mirrors_constructors() {
+        assert_eq!(
+            <blinky::State as BlinkyStates>::blinking(2),
+            blinky::State::blinking(2)
+        );
+        assert_eq!(
+            <buzzer::State as BuzzerStates>::buzzing(),
+            buzzer::State::buzzing()
+        );
+    }
+
+    #[test]
+    fn generic_code_over_constructors() {
+        let mut blinky = blinky::State::blinking(1);
+        let mut buzzer = buzzer::State::buzzing();
+
+        reset(&mut blinky);
+        reset(&mut buzzer);
+
+        assert_eq!(blinky, <blinky::State as BlinkyStates>::idle());
+        assert_eq!(buzzer, <buzzer::State as BuzzerStates>::idle());
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Counter;
+
+    struct World {
+        count: usize,
+    }
+
+    enum Event {
+        Increment,
+        Switch,
+    }
+
+    #[state_machine(initial = "State::counting()", state(derive(Debug, PartialEq, Eq)))]
+    impl Counter {
+        #[state(superstate = "active", context = "world")]
+        fn counting(world: &mut World, event: &Event) -> Response<State> {
+            match event {
+                Event::Increment => {
+                    world.count += 1;
+                    Handled
+                }
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "active")]
+        fn doubling(context: &mut World, event: &Event) -> Response<State> {
+            match event {
+                Event::Increment => {
+                    context.count *= 2;
+                    Handled
+                }
+                _ => Super,
+            }
+        }
+
+        #[superstate(context = "registers")]
+        fn active(registers: &mut World, event: &Event) -> Response<State> {
+            match event {
+                Event::Switch if registers.count > 1 => Transition(State::doubling()),
+                _ => Handled,
+            }
+        }
+    }
+
+    #[test]
+    fn context_rename() {
+        let mut world = World { count: 0 };
+        let mut state_machine = Counter
+            .uninitialized_state_machine()
+            .init_with_context(&mut world);
+
+        state_machine.handle_with_context(&Event::Increment, &mut world);
+        state_machine.handle_with_context(&Event::Switch, &mut world);
+
+        assert_eq!(state_machine.state(), &State::counting());
+
+        state_machine.handle_with_context(&Event::Increment, &mut world);
+        state_machine.handle_with_context(&Event::Switch, &mut world);
+        state_machine.handle_with_context(&Event::Increment, &mut world);
+
+        assert_eq!(state_machine.state(), &State::doubling());
+        assert_eq!(world.count, 4);
+    }
+}
@@ -0,0 +1,53 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Debug, Default)]
+    struct Blinky {
+        led: bool,
+    }
+
+    #[state_machine(initial = "State::on()", state(derive(Debug)))]
+    impl Blinky {
+        #[state(entry_action = "enter_on")]
+        fn on() -> Response<State> {
+            Handled
+        }
+
+        #[action]
+        fn enter_on(&mut self) {
+            self.led = true;
+        }
+    }
+
+    #[test]
+    fn debug_lazy_state_machine() {
+        let mut state_machine = Blinky::default().state_machine();
+
+        assert_eq!(
+            format!("{state_machine:?}"),
+            "StateMachine { initialized: false, shared_storage: Blinky { led: false }, state: On }"
+        );
+
+        state_machine.init();
+
+        assert_eq!(
+            format!("{state_machine:?}"),
+            "StateMachine { initialized: true, shared_storage: Blinky { led: true }, state: On }"
+        );
+    }
+
+    #[test]
+    fn debug_response() {
+        assert_eq!(format!("{:?}", Response::<State>::Handled), "Handled");
+        assert_eq!(format!("{:?}", Response::<State>::Super), "Super");
+        assert_eq!(
+            format!("{:?}", Response::Transition(State::on())),
+            "Transition(On)"
+        );
+        assert_eq!(
+            format!("{:?}", Response::<State>::TransitionToSuperstate),
+            "TransitionToSuperstate"
+        );
+    }
+}
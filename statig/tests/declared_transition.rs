@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Step {
+        Log(usize),
+        Exit,
+        Enter,
+        Handler,
+    }
+
+    enum Event {
+        Start(usize),
+        Stop,
+        Tick,
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        steps: Vec<Step>,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Counter {
+        #[state(
+            on(
+                event = "Event::Start(start)",
+                action = "log",
+                target = "State::counting(*start)"
+            ),
+            exit_action = "exit_idle"
+        )]
+        fn idle(&mut self) -> Response<State> {
+            self.steps.push(Step::Handler);
+            Handled
+        }
+
+        #[state(
+            entry_action = "enter_counting",
+            on(event = "Event::Stop", target = "State::idle()")
+        )]
+        fn counting(count: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => {
+                    *count += 1;
+                    Handled
+                }
+                _ => Super,
+            }
+        }
+
+        #[action]
+        fn log(&mut self, start: &usize) {
+            self.steps.push(Step::Log(*start));
+        }
+
+        #[action]
+        fn exit_idle(&mut self) {
+            self.steps.push(Step::Exit);
+        }
+
+        #[action]
+        fn enter_counting(&mut self) {
+            self.steps.push(Step::Enter);
+        }
+    }
+
+    #[test]
+    fn declared_transition() {
+        let mut state_machine = Counter::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Tick);
+
+        assert_eq!(state_machine.steps, [Step::Handler]);
+        assert_eq!(state_machine.state(), &State::idle());
+
+        state_machine.handle(&Event::Start(3));
+
+        assert_eq!(
+            state_machine.steps,
+            [Step::Handler, Step::Log(3), Step::Exit, Step::Enter]
+        );
+        assert_eq!(state_machine.state(), &State::counting(3));
+
+        state_machine.handle(&Event::Tick);
+
+        assert_eq!(state_machine.state(), &State::counting(4));
+
+        state_machine.handle(&Event::Stop);
+
+        assert_eq!(state_machine.state(), &State::idle());
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Counter {
+        count: usize,
+    }
+
+    #[derive(Default)]
+    struct Context {
+        step: usize,
+    }
+
+    enum Event {
+        Increment,
+    }
+
+    #[state_machine(initial = "State::counting()")]
+    impl Counter {
+        #[state]
+        fn counting(&mut self, context: &mut Context, event: &Event) -> Response<State> {
+            match event {
+                Event::Increment => {
+                    context.step += 1;
+                    self.count += context.step;
+                    Handled
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn handle_default_context() {
+        let mut state_machine = Counter::default()
+            .uninitialized_state_machine()
+            .init_with_context(&mut Context::default());
+
+        state_machine.handle_default_context(&Event::Increment);
+        state_machine.handle_default_context(&Event::Increment);
+
+        assert_eq!(state_machine.count, 2);
+
+        let mut lazy_state_machine = Counter::default().state_machine();
+
+        lazy_state_machine.handle_default_context(&Event::Increment);
+
+        assert_eq!(lazy_state_machine.count, 1);
+    }
+}
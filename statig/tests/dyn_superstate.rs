@@ -0,0 +1,154 @@
+#[cfg(test)]
+mod tests {
+    use statig::blocking::{self, *};
+
+    enum Event {
+        Goto(&'static str, Vec<&'static str>),
+        Ping,
+    }
+
+    #[derive(Default)]
+    struct Machine {
+        log: Vec<String>,
+        pings: usize,
+    }
+
+    /// A superstate that is only known at runtime, innermost first.
+    struct Layer {
+        name: &'static str,
+        parent: Option<Box<Layer>>,
+    }
+
+    impl Layer {
+        fn chain(names: &[&'static str]) -> Option<Box<Layer>> {
+            names
+                .iter()
+                .rev()
+                .fold(None, |parent, name| Some(Box::new(Layer { name, parent })))
+        }
+    }
+
+    impl DynSuperstate<Machine> for Layer {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn call_handler(
+            &mut self,
+            machine: &mut Machine,
+            event: &Event,
+            _: &mut (),
+        ) -> Response<State> {
+            match (self.name, event) {
+                ("root", Event::Ping) => {
+                    machine.pings += 1;
+                    Handled
+                }
+                _ => Super,
+            }
+        }
+
+        fn call_entry_action(&mut self, machine: &mut Machine, _: &mut ()) {
+            machine.log.push(format!("enter {}", self.name));
+        }
+
+        fn call_exit_action(&mut self, machine: &mut Machine, _: &mut ()) {
+            machine.log.push(format!("exit {}", self.name));
+        }
+
+        fn superstate(&mut self) -> Option<&mut dyn DynSuperstate<Machine>> {
+            match &mut self.parent {
+                Some(parent) => Some(parent.as_mut()),
+                None => None,
+            }
+        }
+    }
+
+    struct State {
+        name: &'static str,
+        layer: Option<Box<Layer>>,
+    }
+
+    impl IntoStateMachine for Machine {
+        type State = State;
+
+        type Superstate<'sub> = &'sub mut dyn DynSuperstate<Self>;
+
+        type Event<'evt> = Event;
+
+        type Context<'ctx> = ();
+
+        const INITIAL: State = State {
+            name: "idle",
+            layer: None,
+        };
+    }
+
+    impl blocking::State<Machine> for State {
+        fn call_handler(&mut self, _: &mut Machine, event: &Event, _: &mut ()) -> Response<State> {
+            match event {
+                Event::Goto(name, layers) => Transition(State {
+                    name,
+                    layer: Layer::chain(layers),
+                }),
+                Event::Ping => Super,
+            }
+        }
+
+        fn call_entry_action(&mut self, machine: &mut Machine, _: &mut ()) {
+            machine.log.push(format!("enter {}", self.name));
+        }
+
+        fn call_exit_action(&mut self, machine: &mut Machine, _: &mut ()) {
+            machine.log.push(format!("exit {}", self.name));
+        }
+
+        fn is_same(&self, other: &Self) -> bool {
+            self.name == other.name
+        }
+
+        fn superstate(&mut self) -> Option<&mut dyn DynSuperstate<Machine>> {
+            match &mut self.layer {
+                Some(layer) => Some(layer.as_mut()),
+                None => None,
+            }
+        }
+    }
+
+    #[test]
+    fn transitions_stop_at_the_common_ancestor() {
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Goto("a", vec!["group", "root"]));
+        assert_eq!(
+            state_machine.log,
+            [
+                "enter idle",
+                "exit idle",
+                "enter root",
+                "enter group",
+                "enter a"
+            ]
+        );
+
+        state_machine.handle(&Event::Goto("b", vec!["group", "root"]));
+        assert_eq!(state_machine.log[5..], ["exit a", "enter b"]);
+
+        state_machine.handle(&Event::Goto("c", vec!["other", "root"]));
+        assert_eq!(
+            state_machine.log[7..],
+            ["exit b", "exit group", "enter other", "enter c"]
+        );
+    }
+
+    #[test]
+    fn unhandled_events_bubble_up_through_the_layers() {
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Goto("a", vec!["group", "root"]));
+        state_machine.handle(&Event::Ping);
+
+        assert_eq!(state_machine.pings, 1);
+        assert_eq!(state_machine.state().name, "a");
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(test)]
+mod tests {
+    use core::ops::BitOrAssign;
+    use statig::prelude::*;
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    struct Effects(u8);
+
+    impl Effects {
+        const NONE: Effects = Effects(0);
+        const BEEP: Effects = Effects(1 << 0);
+        const LOG: Effects = Effects(1 << 1);
+    }
+
+    impl BitOrAssign for Effects {
+        fn bitor_assign(&mut self, rhs: Self) {
+            self.0 |= rhs.0;
+        }
+    }
+
+    enum Event {
+        Start,
+        Stop,
+        Ping,
+    }
+
+    #[derive(Default)]
+    struct Machine;
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Machine {
+        #[state(superstate = "powered")]
+        fn idle(event: &Event) -> (Effects, Response<State>) {
+            match event {
+                Event::Start => (Effects::BEEP, Transition(State::running())),
+                _ => (Effects::NONE, Super),
+            }
+        }
+
+        #[state(superstate = "powered")]
+        fn running(event: &Event) -> Response<State> {
+            match event {
+                Event::Stop => Transition(State::idle()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn powered(event: &Event) -> (Effects, Response<State>) {
+            match event {
+                Event::Ping => (Effects::LOG, Handled),
+                _ => (Effects::NONE, Handled),
+            }
+        }
+    }
+
+    #[test]
+    fn effects_are_collected_by_handle_with_effects() {
+        let mut state_machine = Machine.uninitialized_state_machine().init();
+
+        let mut effects = Effects::default();
+        state_machine.handle_with_effects(&Event::Start, &mut effects);
+        assert_eq!(effects, Effects::BEEP);
+        assert_eq!(state_machine.state(), &State::running());
+
+        let mut effects = Effects::default();
+        state_machine.handle_with_effects(&Event::Stop, &mut effects);
+        assert_eq!(effects, Effects::NONE);
+        assert_eq!(state_machine.state(), &State::idle());
+    }
+
+    #[test]
+    fn effects_of_superstates_are_combined() {
+        let mut state_machine = Machine.uninitialized_state_machine().init();
+
+        let mut effects = Effects::BEEP;
+        state_machine.handle_with_effects(&Event::Ping, &mut effects);
+
+        assert_eq!(effects, Effects(Effects::BEEP.0 | Effects::LOG.0));
+    }
+
+    #[test]
+    fn effects_are_dropped_by_handle() {
+        let mut state_machine = Machine.uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Start);
+
+        assert_eq!(state_machine.state(), &State::running());
+    }
+
+    mod with_context {
+        use super::Effects;
+        use statig::prelude::*;
+
+        #[derive(Default)]
+        struct Counter {
+            count: usize,
+        }
+
+        #[derive(Default)]
+        struct Machine;
+
+        #[state_machine(initial = "State::idle()")]
+        impl Machine {
+            #[state]
+            fn idle(context: &mut Counter) -> (Effects, Response<State>) {
+                context.count += 1;
+                (Effects::LOG, Handled)
+            }
+        }
+
+        #[test]
+        fn effects_are_collected_next_to_the_context() {
+            let mut context = Counter::default();
+            let mut state_machine = Machine
+                .uninitialized_state_machine()
+                .init_with_context(&mut context);
+
+            let mut effects = Effects::default();
+            state_machine.handle_with_context_and_effects(&(), &mut context, &mut effects);
+
+            assert_eq!(context.count, 1);
+            assert_eq!(effects, Effects::LOG);
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "async")]
+mod awaitable_tests {
+    use futures::executor;
+    use statig::prelude::*;
+
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct Effects(Vec<&'static str>);
+
+    impl core::ops::BitOrAssign<&'static str> for Effects {
+        fn bitor_assign(&mut self, effect: &'static str) {
+            self.0.push(effect);
+        }
+    }
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    #[derive(Default)]
+    struct Machine;
+
+    #[state_machine(
+        initial = "State::idle()",
+        effects = "Effects",
+        state(derive(Debug, PartialEq, Eq)),
+        allow_single_async
+    )]
+    impl Machine {
+        #[state]
+        async fn idle(context: &mut Log) -> (&'static str, Response<State>) {
+            context.0.push("idle");
+            ("beep", Transition(State::done()))
+        }
+
+        #[state]
+        fn done() -> Response<State> {
+            Handled
+        }
+    }
+
+    #[test]
+    fn effects_are_collected_into_the_effects_type() {
+        executor::block_on(async {
+            let mut state_machine = Machine
+                .uninitialized_state_machine()
+                .init_with_context(&mut Log::default())
+                .await;
+
+            let mut log = Log::default();
+            let mut effects = Effects::default();
+            state_machine
+                .handle_with_context_and_effects(&(), &mut log, &mut effects)
+                .await;
+
+            assert_eq!(log.0, vec!["idle"]);
+            assert_eq!(effects, Effects(vec!["beep"]));
+            assert_eq!(state_machine.state(), &State::done());
+        });
+    }
+}
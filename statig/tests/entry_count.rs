@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Connection {
+        attempts: Vec<u32>,
+    }
+
+    enum Event {
+        Failed,
+        TimerElapsed,
+    }
+
+    #[state_machine(initial = "State::connecting()", state(derive(Debug, PartialEq, Eq)))]
+    impl Connection {
+        #[state]
+        fn connecting(&mut self, entry_count: u32, event: &Event) -> Response<State> {
+            match event {
+                Event::Failed if entry_count < 3 => {
+                    self.attempts.push(entry_count);
+                    Transition(State::backoff())
+                }
+                Event::Failed => {
+                    self.attempts.push(entry_count);
+                    Transition(State::offline())
+                }
+                Event::TimerElapsed => Handled,
+            }
+        }
+
+        #[state]
+        fn backoff(event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::connecting()),
+                Event::Failed => Handled,
+            }
+        }
+
+        #[state]
+        fn offline(entry_count: u32) -> Response<State> {
+            assert!(entry_count > 0);
+            Handled
+        }
+    }
+
+    #[test]
+    fn count_entries_across_transitions() {
+        let mut state_machine = Connection::default().uninitialized_state_machine().init();
+
+        for _ in 0..3 {
+            state_machine.handle(&Event::Failed);
+            state_machine.handle(&Event::TimerElapsed);
+        }
+
+        // The entry count is not part of the identity of the state.
+        assert_eq!(state_machine.state(), &State::offline());
+        assert_eq!(state_machine.attempts, [1, 2, 3]);
+    }
+
+    #[test]
+    fn reset_entry_counts() {
+        let mut state_machine = Connection::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Failed);
+        state_machine.handle(&Event::TimerElapsed);
+        state_machine.reset_entry_counts();
+        state_machine.handle(&Event::Failed);
+        state_machine.handle(&Event::TimerElapsed);
+        state_machine.handle(&Event::Failed);
+
+        assert_eq!(state_machine.state(), &State::backoff());
+        assert_eq!(state_machine.attempts, [1, 2, 1]);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "async")]
+mod awaitable_tests {
+    use futures::executor;
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Connection {
+        attempts: Vec<u32>,
+    }
+
+    #[state_machine(initial = "State::connecting()", state(derive(Debug, PartialEq, Eq)))]
+    impl Connection {
+        #[state]
+        async fn connecting(&mut self, entry_count: u32) -> Response<State> {
+            self.attempts.push(entry_count);
+            Transition(State::backoff())
+        }
+
+        #[state]
+        async fn backoff() -> Response<State> {
+            Transition(State::connecting())
+        }
+    }
+
+    #[test]
+    fn count_entries_across_transitions() {
+        executor::block_on(async {
+            let mut state_machine = Connection::default()
+                .uninitialized_state_machine()
+                .init()
+                .await;
+
+            for _ in 0..6 {
+                state_machine.handle(&()).await;
+            }
+
+            assert_eq!(state_machine.attempts, [1, 2, 3]);
+        });
+    }
+}
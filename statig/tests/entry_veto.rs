@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Machine {
+        available: bool,
+        log: Vec<&'static str>,
+        transitions: usize,
+    }
+
+    enum Event {
+        Lock,
+        Unlock,
+    }
+
+    #[state_machine(
+        initial = "State::open()",
+        state(derive(Debug, PartialEq, Eq)),
+        on_transition = "Self::on_transition"
+    )]
+    impl Machine {
+        #[state(
+            superstate = "unlocked",
+            entry_action = "enter_open",
+            exit_action = "exit_open"
+        )]
+        fn open(event: &Event) -> Response<State> {
+            match event {
+                Event::Lock => Transition(State::locked()),
+                Event::Unlock => Handled,
+            }
+        }
+
+        #[superstate(entry_action = "enter_unlocked", exit_action = "exit_unlocked")]
+        fn unlocked() -> Response<State> {
+            Super
+        }
+
+        #[state(superstate = "secured", entry_action = "enter_locked")]
+        fn locked(event: &Event) -> Response<State> {
+            match event {
+                Event::Unlock => Transition(State::open()),
+                Event::Lock => Handled,
+            }
+        }
+
+        #[superstate(entry_action = "enter_secured", exit_action = "exit_secured")]
+        fn secured() -> Response<State> {
+            Super
+        }
+
+        #[action]
+        fn enter_open(&mut self) {
+            self.log.push("enter open");
+        }
+
+        #[action]
+        fn exit_open(&mut self) {
+            self.log.push("exit open");
+        }
+
+        #[action]
+        fn enter_unlocked(&mut self) {
+            self.log.push("enter unlocked");
+        }
+
+        #[action]
+        fn exit_unlocked(&mut self) {
+            self.log.push("exit unlocked");
+        }
+
+        #[action]
+        fn enter_locked(&mut self) -> bool {
+            self.log.push("enter locked");
+            self.available
+        }
+
+        #[action]
+        fn enter_secured(&mut self) {
+            self.log.push("enter secured");
+        }
+
+        #[action]
+        fn exit_secured(&mut self) {
+            self.log.push("exit secured");
+        }
+    }
+
+    impl Machine {
+        fn on_transition(&mut self, _source: &State, _target: &State) {
+            self.transitions += 1;
+        }
+    }
+
+    #[test]
+    fn vetoed_entry_rolls_back_to_source() {
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Unlock);
+        state_machine.handle(&Event::Lock);
+
+        assert_eq!(state_machine.state(), &State::open());
+        assert_eq!(state_machine.transitions, 0);
+        assert_eq!(
+            state_machine.log,
+            [
+                "enter unlocked",
+                "enter open",
+                "exit open",
+                "exit unlocked",
+                "enter secured",
+                "enter locked",
+                "exit secured",
+                "enter unlocked",
+                "enter open",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepted_entry_completes_transition() {
+        let machine = Machine {
+            available: true,
+            ..Default::default()
+        };
+        let mut state_machine = machine.uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Lock);
+
+        assert_eq!(state_machine.state(), &State::locked());
+        assert_eq!(state_machine.transitions, 1);
+        assert_eq!(
+            state_machine.log,
+            [
+                "enter unlocked",
+                "enter open",
+                "exit open",
+                "exit unlocked",
+                "enter secured",
+                "enter locked",
+            ]
+        );
+
+        state_machine.handle(&Event::Unlock);
+
+        assert_eq!(state_machine.state(), &State::open());
+        assert_eq!(state_machine.transitions, 2);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "async")]
+mod awaitable_tests {
+    use futures::executor;
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Machine {
+        available: bool,
+        log: Vec<&'static str>,
+    }
+
+    enum Event {
+        Lock,
+    }
+
+    #[state_machine(initial = "State::open()", state(derive(Debug, PartialEq, Eq)))]
+    impl Machine {
+        #[state(exit_action = "exit_open")]
+        async fn open(event: &Event) -> Response<State> {
+            match event {
+                Event::Lock => Transition(State::locked()),
+            }
+        }
+
+        #[state(entry_action = "enter_locked")]
+        fn locked() -> Response<State> {
+            Handled
+        }
+
+        #[action]
+        fn exit_open(&mut self) {
+            self.log.push("exit open");
+        }
+
+        #[action]
+        async fn enter_locked(&mut self) -> bool {
+            self.log.push("enter locked");
+            self.available
+        }
+    }
+
+    #[test]
+    fn vetoed_entry_rolls_back_to_source() {
+        executor::block_on(async {
+            let mut state_machine = Machine::default()
+                .uninitialized_state_machine()
+                .init()
+                .await;
+
+            state_machine.handle(&Event::Lock).await;
+
+            assert_eq!(state_machine.state(), &State::open());
+            assert_eq!(state_machine.log, ["exit open", "enter locked"]);
+        });
+    }
+}
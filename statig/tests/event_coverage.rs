@@ -0,0 +1,77 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Player {
+        position: u32,
+        song: String,
+    }
+
+    enum Event<'a> {
+        Play,
+        Pause { at: u32 },
+        Seek(u32),
+        Load(&'a str),
+        Eject,
+    }
+
+    #[state_machine(
+        initial = "State::stopped()",
+        state(derive(Debug, PartialEq, Eq)),
+        check_event_coverage
+    )]
+    impl Player {
+        #[state(
+            superstate = "loaded",
+            on(event = "Event::Play", target = "State::playing()")
+        )]
+        fn stopped() -> Response<State> {
+            Super
+        }
+
+        #[state(superstate = "loaded")]
+        fn playing(&mut self, event: &Event<'_>) -> Response<State> {
+            match event {
+                Event::Pause { at } => {
+                    self.position = *at;
+                    Transition(State::stopped())
+                }
+                Event::Seek(position) => {
+                    self.position = *position;
+                    Handled
+                }
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn loaded(&mut self, event: &Event<'_>) -> Response<State> {
+            if let Event::Load(song) = event {
+                self.song = song.to_string();
+            }
+            if matches!(event, Event::Load(_) | Event::Eject) {
+                return Transition(State::stopped());
+            }
+            Super
+        }
+    }
+
+    #[test]
+    fn covered_events_are_handled() {
+        let mut state_machine = Player::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Play);
+        assert_eq!(state_machine.state(), &State::playing());
+
+        state_machine.handle(&Event::Seek(10));
+        state_machine.handle(&Event::Pause { at: 12 });
+        assert_eq!(state_machine.state(), &State::stopped());
+        assert_eq!(state_machine.position, 12);
+
+        state_machine.handle(&Event::Load("song"));
+        assert_eq!(state_machine.song, "song");
+        state_machine.handle(&Event::Eject);
+        assert_eq!(state_machine.state(), &State::stopped());
+    }
+}
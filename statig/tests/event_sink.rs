@@ -0,0 +1,169 @@
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Step {
+        Handled(Event),
+        Entered(usize),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Event {
+        Connect,
+        Handshake,
+        Ready,
+    }
+
+    #[derive(Default)]
+    struct Context {
+        sink: EventSink<Event>,
+        steps: Vec<Step>,
+    }
+
+    impl AsMut<EventSink<Event>> for Context {
+        fn as_mut(&mut self) -> &mut EventSink<Event> {
+            &mut self.sink
+        }
+    }
+
+    #[derive(Default)]
+    struct Connection;
+
+    #[state_machine(initial = "State::disconnected()", state(derive(Debug, PartialEq)))]
+    impl Connection {
+        #[state(superstate = "logging")]
+        fn disconnected(context: &mut Context, event: &Event) -> Response<State> {
+            match event {
+                Event::Connect => {
+                    context.sink.post(Event::Handshake);
+                    Transition(State::connected())
+                }
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "logging", entry_action = "enter_connected")]
+        fn connected(event: &Event) -> Response<State> {
+            match event {
+                Event::Ready => Transition(State::ready()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "logging")]
+        fn ready() -> Response<State> {
+            Super
+        }
+
+        #[superstate]
+        fn logging(context: &mut Context, event: &Event) -> Response<State> {
+            context.steps.push(Step::Handled(*event));
+            if *event == Event::Handshake {
+                context.sink.post(Event::Ready);
+            }
+            Handled
+        }
+
+        #[action]
+        fn enter_connected(context: &mut Context) {
+            context.steps.push(Step::Entered(context.sink.len()));
+        }
+    }
+
+    #[test]
+    fn handle_with_sink() {
+        let mut context = Context::default();
+        let mut state_machine = Connection
+            .uninitialized_state_machine()
+            .init_with_context(&mut context);
+
+        state_machine
+            .handle_with_sink(&Event::Connect, &mut context)
+            .unwrap();
+
+        assert_eq!(state_machine.state(), &State::ready());
+        assert_eq!(
+            context.steps,
+            [Step::Entered(1), Step::Handled(Event::Handshake)]
+        );
+        assert!(context.sink.is_empty());
+    }
+
+    #[test]
+    fn posted_events_are_handled_first() {
+        let mut context = Context::default();
+        let mut state_machine = Connection
+            .uninitialized_state_machine()
+            .init_with_context(&mut context);
+
+        context.sink.post(Event::Connect);
+        state_machine
+            .handle_with_sink(&Event::Handshake, &mut context)
+            .unwrap();
+
+        assert_eq!(state_machine.state(), &State::ready());
+        assert_eq!(
+            context.steps,
+            [
+                Step::Entered(1),
+                Step::Handled(Event::Handshake),
+                Step::Handled(Event::Handshake),
+                Step::Handled(Event::Ready),
+            ]
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod limit {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Ping {
+        pings: usize,
+    }
+
+    #[state_machine(initial = "State::pinging()")]
+    impl Ping {
+        #[state]
+        fn pinging(&mut self, context: &mut EventSink<()>) -> Response<State> {
+            self.pings += 1;
+            context.post(());
+            Handled
+        }
+    }
+
+    #[test]
+    fn self_posting_handler_stops_at_limit() {
+        let mut sink = EventSink::with_limit(10);
+        let mut state_machine = Ping::default()
+            .uninitialized_state_machine()
+            .init_with_context(&mut sink);
+
+        let result = state_machine.handle_with_sink(&(), &mut sink);
+
+        assert_eq!(result, Err(SinkLimitReached { remaining: 1 }));
+        assert_eq!(state_machine.pings, 11);
+        assert_eq!(sink.len(), 1);
+    }
+
+    #[test]
+    fn event_is_handled_when_waiting_events_reach_limit() {
+        let mut sink = EventSink::with_limit(2);
+        let mut state_machine = Ping::default()
+            .uninitialized_state_machine()
+            .init_with_context(&mut sink);
+
+        sink.post(());
+        sink.post(());
+        sink.post(());
+
+        let result = state_machine.handle_with_sink(&(), &mut sink);
+
+        assert_eq!(result, Err(SinkLimitReached { remaining: 4 }));
+        assert_eq!(state_machine.pings, 3);
+    }
+}
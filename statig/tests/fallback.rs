@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Machine {
+        superstate_calls: usize,
+    }
+
+    enum Event {
+        Start,
+        Tick,
+        Reset,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Machine {
+        #[state(superstate = "active")]
+        fn idle(event: &Event) -> Response<State> {
+            match event {
+                Event::Start => Transition(State::running()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "active", fallback = "State::idle()")]
+        fn running(event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => Handled,
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "active", fallback = "Handled")]
+        fn paused(event: &Event) -> Response<State> {
+            match event {
+                Event::Start => Transition(State::running()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn active(&mut self, event: &Event) -> Response<State> {
+            self.superstate_calls += 1;
+            match event {
+                Event::Reset => Transition(State::paused()),
+                _ => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn fallback() {
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Tick);
+
+        assert_eq!(state_machine.state(), &State::idle());
+        assert_eq!(state_machine.superstate_calls, 1);
+
+        state_machine.handle(&Event::Start);
+        state_machine.handle(&Event::Tick);
+
+        assert_eq!(state_machine.state(), &State::running());
+
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::idle());
+        assert_eq!(state_machine.superstate_calls, 1);
+
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::paused());
+        assert_eq!(state_machine.superstate_calls, 2);
+
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::paused());
+        assert_eq!(state_machine.superstate_calls, 2);
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+    use statig::FfiEvents;
+
+    #[derive(Default)]
+    struct Motor {
+        speed: u32,
+    }
+
+    #[repr(u32)]
+    enum Event {
+        Start,
+        Stop,
+        SetSpeed(u32),
+    }
+
+    #[state_machine(
+        initial = "State::idle()",
+        state(derive(Debug, PartialEq, Eq)),
+        ffi_events(
+            "Event::Start",
+            "Event::Stop",
+            "Event::SetSpeed(unsafe { payload.cast::<u32>().read_unaligned() })"
+        )
+    )]
+    impl Motor {
+        #[state]
+        fn idle(event: &Event) -> Response<State> {
+            match event {
+                Event::Start => Transition(State::running()),
+                _ => Handled,
+            }
+        }
+
+        #[state]
+        fn running(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Stop => Transition(State::idle()),
+                Event::SetSpeed(speed) => {
+                    self.speed = *speed;
+                    Handled
+                }
+                Event::Start => Handled,
+            }
+        }
+    }
+
+    #[test]
+    fn tags_map_to_events_in_order() {
+        unsafe {
+            assert!(matches!(
+                Motor::try_from_tag(0, core::ptr::null()),
+                Some(Event::Start)
+            ));
+            assert!(matches!(
+                Motor::try_from_tag(1, core::ptr::null()),
+                Some(Event::Stop)
+            ));
+            assert!(Motor::try_from_tag(3, core::ptr::null()).is_none());
+        }
+    }
+
+    #[test]
+    fn handle_raw_dispatches_reconstructed_event() {
+        let mut state_machine = Motor::default().uninitialized_state_machine().init();
+        let speed: u32 = 1200;
+
+        unsafe {
+            assert!(state_machine.handle_raw(0, core::ptr::null()));
+            assert!(state_machine.handle_raw(2, (&speed as *const u32).cast()));
+        }
+
+        assert_eq!(state_machine.state(), &State::running());
+        assert_eq!(state_machine.speed, 1200);
+
+        unsafe {
+            assert!(!state_machine.handle_raw(7, core::ptr::null()));
+            assert!(state_machine.handle_raw(1, core::ptr::null()));
+        }
+
+        assert_eq!(state_machine.state(), &State::idle());
+    }
+}
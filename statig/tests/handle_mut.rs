@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Server {
+        requests: usize,
+    }
+
+    #[derive(Default)]
+    struct Request {
+        path: &'static str,
+        response: Vec<&'static str>,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Server {
+        #[state(superstate = "online")]
+        fn idle(&mut self, event: &mut Request) -> Response<State> {
+            self.requests += 1;
+            event.response.push("idle");
+            match event.path {
+                "/busy" => Transition(State::busy()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "online")]
+        fn busy(event: &Request) -> Response<State> {
+            match event.path {
+                "/done" => Transition(State::idle()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn online(event: &mut Request) -> Response<State> {
+            event.response.push("online");
+            Handled
+        }
+    }
+
+    #[test]
+    fn handlers_mutate_the_event() {
+        let mut state_machine = Server::default().uninitialized_state_machine().init();
+
+        let mut request = Request {
+            path: "/",
+            ..Default::default()
+        };
+        state_machine.handle_mut(&mut request);
+        assert_eq!(request.response, ["idle", "online"]);
+
+        let mut request = Request {
+            path: "/busy",
+            ..Default::default()
+        };
+        state_machine.handle_mut(&mut request);
+        assert_eq!(request.response, ["idle"]);
+        assert_eq!(state_machine.state(), &State::busy());
+
+        let mut request = Request {
+            path: "/",
+            ..Default::default()
+        };
+        state_machine.handle_mut(&mut request);
+        assert_eq!(request.response, ["online"]);
+        assert_eq!(state_machine.requests, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be driven with `handle_mut`")]
+    fn handle_panics_when_event_is_taken_as_mut() {
+        let mut state_machine = Server::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Request::default());
+    }
+}
+
+#[cfg(test)]
+mod shared_event {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Counter {
+        count: usize,
+    }
+
+    #[state_machine(initial = "State::counting()")]
+    impl Counter {
+        #[state]
+        fn counting(&mut self, event: &usize) -> Response<State> {
+            self.count += event;
+            Handled
+        }
+    }
+
+    #[test]
+    fn handle_mut_works_for_shared_events() {
+        let mut state_machine = Counter::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&1);
+        state_machine.handle_mut(&mut 2);
+
+        assert_eq!(state_machine.count, 3);
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Door;
+
+    enum Event {
+        Open,
+        Close,
+        Lock,
+        Unlock,
+    }
+
+    #[state_machine(
+        initial_superstate = "door_closed",
+        state(derive(Debug, PartialEq, Eq))
+    )]
+    impl Door {
+        #[state(superstate = "door_closed")]
+        fn unlocked(event: &Event) -> Response<State> {
+            match event {
+                Event::Lock => Transition(State::locked()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "door_closed")]
+        fn locked(event: &Event) -> Response<State> {
+            match event {
+                Event::Unlock => Transition(State::unlocked()),
+                Event::Open => Handled,
+                _ => Super,
+            }
+        }
+
+        #[superstate(initial = "State::locked()")]
+        fn door_closed(event: &Event) -> Response<State> {
+            match event {
+                Event::Open => Transition(State::open()),
+                _ => Super,
+            }
+        }
+
+        #[state]
+        fn open(event: &Event) -> Response<State> {
+            match event {
+                Event::Close => Transition(State::unlocked()),
+                _ => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn initial_superstate() {
+        let mut state_machine = Door.uninitialized_state_machine().init();
+
+        assert_eq!(state_machine.state(), &State::locked());
+
+        state_machine.handle(&Event::Open);
+
+        assert_eq!(state_machine.state(), &State::locked());
+
+        state_machine.handle(&Event::Unlock);
+        state_machine.handle(&Event::Open);
+
+        assert_eq!(state_machine.state(), &State::open());
+
+        state_machine.handle(&Event::Close);
+        state_machine.handle(&Event::Lock);
+
+        assert_eq!(state_machine.state(), &State::locked());
+    }
+}
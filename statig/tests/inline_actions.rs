@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Blinky {
+        led: bool,
+        log: Vec<&'static str>,
+    }
+
+    enum Event {
+        TimerElapsed,
+    }
+
+    #[state_machine(initial = "State::led_on(0)", state(derive(Debug, PartialEq, Eq)))]
+    impl Blinky {
+        #[state(
+            entry = "self.led = true; *blinks += 1",
+            exit = "self.log.push(\"exit led_on\")"
+        )]
+        fn led_on(blinks: &mut u32, event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_off(*blinks)),
+            }
+        }
+
+        #[state(entry = "self.led = false", exit_action = "exit_led_off")]
+        fn led_off(blinks: &u32, event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_on(*blinks)),
+            }
+        }
+
+        #[action]
+        fn exit_led_off(&mut self) {
+            self.log.push("exit led_off");
+        }
+    }
+
+    #[test]
+    fn inline_actions_run_on_entry_and_exit() {
+        let mut state_machine = Blinky::default().uninitialized_state_machine().init();
+
+        assert!(state_machine.led);
+        assert_eq!(state_machine.state(), &State::LedOn { blinks: 1 });
+
+        state_machine.handle(&Event::TimerElapsed);
+
+        assert!(!state_machine.led);
+        assert_eq!(state_machine.state(), &State::LedOff { blinks: 1 });
+
+        state_machine.handle(&Event::TimerElapsed);
+
+        assert!(state_machine.led);
+        assert_eq!(state_machine.state(), &State::LedOn { blinks: 2 });
+        assert_eq!(state_machine.log, ["exit led_on", "exit led_off"]);
+    }
+}
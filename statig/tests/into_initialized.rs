@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Light {
+        entered: usize,
+    }
+
+    enum Event {
+        Toggle,
+    }
+
+    #[state_machine(initial = "State::off()", state(derive(Debug, PartialEq, Eq)))]
+    impl Light {
+        #[state(entry_action = "enter_off")]
+        fn off(event: &Event) -> Response<State> {
+            match event {
+                Event::Toggle => Transition(State::on()),
+            }
+        }
+
+        #[state]
+        fn on(event: &Event) -> Response<State> {
+            match event {
+                Event::Toggle => Transition(State::off()),
+            }
+        }
+
+        #[action]
+        fn enter_off(&mut self) {
+            self.entered += 1;
+        }
+    }
+
+    #[test]
+    fn uninitialized_state_machine_is_initialized() {
+        let state_machine = Light::default().state_machine().into_initialized();
+
+        assert_eq!(state_machine.entered, 1);
+        assert_eq!(state_machine.state(), &State::off());
+    }
+
+    #[test]
+    fn initialized_state_machine_is_not_initialized_again() {
+        let mut state_machine = Light::default().state_machine();
+        state_machine.handle(&Event::Toggle);
+
+        let mut state_machine = state_machine.into_initialized_with_context(&mut ());
+
+        assert_eq!(state_machine.entered, 1);
+        assert_eq!(state_machine.state(), &State::on());
+
+        state_machine.handle(&Event::Toggle);
+
+        assert_eq!(state_machine.entered, 2);
+    }
+}
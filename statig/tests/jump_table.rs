@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    pub enum Event {
+        TimerElapsed,
+        ButtonPressed,
+    }
+
+    #[derive(Default)]
+    pub struct Blinky {
+        toggles: usize,
+    }
+
+    #[state_machine(
+        initial = "State::led_on(2)",
+        state(derive(Debug, PartialEq, Eq), jump_table)
+    )]
+    impl Blinky {
+        #[state(superstate = "blinking")]
+        fn led_on(&mut self, counter: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed if *counter > 0 => {
+                    *counter -= 1;
+                    self.toggles += 1;
+                    Transition(State::led_off(*counter))
+                }
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "blinking")]
+        fn led_off(&mut self, counter: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => {
+                    self.toggles += 1;
+                    Transition(State::led_on(*counter))
+                }
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn blinking(context: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::ButtonPressed => {
+                    *context += 1;
+                    Transition(State::not_blinking())
+                }
+                _ => Super,
+            }
+        }
+
+        #[state]
+        fn not_blinking(event: &Event) -> Response<State> {
+            match event {
+                Event::ButtonPressed => Transition(State::led_on(2)),
+                _ => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn jump_table() {
+        let mut presses = 0;
+        let mut state_machine = Blinky::default()
+            .uninitialized_state_machine()
+            .init_with_context(&mut presses);
+
+        state_machine.handle_with_context(&Event::TimerElapsed, &mut presses);
+        assert_eq!(state_machine.state(), &State::led_off(1));
+
+        state_machine.handle_with_context(&Event::TimerElapsed, &mut presses);
+        assert_eq!(state_machine.state(), &State::led_on(1));
+
+        state_machine.handle_with_context(&Event::TimerElapsed, &mut presses);
+        state_machine.handle_with_context(&Event::TimerElapsed, &mut presses);
+        assert_eq!(state_machine.state(), &State::led_on(0));
+
+        // The counter has run out, so the event is deferred to the superstate.
+        state_machine.handle_with_context(&Event::TimerElapsed, &mut presses);
+        assert_eq!(state_machine.state(), &State::led_on(0));
+        assert_eq!(state_machine.toggles, 4);
+
+        state_machine.handle_with_context(&Event::ButtonPressed, &mut presses);
+        assert_eq!(state_machine.state(), &State::not_blinking());
+        assert_eq!(presses, 1);
+
+        state_machine.handle_with_context(&Event::ButtonPressed, &mut presses);
+        assert_eq!(state_machine.state(), &State::led_on(2));
+    }
+}
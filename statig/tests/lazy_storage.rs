@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use statig::prelude::*;
+
+    static BUFFERS_BUILT: AtomicUsize = AtomicUsize::new(0);
+
+    fn make_buf() -> Vec<u8> {
+        BUFFERS_BUILT.fetch_add(1, Ordering::Relaxed);
+        Vec::with_capacity(16)
+    }
+
+    fn buffers_built() -> usize {
+        BUFFERS_BUILT.load(Ordering::Relaxed)
+    }
+
+    #[derive(Default)]
+    struct Recorder {
+        entered: usize,
+    }
+
+    enum Event {
+        Start,
+        Byte(u8),
+        Stop,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Recorder {
+        #[state]
+        fn idle(event: &Event) -> Response<State> {
+            match event {
+                Event::Start => Transition(State::recording(0)),
+                _ => Handled,
+            }
+        }
+
+        #[state(
+            lazy_storage("buf: Vec<u8> = make_buf()"),
+            entry_action = "enter_recording"
+        )]
+        fn recording(buf: &mut Vec<u8>, dropped: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::Byte(byte) if buf.len() < 2 => {
+                    buf.push(*byte);
+                    Handled
+                }
+                Event::Byte(_) => {
+                    *dropped += 1;
+                    Handled
+                }
+                Event::Stop => Transition(State::idle()),
+                Event::Start => Handled,
+            }
+        }
+
+        #[action]
+        fn enter_recording(&mut self) {
+            self.entered += 1;
+        }
+    }
+
+    #[test]
+    fn storage_is_built_on_first_use() {
+        let state = State::recording(0);
+        assert_eq!(
+            state,
+            State::Recording {
+                dropped: 0,
+                buf: None
+            }
+        );
+        assert_eq!(buffers_built(), 0);
+
+        let mut state_machine = Recorder::default().uninitialized_state_machine().init();
+        assert_eq!(buffers_built(), 0);
+
+        state_machine.handle(&Event::Start);
+        assert_eq!(buffers_built(), 0);
+        assert_eq!(state_machine.entered, 1);
+
+        state_machine.handle(&Event::Byte(1));
+        assert_eq!(buffers_built(), 1);
+        state_machine.handle(&Event::Byte(2));
+        state_machine.handle(&Event::Byte(3));
+        assert_eq!(
+            state_machine.state(),
+            &State::Recording {
+                dropped: 1,
+                buf: Some(vec![1, 2])
+            }
+        );
+        assert_eq!(buffers_built(), 1);
+
+        state_machine.handle(&Event::Stop);
+        state_machine.handle(&Event::Start);
+        assert_eq!(buffers_built(), 1);
+        assert_eq!(
+            state_machine.state(),
+            &State::Recording {
+                dropped: 0,
+                buf: None
+            }
+        );
+
+        state_machine.handle(&Event::Byte(4));
+        assert_eq!(buffers_built(), 2);
+        assert_eq!(
+            state_machine.state(),
+            &State::Recording {
+                dropped: 0,
+                buf: Some(vec![4])
+            }
+        );
+    }
+}
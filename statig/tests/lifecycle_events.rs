@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Door {
+        log: Vec<String>,
+    }
+
+    enum Event {
+        Open,
+        Close,
+        Knock,
+    }
+
+    #[state_machine(
+        initial = "State::closed(0)",
+        state(derive(Debug, PartialEq, Eq)),
+        lifecycle_events
+    )]
+    impl Door {
+        #[state(superstate = "standing")]
+        fn closed(&mut self, knocks: &mut usize, event: &Lifecycle<Event>) -> Response<State> {
+            match event {
+                Lifecycle::Enter => {
+                    self.log.push(format!("enter closed after {knocks} knocks"));
+                    // The response to the entry is ignored.
+                    Transition(State::open())
+                }
+                Lifecycle::Exit => {
+                    self.log.push("exit closed".into());
+                    Handled
+                }
+                Lifecycle::Event(Event::Knock) => {
+                    *knocks += 1;
+                    Handled
+                }
+                Lifecycle::Event(Event::Open) => Transition(State::open()),
+                Lifecycle::Event(_) => Super,
+            }
+        }
+
+        #[state(superstate = "standing")]
+        fn open(&mut self, event: &Lifecycle<Event>) -> Response<State> {
+            match event {
+                Lifecycle::Enter => {
+                    self.log.push("enter open".into());
+                    Handled
+                }
+                Lifecycle::Exit => {
+                    self.log.push("exit open".into());
+                    Handled
+                }
+                Lifecycle::Event(Event::Close) => Transition(State::closed(0)),
+                Lifecycle::Event(_) => Super,
+            }
+        }
+
+        #[superstate]
+        fn standing(&mut self, event: &Lifecycle<Event>) -> Response<State> {
+            match event {
+                Lifecycle::Enter => self.log.push("enter standing".into()),
+                Lifecycle::Exit => self.log.push("exit standing".into()),
+                Lifecycle::Event(_) => self.log.push("standing".into()),
+            }
+            Handled
+        }
+    }
+
+    #[test]
+    fn handlers_receive_lifecycle_events() {
+        let mut state_machine = Door::default().uninitialized_state_machine().init();
+
+        assert_eq!(state_machine.state(), &State::closed(0));
+        assert_eq!(
+            state_machine.log,
+            ["enter standing", "enter closed after 0 knocks"]
+        );
+
+        state_machine.handle(&Event::Knock);
+        state_machine.handle(&Event::Close);
+        state_machine.handle(&Event::Open);
+        state_machine.handle(&Event::Open);
+        state_machine.handle(&Event::Close);
+
+        assert_eq!(state_machine.state(), &State::closed(0));
+        assert_eq!(
+            state_machine.log,
+            [
+                "enter standing",
+                "enter closed after 0 knocks",
+                "standing",
+                "exit closed",
+                "enter open",
+                "standing",
+                "exit open",
+                "enter closed after 0 knocks"
+            ]
+        );
+    }
+}
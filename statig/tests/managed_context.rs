@@ -0,0 +1,146 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Door {
+        opened: usize,
+    }
+
+    #[derive(Debug, Default)]
+    struct Log {
+        entries: Vec<&'static str>,
+    }
+
+    enum Event {
+        Open,
+        Close,
+    }
+
+    #[state_machine(initial = "State::closed()", state(derive(Debug, PartialEq, Eq)))]
+    impl Door {
+        #[state(entry_action = "enter_closed")]
+        fn closed(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Open => {
+                    self.opened += 1;
+                    Transition(State::open())
+                }
+                Event::Close => Handled,
+            }
+        }
+
+        #[state]
+        fn open(context: &mut Log, event: &Event) -> Response<State> {
+            match event {
+                Event::Close => {
+                    context.entries.push("closing");
+                    Transition(State::closed())
+                }
+                Event::Open => Handled,
+            }
+        }
+
+        #[action]
+        fn enter_closed(context: &mut Log) {
+            context.entries.push("closed");
+        }
+    }
+
+    #[test]
+    fn handle_uses_owned_context() {
+        let mut state_machine = Door::default()
+            .uninitialized_state_machine()
+            .with_context(Log::default());
+
+        state_machine.handle(&Event::Open);
+        assert_eq!(state_machine.state(), &State::open());
+        assert_eq!(state_machine.opened, 1);
+
+        state_machine.handle(&Event::Close);
+        assert_eq!(state_machine.state(), &State::closed());
+
+        state_machine.context_mut().entries.push("done");
+        assert_eq!(
+            state_machine.context().entries,
+            ["closed", "closing", "closed", "done"]
+        );
+
+        let (state_machine, log) = state_machine.into_parts();
+        assert_eq!(state_machine.state(), &State::closed());
+        assert_eq!(log.entries.len(), 4);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "async")]
+mod awaitable_tests {
+    use futures::executor;
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Door;
+
+    #[derive(Debug, Default)]
+    struct Log {
+        entries: Vec<&'static str>,
+    }
+
+    enum Event {
+        Open,
+        Close,
+    }
+
+    #[state_machine(initial = "State::closed()", state(derive(Debug, PartialEq, Eq)))]
+    impl Door {
+        #[state(entry_action = "enter_closed")]
+        async fn closed(event: &Event) -> Response<State> {
+            match event {
+                Event::Open => Transition(State::open()),
+                Event::Close => Handled,
+            }
+        }
+
+        #[state]
+        async fn open(context: &mut Log, event: &Event) -> Response<State> {
+            match event {
+                Event::Close => {
+                    context.entries.push("closing");
+                    Transition(State::closed())
+                }
+                Event::Open => Handled,
+            }
+        }
+
+        #[action]
+        async fn enter_closed(context: &mut Log) {
+            context.entries.push("closed");
+        }
+    }
+
+    #[test]
+    fn handle_uses_owned_context() {
+        executor::block_on(async {
+            let mut state_machine = Door
+                .uninitialized_state_machine()
+                .with_context(Log::default())
+                .await;
+
+            state_machine.handle(&Event::Open).await;
+            assert_eq!(state_machine.state(), &State::open());
+
+            state_machine.handle(&Event::Close).await;
+            assert_eq!(state_machine.state(), &State::closed());
+
+            state_machine.context_mut().entries.push("done");
+            assert_eq!(
+                state_machine.context().entries,
+                ["closed", "closing", "closed", "done"]
+            );
+
+            let (state_machine, log) = state_machine.into_parts();
+            assert_eq!(state_machine.state(), &State::closed());
+            assert_eq!(log.entries.len(), 4);
+        });
+    }
+}
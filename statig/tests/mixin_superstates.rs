@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Handler {
+        Connected,
+        Authenticated,
+        Logging,
+        Online,
+    }
+
+    #[derive(Default)]
+    struct Session {
+        handlers: Vec<Handler>,
+    }
+
+    enum Event {
+        Ping,
+        Logout,
+        Disconnect,
+        Message,
+    }
+
+    #[state_machine(initial = "State::ready()", state(derive(Debug, PartialEq, Eq)))]
+    impl Session {
+        #[state(superstate("online", "connected", "authenticated"))]
+        fn ready(event: &Event) -> Response<State> {
+            match event {
+                Event::Message => Handled,
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "online")]
+        fn offline() -> Response<State> {
+            Super
+        }
+
+        #[superstate]
+        fn online(&mut self) -> Response<State> {
+            self.handlers.push(Handler::Online);
+            Super
+        }
+
+        #[superstate(superstate = "logging")]
+        fn connected(&mut self, event: &Event) -> Response<State> {
+            self.handlers.push(Handler::Connected);
+            match event {
+                Event::Disconnect => Transition(State::offline()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn authenticated(&mut self, event: &Event) -> Response<State> {
+            self.handlers.push(Handler::Authenticated);
+            match event {
+                Event::Logout | Event::Disconnect => Transition(State::offline()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn logging(&mut self) -> Response<State> {
+            self.handlers.push(Handler::Logging);
+            Super
+        }
+    }
+
+    #[test]
+    fn mixin_superstates() {
+        let mut state_machine = Session::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Message);
+
+        assert!(state_machine.handlers.is_empty());
+
+        state_machine.handle(&Event::Ping);
+
+        assert_eq!(
+            state_machine.handlers,
+            [Handler::Connected, Handler::Authenticated, Handler::Online]
+        );
+        assert_eq!(state_machine.state(), &State::ready());
+
+        state_machine.handle(&Event::Logout);
+
+        assert_eq!(
+            state_machine.handlers[3..],
+            [Handler::Connected, Handler::Authenticated]
+        );
+        assert_eq!(state_machine.state(), &State::offline());
+
+        state_machine = Session::default().uninitialized_state_machine().init();
+        state_machine.handle(&Event::Disconnect);
+
+        assert_eq!(state_machine.handlers, [Handler::Connected]);
+        assert_eq!(state_machine.state(), &State::offline());
+    }
+}
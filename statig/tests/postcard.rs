@@ -0,0 +1,97 @@
+#[test]
+#[cfg(feature = "serde")]
+fn postcard_round_trip() {
+    use serde::{Deserialize, Serialize};
+    use statig::prelude::*;
+
+    #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Blinky {
+        led: bool,
+        toggles: u16,
+    }
+
+    pub enum Event {
+        TimerElapsed,
+        ButtonPressed,
+    }
+
+    #[state_machine(
+        initial = "State::led_on()",
+        state(derive(Debug, Serialize, Deserialize, Clone, PartialEq))
+    )]
+    impl Blinky {
+        #[state(superstate = "blinking", entry_action = "enter_led_on")]
+        fn led_on(event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_off()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "blinking", entry_action = "enter_led_off")]
+        fn led_off(event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_on()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn blinking(event: &Event) -> Response<State> {
+            match event {
+                Event::ButtonPressed => Transition(State::not_blinking(0)),
+                _ => Super,
+            }
+        }
+
+        #[state]
+        fn not_blinking(presses: &mut u8, event: &Event) -> Response<State> {
+            match event {
+                Event::ButtonPressed => {
+                    *presses += 1;
+                    Handled
+                }
+                Event::TimerElapsed => Handled,
+            }
+        }
+
+        #[action]
+        fn enter_led_on(&mut self) {
+            self.led = true;
+            self.toggles += 1;
+        }
+
+        #[action]
+        fn enter_led_off(&mut self) {
+            self.led = false;
+            self.toggles += 1;
+        }
+    }
+
+    let state_machine = Blinky::default().uninitialized_state_machine();
+
+    let ser = postcard::to_allocvec(&state_machine).unwrap();
+    let de: statig::blocking::UninitializedStateMachine<Blinky> =
+        postcard::from_bytes(&ser).unwrap();
+
+    assert_eq!(de, state_machine);
+
+    let mut state_machine = state_machine.init();
+    state_machine.handle(&Event::TimerElapsed);
+    state_machine.handle(&Event::ButtonPressed);
+    state_machine.handle(&Event::ButtonPressed);
+
+    let ser = postcard::to_allocvec(&state_machine).unwrap();
+    let de: statig::blocking::UninitializedStateMachine<Blinky> =
+        postcard::from_bytes(&ser).unwrap();
+    let de = de.init();
+
+    assert_eq!(de, state_machine);
+    assert_eq!(de.state(), &State::not_blinking(1));
+    assert_eq!(de.toggles, 2);
+
+    let ser = postcard::to_allocvec(state_machine.state()).unwrap();
+    let de: State = postcard::from_bytes(&ser).unwrap();
+
+    assert_eq!(&de, state_machine.state());
+}
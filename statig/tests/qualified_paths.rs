@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use statig::blocking::IntoStateMachineExt as _;
+    use statig::state_machine;
+
+    /// A type of our own with the same name as the one of statig, the generated
+    /// code must not pick it up.
+    #[allow(dead_code)]
+    struct Response;
+
+    #[derive(Default)]
+    struct Counter {
+        dispatched: usize,
+        count: u32,
+    }
+
+    #[state_machine(
+        initial = "State::counting()",
+        state(derive(Debug, PartialEq, Eq)),
+        on_dispatch = "Self::on_dispatch"
+    )]
+    impl Counter {
+        #[state(superstate = "active", fallback = "Handled")]
+        fn counting(&mut self, event: &u32) -> statig::Response<State> {
+            match event {
+                0 => statig::Response::Super,
+                value => {
+                    self.count += value;
+                    statig::Response::Handled
+                }
+            }
+        }
+
+        #[superstate]
+        fn active() -> statig::Response<State> {
+            statig::Response::Super
+        }
+    }
+
+    impl Counter {
+        fn on_dispatch(&mut self, _: statig::StateOrSuperstate<'_, '_, Self>, _: &u32) {
+            self.dispatched += 1;
+        }
+    }
+
+    #[test]
+    fn generated_code_uses_qualified_paths() {
+        let mut state_machine = Counter::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&2);
+        state_machine.handle(&0);
+        state_machine.handle(&3);
+
+        assert_eq!(state_machine.count, 5);
+        assert_eq!(state_machine.dispatched, 3);
+        assert_eq!(state_machine.state(), &State::counting());
+    }
+}
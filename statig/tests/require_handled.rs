@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Door;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Open,
+        Close,
+        Knock,
+    }
+
+    #[state_machine(
+        initial = "State::closed()",
+        state(derive(Debug, PartialEq, Eq)),
+        require_handled
+    )]
+    impl Door {
+        #[state(superstate = "door")]
+        fn closed(event: &Event) -> Response<State> {
+            match event {
+                Event::Open => Transition(State::open()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "door")]
+        fn open(event: &Event) -> Response<State> {
+            match event {
+                Event::Close => Transition(State::closed()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn door(event: &Event) -> Response<State> {
+            match event {
+                Event::Open | Event::Close => Handled,
+                Event::Knock => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn try_handle_returns_unhandled_event() {
+        let mut state_machine = Door.uninitialized_state_machine().init();
+
+        assert!(state_machine.try_handle(&Event::Close).is_ok());
+        assert!(state_machine.try_handle(&Event::Open).is_ok());
+        assert_eq!(state_machine.state(), &State::open());
+
+        let unhandled = state_machine.try_handle(&Event::Knock).unwrap_err();
+        assert_eq!(unhandled.event, &Event::Knock);
+        assert_eq!(state_machine.state(), &State::open());
+    }
+
+    #[test]
+    fn handle_ignores_unhandled_event() {
+        let mut state_machine = Door.uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Open);
+        state_machine.handle(&Event::Knock);
+
+        assert_eq!(state_machine.state(), &State::open());
+    }
+
+    #[test]
+    fn lazy_state_machine_initializes_on_try_handle() {
+        let mut state_machine = Door.state_machine();
+
+        assert!(state_machine.try_handle(&Event::Knock).is_err());
+        assert!(state_machine.try_handle(&Event::Open).is_ok());
+        assert_eq!(state_machine.state(), &State::open());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "async")]
+mod awaitable_tests {
+    use futures::executor;
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Door;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Open,
+        Knock,
+    }
+
+    #[state_machine(
+        initial = "State::closed()",
+        state(derive(Debug, PartialEq, Eq)),
+        require_handled
+    )]
+    impl Door {
+        #[state]
+        async fn closed(event: &Event) -> Response<State> {
+            match event {
+                Event::Open => Handled,
+                Event::Knock => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn try_handle_returns_unhandled_event() {
+        executor::block_on(async {
+            let mut state_machine = Door.uninitialized_state_machine().init().await;
+
+            assert!(state_machine.try_handle(&Event::Open).await.is_ok());
+
+            let unhandled = state_machine.try_handle(&Event::Knock).await.unwrap_err();
+            assert_eq!(unhandled.event, &Event::Knock);
+        });
+    }
+}
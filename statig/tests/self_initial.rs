@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Counter;
+
+    enum Event {
+        Pause,
+        Resume,
+    }
+
+    impl Counter {
+        const START: State = State::counting(10);
+
+        const fn resume_state() -> State {
+            State::counting(0)
+        }
+    }
+
+    #[state_machine(initial = "Self::START", state(derive(Debug, PartialEq, Eq)))]
+    impl Counter {
+        #[state(superstate = "running")]
+        fn counting(count: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::Pause => Transition(State::paused()),
+                _ => {
+                    *count += 1;
+                    Super
+                }
+            }
+        }
+
+        #[state(superstate = "running")]
+        fn paused(event: &Event) -> Response<State> {
+            match event {
+                Event::Resume => TransitionToSuperstate,
+                _ => Super,
+            }
+        }
+
+        #[superstate(initial = "Self::resume_state()")]
+        fn running() -> Response<State> {
+            Handled
+        }
+    }
+
+    #[test]
+    fn initial_state_from_associated_const() {
+        let mut state_machine = Counter.uninitialized_state_machine().init();
+        assert_eq!(state_machine.state(), &State::counting(10));
+
+        state_machine.handle(&Event::Resume);
+        assert_eq!(state_machine.state(), &State::counting(11));
+    }
+
+    #[test]
+    fn superstate_initial_state_from_associated_fn() {
+        let mut state_machine = Counter.uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Pause);
+        state_machine.handle(&Event::Resume);
+        assert_eq!(state_machine.state(), &State::counting(0));
+    }
+}
@@ -96,3 +96,150 @@ fn serialize_deserialize() {
 
     assert_eq!(de, state_machine_not_blinking);
 }
+
+#[test]
+#[cfg(feature = "serde")]
+fn serialize_deserialize_state() {
+    use serde::{Deserialize, Serialize};
+    use statig::prelude::*;
+
+    // The shared storage is not serializable.
+    pub struct Blinky {
+        led: std::cell::Cell<bool>,
+    }
+
+    pub enum Event {
+        TimerElapsed,
+    }
+
+    #[state_machine(
+        initial = "State::led_on()",
+        state(derive(Debug, Serialize, Deserialize, PartialEq))
+    )]
+    impl Blinky {
+        #[state(entry_action = "enter_led_on")]
+        fn led_on(event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_off(1)),
+            }
+        }
+
+        #[action]
+        fn enter_led_on(&mut self) {
+            self.led.set(true);
+        }
+
+        #[state(entry_action = "enter_led_off")]
+        fn led_off(counter: &mut usize, event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => {
+                    *counter += 1;
+                    Handled
+                }
+            }
+        }
+
+        #[action]
+        fn enter_led_off(&mut self) {
+            self.led.set(false);
+        }
+    }
+
+    let mut state_machine = Blinky {
+        led: Default::default(),
+    }
+    .uninitialized_state_machine()
+    .init();
+    state_machine.handle(&Event::TimerElapsed);
+    state_machine.handle(&Event::TimerElapsed);
+
+    let ser = serde_json::to_vec(&state_machine.state()).unwrap();
+    let mut manual = Vec::new();
+    state_machine
+        .serialize_state(&mut serde_json::Serializer::new(&mut manual))
+        .unwrap();
+
+    assert_eq!(ser, manual);
+
+    let mut de = Blinky {
+        led: std::cell::Cell::new(true),
+    }
+    .uninitialized_state_machine();
+    de.deserialize_state(&mut serde_json::Deserializer::from_slice(&ser))
+        .unwrap();
+    let de = de.init();
+
+    assert_eq!(de.state(), &State::led_off(2));
+    assert!(!de.led.get());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn deserialize_non_self_describing() {
+    use bincode::Options;
+    use serde::de::value::MapDeserializer;
+    use serde::{Deserialize, Serialize};
+    use statig::prelude::*;
+
+    #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Counter {
+        count: u32,
+    }
+
+    pub enum Event {
+        Tick,
+    }
+
+    #[state_machine(
+        initial = "State::idle()",
+        state(derive(Debug, Serialize, Deserialize, Clone, PartialEq))
+    )]
+    impl Counter {
+        #[state]
+        fn idle(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => {
+                    self.count += 1;
+                    Transition(State::counting(300))
+                }
+            }
+        }
+
+        #[state]
+        fn counting(&mut self, remaining: &mut u32, event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => {
+                    self.count += 1;
+                    *remaining -= 1;
+                    Handled
+                }
+            }
+        }
+    }
+
+    let mut state_machine = Counter::default().uninitialized_state_machine().init();
+    state_machine.handle(&Event::Tick);
+    state_machine.handle(&Event::Tick);
+
+    // Variable length integers, as used by compact formats such as `postcard`.
+    let options = bincode::DefaultOptions::new();
+    let ser = options.serialize(&state_machine).unwrap();
+    let de: statig::blocking::UninitializedStateMachine<Counter> =
+        options.deserialize(&ser).unwrap();
+    let de = de.init();
+
+    assert_eq!(de, state_machine);
+    assert_eq!(de.state(), &State::counting(299));
+
+    // Fields that are identified by their index instead of their name.
+    let entries = vec![
+        (0u64, serde_json::to_value(Counter { count: 2 }).unwrap()),
+        (1u64, serde_json::to_value(State::counting(299)).unwrap()),
+    ];
+    let deserializer = MapDeserializer::<_, serde_json::Error>::new(entries.into_iter());
+    let de =
+        statig::blocking::UninitializedStateMachine::<Counter>::deserialize(deserializer).unwrap();
+    let de = de.init();
+
+    assert_eq!(de, state_machine);
+}
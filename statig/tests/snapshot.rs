@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Editor {
+        text: String,
+        history: RefCell<Vec<(String, State)>>,
+    }
+
+    enum Event {
+        Type(&'static str),
+        Save,
+    }
+
+    #[state_machine(
+        initial = "State::clean()",
+        state(derive(Clone, Debug, PartialEq, Eq)),
+        on_snapshot = "Self::on_snapshot"
+    )]
+    impl Editor {
+        #[state]
+        fn clean(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Type(text) => {
+                    self.text.push_str(text);
+                    Transition(State::dirty())
+                }
+                Event::Save => Handled,
+            }
+        }
+
+        #[state]
+        fn dirty(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Type(text) => {
+                    self.text.push_str(text);
+                    Handled
+                }
+                Event::Save => Transition(State::clean()),
+            }
+        }
+    }
+
+    impl Editor {
+        fn on_snapshot(&self, state: &State) {
+            self.history
+                .borrow_mut()
+                .push((self.text.clone(), state.clone()));
+        }
+    }
+
+    #[test]
+    fn snapshot_before_transition() {
+        let mut state_machine = Editor::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Type("a"));
+        state_machine.handle(&Event::Type("b"));
+        state_machine.handle(&Event::Save);
+        state_machine.handle(&Event::Save);
+
+        assert_eq!(
+            *state_machine.history.borrow(),
+            [
+                (String::from("a"), State::clean()),
+                (String::from("ab"), State::dirty()),
+            ]
+        );
+    }
+
+    mod veto {
+        use std::cell::RefCell;
+
+        use statig::prelude::*;
+
+        #[derive(Default)]
+        struct Door {
+            snapshots: RefCell<Vec<State>>,
+            transitions: usize,
+        }
+
+        #[state_machine(
+            initial = "State::open()",
+            state(derive(Clone, Debug, PartialEq, Eq)),
+            on_snapshot = "Self::on_snapshot",
+            on_transition = "Self::on_transition"
+        )]
+        impl Door {
+            #[state]
+            fn open(event: &bool) -> Response<State> {
+                match event {
+                    true => Transition(State::locked()),
+                    false => Handled,
+                }
+            }
+
+            #[state(entry_action = "enter_locked")]
+            fn locked() -> Response<State> {
+                Handled
+            }
+
+            #[action]
+            fn enter_locked() -> bool {
+                false
+            }
+        }
+
+        impl Door {
+            fn on_snapshot(&self, state: &State) {
+                self.snapshots.borrow_mut().push(state.clone());
+            }
+
+            fn on_transition(&mut self, _: &State, _: &State) {
+                self.transitions += 1;
+            }
+        }
+
+        #[test]
+        fn snapshot_before_vetoed_transition() {
+            let mut state_machine = Door::default().uninitialized_state_machine().init();
+
+            state_machine.handle(&true);
+
+            // The snapshot is taken before the entry is vetoed, and the state machine
+            // stays in the state of the snapshot.
+            assert_eq!(*state_machine.snapshots.borrow(), [State::open()]);
+            assert_eq!(state_machine.state(), &State::open());
+            assert_eq!(state_machine.transitions, 0);
+        }
+    }
+}
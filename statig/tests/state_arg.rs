@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Machine {
+        log: Vec<String>,
+    }
+
+    enum Event {
+        Tick,
+        Next,
+    }
+
+    #[state_machine(initial = "State::waiting(3)", state(derive(Debug, PartialEq, Eq)))]
+    impl Machine {
+        #[state]
+        fn waiting(&mut self, state: &State, remaining: &u32, event: &Event) -> Response<State> {
+            self.log.push(format!("{state:?}"));
+            match event {
+                Event::Tick if *remaining > 0 => Transition(State::waiting(remaining - 1)),
+                Event::Tick => Handled,
+                Event::Next => Transition(State::done()),
+            }
+        }
+
+        #[state]
+        fn done(&mut self, state: &State) -> Response<State> {
+            self.log.push(format!("{state:?}"));
+            Handled
+        }
+    }
+
+    #[test]
+    fn state_arg() {
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Tick);
+        state_machine.handle(&Event::Tick);
+        state_machine.handle(&Event::Next);
+        state_machine.handle(&Event::Next);
+
+        assert_eq!(state_machine.state(), &State::done());
+        assert_eq!(
+            state_machine.log,
+            [
+                "Waiting { remaining: 3 }",
+                "Waiting { remaining: 2 }",
+                "Waiting { remaining: 1 }",
+                "Done",
+            ]
+        );
+    }
+
+    mod jump_table {
+        use statig::prelude::*;
+
+        #[derive(Default)]
+        pub struct Counter;
+
+        #[state_machine(initial = "State::counting(0)", state(derive(Debug), jump_table))]
+        impl Counter {
+            #[state]
+            fn counting(state: &State, count: &u32) -> Response<State> {
+                match state {
+                    State::Counting { .. } if *count < 2 => Transition(State::counting(count + 1)),
+                    _ => Handled,
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn state_arg_jump_table() {
+        let mut state_machine = jump_table::Counter.uninitialized_state_machine().init();
+
+        state_machine.handle(&());
+        state_machine.handle(&());
+        state_machine.handle(&());
+
+        assert!(matches!(
+            state_machine.state(),
+            jump_table::State::Counting { count: 2 }
+        ));
+    }
+}
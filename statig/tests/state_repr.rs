@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Counter;
+
+    enum Event {
+        Tick,
+        Reset,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug), repr = "u8"))]
+    impl Counter {
+        #[state]
+        fn idle(event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => Transition(State::counting(1)),
+                Event::Reset => Handled,
+            }
+        }
+
+        #[action]
+        fn enter_stopped() {}
+
+        #[state]
+        fn counting(count: &mut u32, event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => {
+                    *count += 1;
+                    Handled
+                }
+                Event::Reset => Transition(State::idle()),
+            }
+        }
+
+        #[state(entry_action = "enter_stopped")]
+        fn stopped() -> Response<State> {
+            Handled
+        }
+    }
+
+    #[test]
+    fn discriminant() {
+        const IDLE: u8 = State::idle().discriminant();
+
+        assert_eq!(IDLE, 0);
+        assert_eq!(State::counting(7).discriminant(), 1);
+        assert_eq!(State::stopped().discriminant(), 2);
+
+        let mut state_machine = Counter.uninitialized_state_machine().init();
+
+        assert_eq!(state_machine.state().discriminant(), 0);
+
+        state_machine.handle(&Event::Tick);
+        state_machine.handle(&Event::Tick);
+
+        assert_eq!(state_machine.state().discriminant(), 1);
+
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state().discriminant(), 0);
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    pub struct Blinky;
+
+    pub enum Event {
+        TimerElapsed,
+        ButtonPressed,
+        Fault,
+    }
+
+    #[state_machine(initial = "State::led_on()", state(derive(Debug, Clone)))]
+    impl Blinky {
+        #[state(superstate = "blinking")]
+        fn led_on(event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_off()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "blinking")]
+        fn led_off(event: &Event) -> Response<State> {
+            match event {
+                Event::TimerElapsed => Transition(State::led_on()),
+                _ => Super,
+            }
+        }
+
+        #[superstate(superstate = "operational")]
+        fn blinking(event: &Event) -> Response<State> {
+            match event {
+                Event::ButtonPressed => Transition(State::not_blinking()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "operational")]
+        fn not_blinking(event: &Event) -> Response<State> {
+            match event {
+                Event::ButtonPressed => Transition(State::led_on()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn operational(event: &Event) -> Response<State> {
+            match event {
+                Event::Fault => Transition(State::faulted()),
+                _ => Super,
+            }
+        }
+
+        #[state]
+        fn faulted() -> Response<State> {
+            Handled
+        }
+    }
+
+    #[test]
+    fn superstate_depth() {
+        let mut state_machine = Blinky.uninitialized_state_machine().init();
+
+        assert!(matches!(state_machine.state(), State::LedOn {}));
+        assert_eq!(state_machine.superstate_depth(), 2);
+        assert!(state_machine.is_nested());
+
+        state_machine.handle(&Event::TimerElapsed);
+
+        assert!(matches!(state_machine.state(), State::LedOff {}));
+        assert_eq!(state_machine.superstate_depth(), 2);
+        assert!(state_machine.is_nested());
+
+        state_machine.handle(&Event::ButtonPressed);
+
+        assert!(matches!(state_machine.state(), State::NotBlinking {}));
+        assert_eq!(state_machine.superstate_depth(), 1);
+        assert!(state_machine.is_nested());
+
+        state_machine.handle(&Event::Fault);
+
+        assert!(matches!(state_machine.state(), State::Faulted {}));
+        assert_eq!(state_machine.superstate_depth(), 0);
+        assert!(!state_machine.is_nested());
+    }
+
+    #[test]
+    fn superstate_depth_lazy() {
+        let mut state_machine = Blinky.state_machine();
+
+        assert_eq!(state_machine.superstate_depth(), 2);
+
+        state_machine.handle(&Event::Fault);
+
+        assert_eq!(state_machine.superstate_depth(), 0);
+        assert!(!state_machine.is_nested());
+    }
+
+    #[test]
+    fn superstate_name() {
+        const NAME: Option<&str> = State::led_on().superstate_name();
+
+        assert_eq!(NAME, Some("blinking"));
+        assert_eq!(State::not_blinking().superstate_name(), Some("operational"));
+        assert_eq!(State::faulted().superstate_name(), None);
+    }
+
+    #[test]
+    fn superstate_children() {
+        assert_eq!(Superstate::Blinking {}.children(), ["led_on", "led_off"]);
+        assert_eq!(
+            Superstate::Operational {}.children(),
+            ["blinking", "not_blinking"]
+        );
+    }
+}
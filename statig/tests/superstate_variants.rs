@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use statig::blocking::Superstate as _;
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    pub struct Player;
+
+    pub enum Event {
+        Stop,
+        Eject,
+    }
+
+    #[state_machine(
+        initial = "State::playing()",
+        state(derive(Debug, PartialEq, Eq)),
+        superstate(derive(Debug, PartialEq, Eq))
+    )]
+    impl Player {
+        #[state(superstate = "active")]
+        fn playing() -> Response<State> {
+            Super
+        }
+
+        #[state(superstate = "active")]
+        fn paused() -> Response<State> {
+            Super
+        }
+
+        #[state(superstate = "loaded")]
+        fn stopped() -> Response<State> {
+            Super
+        }
+
+        #[superstate(superstate = "loaded")]
+        fn active(event: &Event) -> Response<State> {
+            match event {
+                Event::Stop => Transition(State::stopped()),
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn loaded(event: &Event) -> Response<State> {
+            match event {
+                Event::Eject => Handled,
+                _ => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn variants_in_declaration_order() {
+        assert_eq!(Superstate::COUNT, 2);
+        assert_eq!(
+            Superstate::VARIANTS,
+            [Superstate::Active {}, Superstate::Loaded {}]
+        );
+    }
+
+    #[test]
+    fn only_loaded_handles_eject() {
+        let mut handled = Vec::new();
+        for mut superstate in Superstate::VARIANTS {
+            let response = superstate.call_handler(&mut Player, &Event::Eject, &mut ());
+            if matches!(response, Response::Handled) {
+                handled.push(superstate);
+            }
+        }
+        assert_eq!(handled, [Superstate::Loaded {}]);
+    }
+
+    #[test]
+    fn only_active_handles_stop() {
+        let mut handled = Vec::new();
+        for mut superstate in Superstate::VARIANTS {
+            let response = superstate.call_handler(&mut Player, &Event::Stop, &mut ());
+            if matches!(response, Response::Transition(State::Stopped {})) {
+                handled.push(superstate);
+            }
+        }
+        assert_eq!(handled, [Superstate::Active {}]);
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    pub enum Event {
+        Start,
+        Finish,
+    }
+
+    #[derive(Default)]
+    pub struct Job {
+        ignored: usize,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Job {
+        #[state(superstate = "active")]
+        fn idle(event: &Event) -> Response<State> {
+            match event {
+                Event::Start => Transition(State::running()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "active")]
+        fn running(event: &Event) -> Response<State> {
+            match event {
+                Event::Start => Handled,
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn active(event: &Event) -> Response<State> {
+            match event {
+                Event::Finish => Transition(State::done()),
+                _ => Super,
+            }
+        }
+
+        #[state(terminal)]
+        fn done(&mut self) {
+            self.ignored += 1;
+        }
+    }
+
+    #[test]
+    fn terminal() {
+        let mut state_machine = Job::default().uninitialized_state_machine().init();
+
+        assert!(!state_machine.is_terminal());
+
+        state_machine.handle(&Event::Start);
+        state_machine.handle(&Event::Finish);
+
+        assert_eq!(state_machine.state(), &State::done());
+        assert!(state_machine.is_terminal());
+
+        // Further events are ignored.
+        state_machine.handle(&Event::Start);
+        state_machine.handle(&Event::Finish);
+
+        assert_eq!(state_machine.state(), &State::done());
+        assert!(state_machine.is_terminal());
+        assert_eq!(state_machine.ignored, 2);
+    }
+
+    #[test]
+    fn is_final_and_is_done() {
+        assert!(!State::idle().is_final());
+        assert!(!State::running().is_final());
+        assert!(State::done().is_final());
+
+        let mut state_machine = Job::default().uninitialized_state_machine().init();
+        let mut events = [Event::Start, Event::Start, Event::Finish].into_iter();
+
+        while !state_machine.is_done() {
+            state_machine.handle(&events.next().unwrap());
+        }
+
+        assert_eq!(state_machine.state(), &State::done());
+        assert!(events.next().is_none());
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::time::Duration;
+
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Worker;
+
+    enum Event {
+        Work,
+        Skip,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Worker {
+        #[state]
+        fn idle(event: &Event) -> Response<State> {
+            match event {
+                Event::Work => Transition(State::busy()),
+                Event::Skip => Handled,
+            }
+        }
+
+        #[state(entry_action = "enter_busy")]
+        fn busy() -> Response<State> {
+            Handled
+        }
+
+        #[action]
+        fn enter_busy(&mut self) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn elapsed_time_includes_actions() {
+        let mut state_machine = Worker.uninitialized_state_machine().init();
+
+        let elapsed = state_machine.handle_timed(&Event::Skip);
+        assert!(elapsed < Duration::from_millis(5));
+
+        let elapsed = state_machine.handle_timed(&Event::Work);
+        assert!(elapsed >= Duration::from_millis(5));
+        assert_eq!(state_machine.state(), &State::busy());
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+#[cfg(feature = "async")]
+mod tests {
+    use core::future::Future;
+    use core::pin::Pin;
+    use core::task::{Context, Poll};
+
+    use futures::executor;
+    use statig::prelude::*;
+
+    /// A timeout that completes the second time it is polled.
+    struct Expire {
+        polled: bool,
+    }
+
+    impl Future for Expire {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.polled {
+                Poll::Ready(())
+            } else {
+                self.polled = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    /// A future that is pending once before completing.
+    struct Yield {
+        yielded: bool,
+    }
+
+    impl Future for Yield {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.yielded {
+                Poll::Ready(())
+            } else {
+                self.yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Machine {
+        entered: bool,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    impl Machine {
+        #[state]
+        async fn idle() -> Response<State> {
+            Transition(State::busy())
+        }
+
+        #[state(entry_action = "enter_busy")]
+        async fn busy() -> Response<State> {
+            Handled
+        }
+
+        #[action]
+        async fn enter_busy(&mut self) {
+            for _ in 0..3 {
+                Yield { yielded: false }.await;
+            }
+            self.entered = true;
+        }
+    }
+
+    #[test]
+    fn transition_completes_after_response() {
+        executor::block_on(async {
+            let mut state_machine = Machine::default()
+                .uninitialized_state_machine()
+                .init()
+                .await;
+
+            let result = state_machine
+                .handle_with_timeout(&(), Expire { polled: false })
+                .await;
+
+            assert_eq!(result, Ok(()));
+            assert_eq!(state_machine.state(), &State::busy());
+            assert!(state_machine.entered);
+        });
+    }
+}
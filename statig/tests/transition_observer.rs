@@ -0,0 +1,93 @@
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Machine {
+        transitions: usize,
+    }
+
+    enum Event {
+        Toggle,
+        Stay,
+    }
+
+    #[state_machine(
+        initial = "State::off()",
+        state(derive(Debug, Clone, PartialEq, Eq)),
+        on_transition = "Self::on_transition"
+    )]
+    impl Machine {
+        #[state]
+        fn off(event: &Event) -> Response<State> {
+            match event {
+                Event::Toggle => Transition(State::on()),
+                Event::Stay => Handled,
+            }
+        }
+
+        #[state]
+        fn on(event: &Event) -> Response<State> {
+            match event {
+                Event::Toggle => Transition(State::off()),
+                Event::Stay => Handled,
+            }
+        }
+    }
+
+    impl Machine {
+        fn on_transition(&mut self, _: &State, _: &State) {
+            self.transitions += 1;
+        }
+    }
+
+    #[test]
+    fn observer_is_called_next_to_on_transition() {
+        let observed = Arc::new(Mutex::new(Vec::new()));
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        let log = observed.clone();
+        state_machine.set_transition_observer(Box::new(move |source, target| {
+            log.lock().unwrap().push((source.clone(), target.clone()));
+        }));
+
+        state_machine.handle(&Event::Toggle);
+        state_machine.handle(&Event::Stay);
+        state_machine.handle(&Event::Toggle);
+
+        assert_eq!(state_machine.transitions, 2);
+        assert_eq!(
+            *observed.lock().unwrap(),
+            [(State::off(), State::on()), (State::on(), State::off())]
+        );
+
+        assert!(state_machine.take_transition_observer().is_some());
+        state_machine.handle(&Event::Toggle);
+
+        assert_eq!(state_machine.transitions, 3);
+        assert_eq!(observed.lock().unwrap().len(), 2);
+    }
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn state_machine_with_observer_is_send_and_sync() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut state_machine = Machine::default().uninitialized_state_machine().init();
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let observed = count.clone();
+        state_machine.set_transition_observer(Box::new(move |_, _| {
+            observed.fetch_add(1, Ordering::Relaxed);
+        }));
+
+        state_machine.handle(&Event::Toggle);
+
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+        assert_send_sync(&state_machine);
+    }
+}
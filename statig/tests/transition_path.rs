@@ -0,0 +1,69 @@
+#[cfg(test)]
+#[cfg(feature = "testing")]
+mod tests {
+    use statig::prelude::*;
+    use statig::testing::compute_transition_path;
+
+    #[derive(Default)]
+    pub struct Blinky;
+
+    #[state_machine(initial = "State::led_on()", state(derive(Debug, Clone)))]
+    impl Blinky {
+        #[state(superstate = "blinking")]
+        fn led_on() -> Response<State> {
+            Handled
+        }
+
+        #[state(superstate = "blinking")]
+        fn led_off() -> Response<State> {
+            Handled
+        }
+
+        #[superstate(superstate = "operational")]
+        fn blinking() -> Response<State> {
+            Handled
+        }
+
+        #[state(superstate = "operational")]
+        fn not_blinking() -> Response<State> {
+            Handled
+        }
+
+        #[superstate]
+        fn operational() -> Response<State> {
+            Handled
+        }
+
+        #[state]
+        fn faulted() -> Response<State> {
+            Handled
+        }
+    }
+
+    fn path(from: State, to: State) -> (usize, usize) {
+        compute_transition_path::<Blinky>(&from, &to)
+    }
+
+    #[test]
+    fn self_transition() {
+        assert_eq!(path(State::led_on(), State::led_on()), (1, 1));
+        assert_eq!(path(State::faulted(), State::faulted()), (1, 1));
+    }
+
+    #[test]
+    fn siblings() {
+        assert_eq!(path(State::led_on(), State::led_off()), (1, 1));
+    }
+
+    #[test]
+    fn different_depths() {
+        assert_eq!(path(State::led_on(), State::not_blinking()), (2, 1));
+        assert_eq!(path(State::not_blinking(), State::led_off()), (1, 2));
+    }
+
+    #[test]
+    fn no_common_ancestor() {
+        assert_eq!(path(State::led_on(), State::faulted()), (3, 1));
+        assert_eq!(path(State::faulted(), State::not_blinking()), (1, 2));
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(PartialEq, Eq)]
+    enum Event {
+        Start,
+        Pause,
+        Resume,
+        Stop,
+        Tick,
+    }
+
+    #[derive(Default)]
+    struct Player {
+        ticks: usize,
+    }
+
+    #[state_machine(initial = "State::idle()", state(derive(Debug, PartialEq, Eq)))]
+    #[transitions(
+        Idle: Event::Start => Playing,
+        Playing: Event::Pause => Paused, Event::Stop => Idle,
+        Paused: Event::Resume => Playing, Event::Stop => Idle,
+    )]
+    impl Player {
+        #[state]
+        fn playing(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Tick => {
+                    self.ticks += 1;
+                    Handled
+                }
+                _ => Super,
+            }
+        }
+    }
+
+    #[test]
+    fn table_transitions() {
+        let mut state_machine = Player::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Pause);
+        assert_eq!(state_machine.state(), &State::idle());
+
+        state_machine.handle(&Event::Start);
+        assert_eq!(state_machine.state(), &State::playing());
+
+        state_machine.handle(&Event::Tick);
+        assert_eq!(state_machine.ticks, 1);
+
+        state_machine.handle(&Event::Pause);
+        assert_eq!(state_machine.state(), &State::paused());
+
+        state_machine.handle(&Event::Tick);
+        assert_eq!(state_machine.ticks, 1);
+
+        state_machine.handle(&Event::Resume);
+        state_machine.handle(&Event::Stop);
+        assert_eq!(state_machine.state(), &State::idle());
+    }
+
+    mod table_only {
+        use statig::prelude::*;
+
+        #[derive(PartialEq, Eq)]
+        pub enum Signal {
+            Go,
+            Halt,
+        }
+
+        #[derive(Default)]
+        pub struct Light;
+
+        #[state_machine(initial = "State::red()", state(derive(Debug, PartialEq, Eq)))]
+        #[transitions(event = Signal, Red: Signal::Go => Green, Green: Signal::Halt => Red)]
+        impl Light {}
+
+        #[test]
+        fn states_are_generated_from_the_table() {
+            let mut state_machine = Light.uninitialized_state_machine().init();
+
+            state_machine.handle(&Signal::Go);
+            assert_eq!(state_machine.state(), &State::green());
+
+            state_machine.handle(&Signal::Go);
+            assert_eq!(state_machine.state(), &State::green());
+
+            state_machine.handle(&Signal::Halt);
+            assert_eq!(state_machine.state(), &State::red());
+        }
+    }
+}
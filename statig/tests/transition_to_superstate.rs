@@ -0,0 +1,147 @@
+#[cfg(test)]
+mod tests {
+    use statig::prelude::*;
+
+    #[derive(Default)]
+    struct Player {
+        log: Vec<&'static str>,
+    }
+
+    enum Event {
+        Next,
+        Reset,
+        Stop,
+        Hold,
+    }
+
+    #[state_machine(initial = "State::intro()", state(derive(Debug, PartialEq, Eq)))]
+    impl Player {
+        #[state(superstate = "playing", entry_action = "enter_intro")]
+        fn intro(event: &Event) -> Response<State> {
+            match event {
+                Event::Next => Transition(State::chorus()),
+                _ => Super,
+            }
+        }
+
+        #[state(superstate = "playing", exit_action = "exit_chorus")]
+        fn chorus(event: &Event) -> Response<State> {
+            match event {
+                Event::Reset => Response::transition_to_superstate(),
+                _ => Super,
+            }
+        }
+
+        #[superstate(initial = "State::intro()", exit_action = "exit_playing")]
+        fn playing(event: &Event) -> Response<State> {
+            match event {
+                Event::Reset => TransitionToSuperstate,
+                Event::Stop => Transition(State::stopped()),
+                _ => Super,
+            }
+        }
+
+        #[state]
+        fn stopped(event: &Event) -> Response<State> {
+            match event {
+                Event::Next => Transition(State::intro()),
+                Event::Hold => Transition(State::held()),
+                _ => TransitionToSuperstate,
+            }
+        }
+
+        #[state(superstate = "waiting", exit_action = "exit_held")]
+        fn held(event: &Event) -> Response<State> {
+            match event {
+                Event::Reset => TransitionToSuperstate,
+                _ => Super,
+            }
+        }
+
+        #[superstate]
+        fn waiting(event: &Event) -> Response<State> {
+            match event {
+                Event::Next => Transition(State::intro()),
+                _ => Super,
+            }
+        }
+
+        #[action]
+        fn enter_intro(&mut self) {
+            self.log.push("enter intro");
+        }
+
+        #[action]
+        fn exit_chorus(&mut self) {
+            self.log.push("exit chorus");
+        }
+
+        #[action]
+        fn exit_playing(&mut self) {
+            self.log.push("exit playing");
+        }
+
+        #[action]
+        fn exit_held(&mut self) {
+            self.log.push("exit held");
+        }
+    }
+
+    #[test]
+    fn transition_to_superstate() {
+        let mut state_machine = Player::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Next);
+
+        assert_eq!(state_machine.state(), &State::chorus());
+
+        // The superstate itself is not exited.
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::intro());
+        assert_eq!(
+            state_machine.log,
+            ["enter intro", "exit chorus", "enter intro"]
+        );
+
+        // Resolved from the current state when returned by the superstate handler.
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::intro());
+        assert_eq!(
+            state_machine.log,
+            ["enter intro", "exit chorus", "enter intro", "enter intro"]
+        );
+    }
+
+    #[test]
+    fn transition_to_superstate_without_superstate() {
+        let mut state_machine = Player::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Stop);
+
+        assert_eq!(state_machine.state(), &State::stopped());
+
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::stopped());
+        assert_eq!(state_machine.log, ["enter intro", "exit playing"]);
+    }
+
+    #[test]
+    fn transition_to_superstate_without_initial_state() {
+        let mut state_machine = Player::default().uninitialized_state_machine().init();
+
+        state_machine.handle(&Event::Stop);
+        state_machine.handle(&Event::Hold);
+
+        assert_eq!(state_machine.state(), &State::held());
+
+        // The superstate has no initial state, so the event is handled without a
+        // transition.
+        state_machine.handle(&Event::Reset);
+
+        assert_eq!(state_machine.state(), &State::held());
+        assert_eq!(state_machine.log, ["enter intro", "exit playing"]);
+    }
+}
@@ -0,0 +1,127 @@
+//! A blocking state machine that must build under `#![no_std]` without an
+//! allocator.
+
+#![no_std]
+
+use serde::{Deserialize, Serialize};
+use statig::prelude::*;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Blinky {
+    pub led: bool,
+    pub transitions: usize,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    TimerElapsed,
+    ButtonPressed,
+}
+
+/// External context that is passed along with every event.
+#[derive(Debug, Default)]
+pub struct Context {
+    pub presses: usize,
+}
+
+#[state_machine(
+    initial = "State::led_on()",
+    state(derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)),
+    superstate(derive(Debug)),
+    on_transition = "Self::on_transition"
+)]
+impl Blinky {
+    #[state(
+        superstate = "blinking",
+        entry_action = "enter_led_on",
+        exit_action = "exit_led_on"
+    )]
+    fn led_on(event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => Transition(State::led_off()),
+            _ => Super,
+        }
+    }
+
+    #[state(superstate = "blinking")]
+    fn led_off(event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => Transition(State::led_on()),
+            _ => Super,
+        }
+    }
+
+    #[superstate]
+    fn blinking(context: &mut Context, event: &Event) -> Response<State> {
+        match event {
+            Event::ButtonPressed => {
+                context.presses += 1;
+                Transition(State::not_blinking(0))
+            }
+            _ => Super,
+        }
+    }
+
+    #[state]
+    fn not_blinking(context: &mut Context, ticks: &mut usize, event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => {
+                *ticks += 1;
+                Handled
+            }
+            Event::ButtonPressed => {
+                context.presses += 1;
+                Transition(State::led_on())
+            }
+        }
+    }
+
+    #[action]
+    fn enter_led_on(&mut self) {
+        self.led = true;
+    }
+
+    #[action]
+    fn exit_led_on(&mut self) {
+        self.led = false;
+    }
+}
+
+impl Blinky {
+    fn on_transition(&mut self, _source: &State, _target: &State) {
+        self.transitions += 1;
+    }
+}
+
+/// Drive the state machine through all of its states and return it together
+/// with the context.
+pub fn run() -> (statig::blocking::InitializedStateMachine<Blinky>, Context) {
+    let mut context = Context::default();
+    let mut state_machine = Blinky::default()
+        .uninitialized_state_machine()
+        .init_with_context(&mut context);
+
+    let events = [
+        Event::TimerElapsed,
+        Event::ButtonPressed,
+        Event::TimerElapsed,
+        Event::TimerElapsed,
+    ];
+
+    for event in &events {
+        state_machine.handle_with_context(event, &mut context);
+    }
+
+    (state_machine, context)
+}
+
+/// Serialize only the state of the state machine.
+pub fn serialize_state<S>(
+    state_machine: &statig::blocking::InitializedStateMachine<Blinky>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    state_machine.serialize_state(serializer)
+}
@@ -0,0 +1,111 @@
+//! An async state machine that must build under `#![no_std]` with only the
+//! `alloc` crate available.
+
+#![no_std]
+
+use serde::{Deserialize, Serialize};
+use statig::prelude::*;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Blinky {
+    pub led: bool,
+    pub transitions: usize,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    TimerElapsed,
+    ButtonPressed,
+}
+
+#[state_machine(
+    initial = "State::led_on()",
+    state(derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)),
+    superstate(derive(Debug)),
+    on_transition = "Self::on_transition"
+)]
+impl Blinky {
+    #[state(
+        superstate = "blinking",
+        entry_action = "enter_led_on",
+        exit_action = "exit_led_on"
+    )]
+    async fn led_on(event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => Transition(State::led_off()),
+            _ => Super,
+        }
+    }
+
+    #[state(superstate = "blinking")]
+    fn led_off(event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => Transition(State::led_on()),
+            _ => Super,
+        }
+    }
+
+    #[superstate]
+    async fn blinking(event: &Event) -> Response<State> {
+        match event {
+            Event::ButtonPressed => Transition(State::not_blinking(0)),
+            _ => Super,
+        }
+    }
+
+    #[state]
+    async fn not_blinking(ticks: &mut usize, event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => {
+                *ticks += 1;
+                Handled
+            }
+            Event::ButtonPressed => Transition(State::led_on()),
+        }
+    }
+
+    #[action]
+    async fn enter_led_on(&mut self) {
+        self.led = true;
+    }
+
+    #[action]
+    fn exit_led_on(&mut self) {
+        self.led = false;
+    }
+}
+
+impl Blinky {
+    fn on_transition(&mut self, _source: &State, _target: &State) {
+        self.transitions += 1;
+    }
+}
+
+/// Drive the state machine through all of its states and return it.
+pub async fn run() -> statig::awaitable::InitializedStateMachine<Blinky> {
+    let mut state_machine = Blinky::default().uninitialized_state_machine().init().await;
+
+    let events = [
+        Event::TimerElapsed,
+        Event::ButtonPressed,
+        Event::TimerElapsed,
+        Event::TimerElapsed,
+    ];
+
+    for event in &events {
+        state_machine.handle(event).await;
+    }
+
+    state_machine
+}
+
+/// Serialize only the state of the state machine.
+pub fn serialize_state<S>(
+    state_machine: &statig::awaitable::InitializedStateMachine<Blinky>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    state_machine.serialize_state(serializer)
+}
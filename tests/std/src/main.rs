@@ -0,0 +1,184 @@
+//! Builds a blocking and an async state machine on `std` and runs them
+//! together with the machines from the `no_std` crates.
+
+use futures::executor;
+use serde::{Deserialize, Serialize};
+use statig::prelude::*;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Blinky {
+    led: bool,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    TimerElapsed,
+    ButtonPressed,
+}
+
+#[state_machine(
+    initial = "State::led_on()",
+    state(derive(Debug, Clone, PartialEq, Serialize, Deserialize)),
+    superstate(derive(Debug))
+)]
+impl Blinky {
+    #[state(
+        superstate = "blinking",
+        entry_action = "enter_led_on",
+        exit_action = "exit_led_on"
+    )]
+    fn led_on(event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => Transition(State::led_off()),
+            _ => Super,
+        }
+    }
+
+    #[state(superstate = "blinking")]
+    fn led_off(event: &Event) -> Response<State> {
+        match event {
+            Event::TimerElapsed => Transition(State::led_on()),
+            _ => Super,
+        }
+    }
+
+    #[superstate]
+    fn blinking(event: &Event) -> Response<State> {
+        match event {
+            Event::ButtonPressed => Transition(State::not_blinking()),
+            _ => Super,
+        }
+    }
+
+    #[state]
+    fn not_blinking(event: &Event) -> Response<State> {
+        match event {
+            Event::ButtonPressed => Transition(State::led_on()),
+            _ => Super,
+        }
+    }
+
+    #[action]
+    fn enter_led_on(&mut self) {
+        self.led = true;
+    }
+
+    #[action]
+    fn exit_led_on(&mut self) {
+        self.led = false;
+    }
+}
+
+mod awaitable {
+    use statig::prelude::*;
+
+    #[derive(Debug, Default)]
+    pub struct Counter {
+        pub count: usize,
+    }
+
+    pub enum Event {
+        Increment,
+        Reset,
+    }
+
+    #[state_machine(initial = "State::counting()", state(derive(Debug, PartialEq)))]
+    impl Counter {
+        #[state]
+        async fn counting(&mut self, event: &Event) -> Response<State> {
+            match event {
+                Event::Increment => {
+                    self.count += 1;
+                    Handled
+                }
+                Event::Reset => Transition(State::counting()),
+            }
+        }
+    }
+}
+
+fn run_blocking() {
+    let mut state_machine = Blinky::default().uninitialized_state_machine().init();
+
+    state_machine.handle(&Event::TimerElapsed);
+    assert_eq!(state_machine.state(), &State::led_off());
+    assert!(!state_machine.led);
+
+    state_machine.handle(&Event::ButtonPressed);
+    assert_eq!(state_machine.state(), &State::not_blinking());
+
+    let serialized = serde_json::to_string(&state_machine).unwrap();
+    let deserialized: statig::blocking::UninitializedStateMachine<Blinky> =
+        serde_json::from_str(&serialized).unwrap();
+    let deserialized = deserialized.init();
+
+    assert_eq!(deserialized.state(), state_machine.state());
+}
+
+fn run_awaitable() {
+    executor::block_on(async {
+        let mut state_machine = awaitable::Counter::default()
+            .uninitialized_state_machine()
+            .init()
+            .await;
+
+        state_machine.handle(&awaitable::Event::Increment).await;
+        state_machine.handle(&awaitable::Event::Increment).await;
+        assert_eq!(state_machine.count, 2);
+
+        state_machine.handle(&awaitable::Event::Reset).await;
+        assert_eq!(state_machine.state(), &awaitable::State::Counting {});
+    });
+}
+
+fn run_no_std() {
+    let (state_machine, context) = compile_no_std::run();
+
+    assert_eq!(
+        state_machine.state(),
+        &compile_no_std::State::NotBlinking { ticks: 2 }
+    );
+    assert_eq!(context.presses, 1);
+    assert_eq!(state_machine.transitions, 2);
+    assert!(!state_machine.led);
+
+    let mut serialized = Vec::new();
+    compile_no_std::serialize_state(
+        &state_machine,
+        &mut serde_json::Serializer::new(&mut serialized),
+    )
+    .unwrap();
+    let state: compile_no_std::State = serde_json::from_slice(&serialized).unwrap();
+
+    assert_eq!(&state, state_machine.state());
+}
+
+fn run_no_std_alloc() {
+    let state_machine = executor::block_on(compile_no_std_alloc::run());
+
+    assert_eq!(
+        state_machine.state(),
+        &compile_no_std_alloc::State::NotBlinking { ticks: 2 }
+    );
+    assert_eq!(state_machine.transitions, 2);
+    assert!(!state_machine.led);
+
+    let mut serialized = Vec::new();
+    compile_no_std_alloc::serialize_state(
+        &state_machine,
+        &mut serde_json::Serializer::new(&mut serialized),
+    )
+    .unwrap();
+    let state: compile_no_std_alloc::State = serde_json::from_slice(&serialized).unwrap();
+
+    assert_eq!(&state, state_machine.state());
+}
+
+fn main() {
+    run_blocking();
+    run_awaitable();
+    run_no_std();
+    run_no_std_alloc();
+
+    println!("all state machines ran to completion");
+}